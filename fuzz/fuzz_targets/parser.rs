@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// The parsers have to report errors instead of panicking on any input.
+fuzz_target!(|input: &str| {
+    let _ = powdr::parser::parse(None, input);
+    let _ = powdr::parser::parse_asm(None, input);
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use arbitrary::Unstructured;
+use libfuzzer_sys::fuzz_target;
+use powdr::fuzzing::{check_solver_invariants, constraint_system};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = constraint_system(&mut Unstructured::new(data)) else {
+        return;
+    };
+    if let Err(e) = check_solver_invariants(&source) {
+        panic!("{e}\n{source}");
+    }
+});
@@ -47,6 +47,9 @@ impl Display for Expression {
             Expression::UnaryOperation(op, exp) => write!(f, "{op}{exp}"),
             Expression::FunctionCall(fun, args) => write!(f, "{fun}({})", format_expressions(args)),
             Expression::LocalVariableReference(index) => write!(f, "${index}"),
+            Expression::IfElse(condition, if_true, if_false) => {
+                write!(f, "if {condition} {{ {if_true} }} else {{ {if_false} }}")
+            }
         }
     }
 }
@@ -0,0 +1,70 @@
+use crate::number::{abstract_to_degree, is_zero, AbstractNumberType};
+
+use super::{BinaryOperator, UnaryOperator};
+
+/// Evaluates a binary operation on two numbers.
+/// This is shared between the evaluation of constants during analysis
+/// and the evaluation of fixed columns, so that both agree on the semantics.
+/// Comparisons and logical operators return one (true) or zero (false),
+/// every nonzero number is considered true.
+/// Division is integer division and zero divided by anything is zero.
+/// Division by zero results in zero and the remainder of a division
+/// by zero is the dividend, so that `a == (a / b) * b + a % b` still holds.
+pub fn evaluate_binary_operation(
+    left: AbstractNumberType,
+    op: &BinaryOperator,
+    right: AbstractNumberType,
+) -> AbstractNumberType {
+    match op {
+        BinaryOperator::Add => left + right,
+        BinaryOperator::Sub => left - right,
+        BinaryOperator::Mul => left * right,
+        BinaryOperator::Div => {
+            if is_zero(&left) || is_zero(&right) {
+                0.into()
+            } else {
+                left / right
+            }
+        }
+        BinaryOperator::Pow => {
+            assert!(AbstractNumberType::from(0) <= right && right <= u32::MAX.into());
+            left.pow(abstract_to_degree(&right) as u32)
+        }
+        BinaryOperator::Mod => {
+            if is_zero(&right) {
+                left
+            } else {
+                left % right
+            }
+        }
+        BinaryOperator::BinaryAnd => left & right,
+        BinaryOperator::BinaryXor => left ^ right,
+        BinaryOperator::BinaryOr => left | right,
+        BinaryOperator::ShiftLeft => left << abstract_to_degree(&right),
+        BinaryOperator::ShiftRight => left >> abstract_to_degree(&right),
+        BinaryOperator::LogicalOr => from_bool(!is_zero(&left) || !is_zero(&right)),
+        BinaryOperator::LogicalAnd => from_bool(!is_zero(&left) && !is_zero(&right)),
+        BinaryOperator::Less => from_bool(left < right),
+        BinaryOperator::LessEqual => from_bool(left <= right),
+        BinaryOperator::Equal => from_bool(left == right),
+        BinaryOperator::NotEqual => from_bool(left != right),
+        BinaryOperator::GreaterEqual => from_bool(left >= right),
+        BinaryOperator::Greater => from_bool(left > right),
+    }
+}
+
+/// Evaluates a unary operation on a number.
+pub fn evaluate_unary_operation(
+    op: &UnaryOperator,
+    value: AbstractNumberType,
+) -> AbstractNumberType {
+    match op {
+        UnaryOperator::Plus => value,
+        UnaryOperator::Minus => -value,
+        UnaryOperator::LogicalNot => from_bool(is_zero(&value)),
+    }
+}
+
+fn from_bool(value: bool) -> AbstractNumberType {
+    u32::from(value).into()
+}
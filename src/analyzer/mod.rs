@@ -1,17 +1,48 @@
 pub mod display;
+pub mod evaluator;
 pub mod pil_analyzer;
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::number::{AbstractNumberType, DegreeType};
 pub use crate::parser::ast::{BinaryOperator, UnaryOperator};
 
-pub fn analyze(path: &Path) -> Analyzed {
-    pil_analyzer::process_pil_file(path)
+/// Analyzes a .pil file. Included files which are not found relative to
+/// the including file are searched for in the include paths, in order.
+/// @returns the analyzed file or the first error found.
+pub fn analyze(path: &Path, include_paths: &[PathBuf]) -> Result<Analyzed, String> {
+    pil_analyzer::process_pil_file(path, include_paths)
 }
 
-pub fn analyze_string(contents: &str) -> Analyzed {
+/// @returns the analyzed file and all files it consists of, including includes.
+pub fn analyze_with_sources(
+    path: &Path,
+    include_paths: &[PathBuf],
+) -> Result<(Analyzed, Vec<PathBuf>), String> {
+    pil_analyzer::process_pil_file_with_sources(path, include_paths)
+}
+
+/// Analyzes the contents of a file that might differ from what is stored on disk.
+/// @returns the analyzed file and all files it consists of, including includes.
+pub fn analyze_string_with_sources(
+    path: &Path,
+    include_paths: &[PathBuf],
+    contents: &str,
+) -> Result<(Analyzed, Vec<PathBuf>), String> {
+    pil_analyzer::process_pil_file_contents_with_sources(path, include_paths, contents)
+}
+
+/// Analyzes an expression in the context of a namespace of an analyzed file.
+pub fn analyze_expression(
+    analyzed: &Analyzed,
+    namespace: &str,
+    input: &str,
+) -> Result<Expression, String> {
+    pil_analyzer::process_expression(analyzed, namespace, input)
+}
+
+pub fn analyze_string(contents: &str) -> Result<Analyzed, String> {
     pil_analyzer::process_pil_file_contents(contents)
 }
 
@@ -58,6 +89,12 @@ impl Analyzed {
         self.definitions_in_source_order(PolynomialType::Committed)
     }
 
+    pub fn public_declarations_in_id_order(&self) -> Vec<&PublicDeclaration> {
+        let mut publics = self.public_declarations.values().collect::<Vec<_>>();
+        publics.sort_by_key(|public| public.id);
+        publics
+    }
+
     pub fn definitions_in_source_order(
         &self,
         poly_type: PolynomialType,
@@ -107,8 +144,12 @@ impl Polynomial {
 
 pub enum FunctionValueDefinition {
     Mapping(Expression),
+    /// Array of values, fixed columns loaded from a file are also stored as arrays.
     Array(Vec<Expression>),
     Query(Expression),
+    /// Mapping that can call the column itself on earlier rows,
+    /// the first rows are given explicitly.
+    Recurrence(Expression, Vec<Expression>),
 }
 
 pub struct PublicDeclaration {
@@ -159,6 +200,8 @@ pub enum Expression {
     UnaryOperation(UnaryOperator, Box<Expression>),
     /// Call to a non-macro function (like a constant polynomial)
     FunctionCall(String, Vec<Expression>),
+    /// Condition, value if the condition is nonzero, value otherwise.
+    IfElse(Box<Expression>, Box<Expression>, Box<Expression>),
 }
 
 #[derive(Debug, PartialEq, Eq, Default, Clone)]
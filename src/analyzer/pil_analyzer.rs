@@ -2,23 +2,68 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::number::{abstract_to_degree, DegreeType};
+use crate::number::{from_le_bytes, is_zero, parse_number, to_u64, DegreeType, GOLDILOCKS_MOD};
 use crate::parser::ast;
 pub use crate::parser::ast::{BinaryOperator, UnaryOperator};
 use crate::{parser, utils};
 
 use super::*;
 
-pub fn process_pil_file(path: &Path) -> Analyzed {
-    let mut ctx = PILContext::new();
-    ctx.process_file(path);
-    ctx.into()
+pub fn process_pil_file(path: &Path, include_paths: &[PathBuf]) -> Result<Analyzed, String> {
+    Ok(process_pil_file_with_sources(path, include_paths)?.0)
 }
 
-pub fn process_pil_file_contents(contents: &str) -> Analyzed {
-    let mut ctx = PILContext::new();
-    ctx.process_file_contents(Path::new("input"), contents);
-    ctx.into()
+/// @returns the analyzed file and all files it includes (and itself), sorted.
+pub fn process_pil_file_with_sources(
+    path: &Path,
+    include_paths: &[PathBuf],
+) -> Result<(Analyzed, Vec<PathBuf>), String> {
+    let mut ctx = PILContext::new(include_paths);
+    ctx.process_file(path)?;
+    Ok(ctx.into_analyzed_with_sources())
+}
+
+/// Analyzes the contents as if they were stored in the file at the given path,
+/// which is used to resolve includes.
+/// @returns the analyzed file and all files it includes (and itself), sorted.
+pub fn process_pil_file_contents_with_sources(
+    path: &Path,
+    include_paths: &[PathBuf],
+    contents: &str,
+) -> Result<(Analyzed, Vec<PathBuf>), String> {
+    let mut ctx = PILContext::new(include_paths);
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    ctx.included_files.insert(path.clone());
+    ctx.process_file_contents(&path, contents)?;
+    Ok(ctx.into_analyzed_with_sources())
+}
+
+pub fn process_pil_file_contents(contents: &str) -> Result<Analyzed, String> {
+    let mut ctx = PILContext::new(&[]);
+    ctx.process_file_contents(Path::new("input"), contents)?;
+    Ok(ctx.into())
+}
+
+/// Analyzes a single expression as if it occurred in the given namespace
+/// of the analyzed file. Constants can be used, macros are not available.
+pub fn process_expression(
+    analyzed: &Analyzed,
+    namespace: &str,
+    input: &str,
+) -> Result<Expression, String> {
+    let source = format!("pol expression = {input};");
+    let pil_file = parser::parse(None, &source).map_err(|err| err.message().to_string())?;
+    match &pil_file.0[..] {
+        [ast::Statement::PolynomialDefinition(_, _, expr)] => {
+            let mut ctx = PILContext {
+                namespace: namespace.to_string(),
+                constants: analyzed.constants.clone(),
+                ..Default::default()
+            };
+            ctx.process_expression(expr)
+        }
+        _ => Err("Expected a single expression.".to_string()),
+    }
 }
 
 #[derive(Default)]
@@ -35,6 +80,9 @@ struct PILContext {
     /// appear in the source.
     source_order: Vec<StatementIdentifier>,
     included_files: HashSet<PathBuf>,
+    /// Directories searched for included files that are not found
+    /// relative to the including file.
+    include_paths: Vec<PathBuf>,
     line_starts: Vec<usize>,
     current_file: PathBuf,
     commit_poly_counter: u64,
@@ -77,43 +125,47 @@ impl From<PILContext> for Analyzed {
 }
 
 impl PILContext {
-    pub fn new() -> PILContext {
+    pub fn new(include_paths: &[PathBuf]) -> PILContext {
         PILContext {
             namespace: "Global".to_string(),
+            include_paths: include_paths.to_vec(),
             ..Default::default()
         }
     }
 
-    pub fn process_file(&mut self, path: &Path) {
+    fn into_analyzed_with_sources(self) -> (Analyzed, Vec<PathBuf>) {
+        let mut files = self.included_files.iter().cloned().collect::<Vec<_>>();
+        files.sort();
+        (self.into(), files)
+    }
+
+    pub fn process_file(&mut self, path: &Path) -> Result<(), String> {
         let path = path
             .canonicalize()
-            .unwrap_or_else(|e| panic!("File {path:?} not found: {e}"));
+            .map_err(|e| format!("File {path:?} not found: {e}"))?;
         if !self.included_files.insert(path.clone()) {
-            return;
+            return Ok(());
         }
-        let contents = fs::read_to_string(path.clone()).unwrap();
-        self.process_file_contents(&path, &contents);
+        let contents =
+            fs::read_to_string(&path).map_err(|e| format!("Could not read {path:?}: {e}"))?;
+        self.process_file_contents(&path, &contents)
     }
 
-    pub fn process_file_contents(&mut self, path: &Path, contents: &str) {
+    pub fn process_file_contents(&mut self, path: &Path, contents: &str) -> Result<(), String> {
         let old_current_file = std::mem::take(&mut self.current_file);
         let old_line_starts = std::mem::take(&mut self.line_starts);
 
         // TOOD make this work for other line endings
         self.line_starts = utils::compute_line_starts(contents);
         self.current_file = path.to_path_buf();
-        let pil_file =
-            parser::parse(Some(path.to_str().unwrap()), contents).unwrap_or_else(|err| {
-                eprintln!("Error parsing .pil file:");
-                err.output_to_stderr();
-                panic!();
-            });
+        let pil_file = parser::parse(Some(&path.to_string_lossy()), contents)
+            .map_err(|err| err.to_string())?;
 
         for statement in &pil_file.0 {
             use ast::Statement;
             match statement {
-                Statement::Include(_, include) => self.handle_include(include),
-                Statement::Namespace(_, name, degree) => self.handle_namespace(name, degree),
+                Statement::Include(_, include) => self.handle_include(include)?,
+                Statement::Namespace(_, name, degree) => self.handle_namespace(name, degree)?,
                 Statement::PolynomialDefinition(start, name, value) => {
                     self.handle_polynomial_definition(
                         self.to_source_ref(*start),
@@ -121,16 +173,21 @@ impl PILContext {
                         &None,
                         PolynomialType::Intermediate,
                         Some(&ast::FunctionDefinition::Mapping(vec![], value.clone())),
-                    );
+                    )?;
                 }
                 Statement::PublicDeclaration(start, name, polynomial, index) => self
-                    .handle_public_declaration(self.to_source_ref(*start), name, polynomial, index),
+                    .handle_public_declaration(
+                        self.to_source_ref(*start),
+                        name,
+                        polynomial,
+                        index,
+                    )?,
                 Statement::PolynomialConstantDeclaration(start, polynomials) => self
                     .handle_polynomial_declarations(
                         self.to_source_ref(*start),
                         polynomials,
                         PolynomialType::Constant,
-                    ),
+                    )?,
                 Statement::PolynomialConstantDefinition(start, name, definition) => {
                     self.handle_polynomial_definition(
                         self.to_source_ref(*start),
@@ -138,14 +195,14 @@ impl PILContext {
                         &None,
                         PolynomialType::Constant,
                         Some(definition),
-                    );
+                    )?;
                 }
                 Statement::PolynomialCommitDeclaration(start, polynomials, None) => self
                     .handle_polynomial_declarations(
                         self.to_source_ref(*start),
                         polynomials,
                         PolynomialType::Committed,
-                    ),
+                    )?,
                 Statement::PolynomialCommitDeclaration(start, polynomials, Some(definition)) => {
                     assert!(polynomials.len() == 1);
                     let name = polynomials.first().unwrap();
@@ -155,10 +212,10 @@ impl PILContext {
                         &name.array_size,
                         PolynomialType::Committed,
                         Some(definition),
-                    );
+                    )?;
                 }
                 Statement::ConstantDefinition(_, name, value) => {
-                    self.handle_constant_definition(name, value)
+                    self.handle_constant_definition(name, value)?
                 }
                 Statement::MacroDefinition(start, name, params, statments, expression) => self
                     .handle_macro_definition(
@@ -167,38 +224,41 @@ impl PILContext {
                         params,
                         statments,
                         expression,
-                    ),
+                    )?,
                 _ => {
-                    self.handle_identity_statement(statement);
+                    self.handle_identity_statement(statement)?;
                 }
             }
         }
 
         self.current_file = old_current_file;
         self.line_starts = old_line_starts;
+        Ok(())
     }
 
     fn to_source_ref(&self, start: usize) -> SourceRef {
-        let file = self.current_file.file_name().unwrap().to_str().unwrap();
+        let file = self.current_file.file_name().unwrap().to_string_lossy();
         SourceRef {
             line: utils::offset_to_line(start, &self.line_starts),
             file: file.to_string(),
         }
     }
 
-    fn handle_identity_statement(&mut self, statement: &ast::Statement) {
+    fn handle_identity_statement(&mut self, statement: &ast::Statement) -> Result<(), String> {
         if let ast::Statement::FunctionCall(_start, name, arguments) = statement {
             if !self.macros.contains_key(name) {
-                panic!(
+                return Err(format!(
                     "Macro {name} not found - only macros allowed at this point, no fixed columns."
-                );
+                ));
             }
             // TODO check that it does not contain local variable references.
             // But we also need to do some other well-formedness checks.
-            if self.process_macro_call(name, arguments).is_some() {
-                panic!("Invoked a macro in statement context with non-empty expression.");
+            if self.process_macro_call(name, arguments)?.is_some() {
+                return Err(
+                    "Invoked a macro in statement context with non-empty expression.".to_string(),
+                );
             }
-            return;
+            return Ok(());
         }
 
         let (start, kind, left, right) = match statement {
@@ -206,7 +266,7 @@ impl PILContext {
                 start,
                 IdentityKind::Polynomial,
                 SelectedExpressions {
-                    selector: Some(self.process_expression(expression)),
+                    selector: Some(self.process_expression(expression)?),
                     expressions: vec![],
                 },
                 SelectedExpressions::default(),
@@ -214,31 +274,29 @@ impl PILContext {
             ast::Statement::PlookupIdentity(start, key, haystack) => (
                 start,
                 IdentityKind::Plookup,
-                self.process_selected_expression(key),
-                self.process_selected_expression(haystack),
+                self.process_selected_expression(key)?,
+                self.process_selected_expression(haystack)?,
             ),
             ast::Statement::PermutationIdentity(start, left, right) => (
                 start,
                 IdentityKind::Permutation,
-                self.process_selected_expression(left),
-                self.process_selected_expression(right),
+                self.process_selected_expression(left)?,
+                self.process_selected_expression(right)?,
             ),
             ast::Statement::ConnectIdentity(start, left, right) => (
                 start,
                 IdentityKind::Connect,
                 SelectedExpressions {
                     selector: None,
-                    expressions: self.process_expressions(left),
+                    expressions: self.process_expressions(left)?,
                 },
                 SelectedExpressions {
                     selector: None,
-                    expressions: self.process_expressions(right),
+                    expressions: self.process_expressions(right)?,
                 },
             ),
             // TODO at some point, these should all be caught by the type checker.
-            _ => {
-                panic!("Only identities allowed at this point.")
-            }
+            _ => return Err("Only identities allowed at this point.".to_string()),
         };
         let id = self.dispense_id(kind);
         let identity = Identity {
@@ -251,17 +309,34 @@ impl PILContext {
         let id = self.identities.len();
         self.identities.push(identity);
         self.source_order.push(StatementIdentifier::Identity(id));
+        Ok(())
+    }
+
+    fn handle_include(&mut self, path: &str) -> Result<(), String> {
+        let file = self.resolve_path(path);
+        self.process_file(&file)
     }
 
-    fn handle_include(&mut self, path: &str) {
-        let mut dir = self.current_file.parent().unwrap().to_owned();
-        dir.push(path);
-        self.process_file(&dir);
+    /// Resolves a path relative to the current file or, if it does not
+    /// exist there, relative to the first include path it exists in.
+    fn resolve_path(&self, path: &str) -> PathBuf {
+        let relative = self.current_file.parent().unwrap().join(path);
+        if relative.exists() {
+            relative
+        } else {
+            self.include_paths
+                .iter()
+                .map(|dir| dir.join(path))
+                .find(|file| file.exists())
+                .unwrap_or(relative)
+        }
     }
 
-    fn handle_namespace(&mut self, name: &str, degree: &ast::Expression) {
-        self.polynomial_degree = abstract_to_degree(&self.evaluate_expression(degree).unwrap());
+    fn handle_namespace(&mut self, name: &str, degree: &ast::Expression) -> Result<(), String> {
+        self.polynomial_degree =
+            self.evaluate_to_degree(degree, &format!("Degree of namespace {name}"))?;
         self.namespace = name.to_owned();
+        Ok(())
     }
 
     fn handle_polynomial_declarations(
@@ -269,7 +344,7 @@ impl PILContext {
         source: SourceRef,
         polynomials: &[ast::PolynomialName],
         polynomial_type: PolynomialType,
-    ) {
+    ) -> Result<(), String> {
         for ast::PolynomialName { name, array_size } in polynomials {
             self.handle_polynomial_definition(
                 source.clone(),
@@ -277,8 +352,9 @@ impl PILContext {
                 array_size,
                 polynomial_type,
                 None,
-            );
+            )?;
         }
+        Ok(())
     }
 
     fn handle_polynomial_definition(
@@ -288,13 +364,13 @@ impl PILContext {
         array_size: &Option<ast::Expression>,
         polynomial_type: PolynomialType,
         value: Option<&ast::FunctionDefinition>,
-    ) -> u64 {
+    ) -> Result<u64, String> {
         let length = array_size
             .as_ref()
-            .map(|l| self.evaluate_expression(l).unwrap())
-            .map(|l| abstract_to_degree(&l));
-        if length.is_some() {
-            assert!(value.is_none());
+            .map(|l| self.evaluate_to_degree(l, &format!("Length of array {name}")))
+            .transpose()?;
+        if length.is_some() && value.is_some() {
+            return Err(format!("Polynomial array {name} cannot have a value."));
         }
         let counter = match polynomial_type {
             PolynomialType::Committed => &mut self.commit_poly_counter,
@@ -312,47 +388,125 @@ impl PILContext {
             length,
         };
         let name = poly.absolute_name.clone();
-        let value = value.map(|v| match v {
-            ast::FunctionDefinition::Mapping(params, expr)
-            | ast::FunctionDefinition::Query(params, expr) => {
-                assert!(array_size.is_none());
-                if !params.is_empty() {
-                    assert!(
-                        polynomial_type == PolynomialType::Constant
-                            || polynomial_type == PolynomialType::Committed
-                    );
-                }
+        if self.definitions.contains_key(&name) {
+            return Err(format!("Polynomial {name} was declared twice."));
+        }
+        let value = value
+            .map(|v| match v {
+                ast::FunctionDefinition::Mapping(params, expr)
+                | ast::FunctionDefinition::Query(params, expr)
+                | ast::FunctionDefinition::Recurrence(params, expr, _) => {
+                    if !params.is_empty() {
+                        assert!(
+                            polynomial_type == PolynomialType::Constant
+                                || polynomial_type == PolynomialType::Committed
+                        );
+                    }
+                    if matches!(v, ast::FunctionDefinition::Recurrence(..))
+                        && polynomial_type != PolynomialType::Constant
+                    {
+                        return Err(
+                            "Only fixed columns can be defined by a recurrence.".to_string()
+                        );
+                    }
 
-                assert!(self.local_variables.is_empty());
-                self.local_variables = params
-                    .iter()
-                    .enumerate()
-                    .map(|(i, p)| (p.clone(), i as u64))
-                    .collect();
-                let processed_value = self.process_expression(expr);
-                self.local_variables.clear();
-                match v {
-                    ast::FunctionDefinition::Mapping(_, _) => {
-                        FunctionValueDefinition::Mapping(processed_value)
+                    assert!(self.local_variables.is_empty());
+                    self.local_variables = params
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| (p.clone(), i as u64))
+                        .collect();
+                    let processed_value = self.process_expression(expr)?;
+                    self.local_variables.clear();
+                    Ok(match v {
+                        ast::FunctionDefinition::Mapping(_, _) => {
+                            FunctionValueDefinition::Mapping(processed_value)
+                        }
+                        ast::FunctionDefinition::Query(_, _) => {
+                            FunctionValueDefinition::Query(processed_value)
+                        }
+                        ast::FunctionDefinition::Recurrence(_, _, initial) => {
+                            FunctionValueDefinition::Recurrence(
+                                processed_value,
+                                self.process_expressions(initial)?,
+                            )
+                        }
+                        _ => panic!(),
+                    })
+                }
+                ast::FunctionDefinition::Array(value) => Ok(FunctionValueDefinition::Array(
+                    self.process_expressions(value)?,
+                )),
+                ast::FunctionDefinition::File(path) => {
+                    if polynomial_type != PolynomialType::Constant {
+                        return Err("Only fixed columns can be loaded from a file.".to_string());
                     }
-                    ast::FunctionDefinition::Query(_, _) => {
-                        FunctionValueDefinition::Query(processed_value)
+                    Ok(FunctionValueDefinition::Array(
+                        self.load_fixed_column(&name, path)?
+                            .into_iter()
+                            .map(Expression::Number)
+                            .collect(),
+                    ))
+                }
+                ast::FunctionDefinition::Random(seed) => {
+                    if polynomial_type != PolynomialType::Constant {
+                        return Err(
+                            "Only fixed columns can be filled with random values.".to_string()
+                        );
                     }
-                    _ => panic!(),
+                    let seed =
+                        self.evaluate_to_degree(seed, &format!("Seed of fixed column {name}"))?;
+                    Ok(FunctionValueDefinition::Array(
+                        random_values(seed, self.polynomial_degree)
+                            .map(Expression::Number)
+                            .collect(),
+                    ))
                 }
-            }
-            ast::FunctionDefinition::Array(value) => {
-                FunctionValueDefinition::Array(self.process_expressions(value))
-            }
-        });
-        let is_new = self
-            .definitions
-            .insert(name.clone(), (poly, value))
-            .is_none();
-        assert!(is_new);
+            })
+            .transpose()?;
+        self.definitions.insert(name.clone(), (poly, value));
         self.source_order
             .push(StatementIdentifier::Definition(name));
-        id
+        Ok(id)
+    }
+
+    /// Loads the values of a fixed column from a file, which is resolved
+    /// like includes, see `resolve_path`.
+    /// Files ending in `.csv` contain (possibly negative) numbers separated by commas or newlines,
+    /// all other files contain 8-byte little-endian numbers.
+    fn load_fixed_column(&self, name: &str, path: &str) -> Result<Vec<AbstractNumberType>, String> {
+        let file = self.resolve_path(path);
+        let values: Vec<AbstractNumberType> = if file.extension() == Some("csv".as_ref()) {
+            fs::read_to_string(&file)
+                .map_err(|e| format!("Could not read {file:?}: {e}"))?
+                .split([',', '\n'])
+                .map(|v| v.trim())
+                .filter(|v| !v.is_empty())
+                .map(|v| parse_number(v).ok_or_else(|| format!("Invalid number in {file:?}: {v}")))
+                .collect::<Result<_, _>>()?
+        } else {
+            let contents = fs::read(&file).map_err(|e| format!("Could not read {file:?}: {e}"))?;
+            if !contents.len().is_multiple_of(8) {
+                return Err(format!("Size of {file:?} is not a multiple of 8 bytes."));
+            }
+            contents.chunks(8).map(from_le_bytes).collect()
+        };
+        if values.len() as DegreeType != self.polynomial_degree {
+            return Err(format!(
+                "Fixed column {name} loaded from {file:?} has {} values, but the degree is {}.",
+                values.len(),
+                self.polynomial_degree
+            ));
+        }
+        if let Some(v) = values
+            .iter()
+            .find(|v| *v.magnitude() >= GOLDILOCKS_MOD.into())
+        {
+            return Err(format!(
+                "Value {v} of fixed column {name} loaded from {file:?} is not a field element."
+            ));
+        }
+        Ok(values)
     }
 
     fn handle_public_declaration(
@@ -361,7 +515,7 @@ impl PILContext {
         name: &str,
         poly: &ast::PolynomialReference,
         index: &ast::Expression,
-    ) {
+    ) -> Result<(), String> {
         let id = self.public_declarations.len() as u64;
         self.public_declarations.insert(
             name.to_string(),
@@ -369,21 +523,29 @@ impl PILContext {
                 id,
                 source,
                 name: name.to_string(),
-                polynomial: self.process_polynomial_reference(poly),
-                index: abstract_to_degree(&self.evaluate_expression(index).unwrap()),
+                polynomial: self.process_polynomial_reference(poly)?,
+                index: self.evaluate_to_degree(index, &format!("Row of public value {name}"))?,
             },
         );
         self.source_order
             .push(StatementIdentifier::PublicDeclaration(name.to_string()));
+        Ok(())
     }
 
-    fn handle_constant_definition(&mut self, name: &str, value: &ast::Expression) {
+    fn handle_constant_definition(
+        &mut self,
+        name: &str,
+        value: &ast::Expression,
+    ) -> Result<(), String> {
         // TODO does the order matter here?
-        let is_new = self
-            .constants
-            .insert(name.to_string(), self.evaluate_expression(value).unwrap())
-            .is_none();
-        assert!(is_new, "Constant {name} was defined twice.");
+        let value = self
+            .evaluate_expression(value)?
+            .ok_or_else(|| format!("Value of constant {name} is not constant."))?;
+        let is_new = self.constants.insert(name.to_string(), value).is_none();
+        if !is_new {
+            return Err(format!("Constant {name} was defined twice."));
+        }
+        Ok(())
     }
 
     fn dispense_id(&mut self, kind: IdentityKind) -> u64 {
@@ -400,7 +562,7 @@ impl PILContext {
         params: &[String],
         statements: &[ast::Statement],
         expression: &Option<ast::Expression>,
-    ) {
+    ) -> Result<(), String> {
         let is_new = self
             .macros
             .insert(
@@ -414,7 +576,10 @@ impl PILContext {
                 },
             )
             .is_none();
-        assert!(is_new);
+        if !is_new {
+            return Err(format!("Macro {name} was defined twice."));
+        }
+        Ok(())
     }
 
     fn namespaced(&self, name: &str) -> String {
@@ -428,75 +593,104 @@ impl PILContext {
     fn process_selected_expression(
         &mut self,
         expr: &ast::SelectedExpressions,
-    ) -> SelectedExpressions {
-        SelectedExpressions {
-            selector: expr.selector.as_ref().map(|e| self.process_expression(e)),
-            expressions: self.process_expressions(&expr.expressions),
-        }
+    ) -> Result<SelectedExpressions, String> {
+        Ok(SelectedExpressions {
+            selector: expr
+                .selector
+                .as_ref()
+                .map(|e| self.process_expression(e))
+                .transpose()?,
+            expressions: self.process_expressions(&expr.expressions)?,
+        })
     }
 
-    fn process_expressions(&mut self, exprs: &[ast::Expression]) -> Vec<Expression> {
+    fn process_expressions(
+        &mut self,
+        exprs: &[ast::Expression],
+    ) -> Result<Vec<Expression>, String> {
         exprs.iter().map(|e| self.process_expression(e)).collect()
     }
 
-    fn process_expression(&mut self, expr: &ast::Expression) -> Expression {
-        match expr {
+    fn process_expression(&mut self, expr: &ast::Expression) -> Result<Expression, String> {
+        Ok(match expr {
             ast::Expression::Constant(name) => Expression::Constant(name.clone()),
             ast::Expression::PolynomialReference(poly) => {
                 if poly.namespace.is_none() && self.local_variables.contains_key(&poly.name) {
                     let id = self.local_variables[&poly.name];
                     // TODO to make this work inside macros, "next" and "index" need to be
                     // their own ast nodes / operators.
-                    assert!(!poly.next);
-                    assert!(poly.index.is_none());
+                    if poly.next || poly.index.is_some() {
+                        return Err(format!(
+                            "Parameter {} cannot be shifted or indexed.",
+                            poly.name
+                        ));
+                    }
                     if let Some(arguments) = &self.macro_arguments {
                         arguments[id as usize].clone()
                     } else {
                         Expression::LocalVariableReference(id)
                     }
                 } else {
-                    Expression::PolynomialReference(self.process_polynomial_reference(poly))
+                    Expression::PolynomialReference(self.process_polynomial_reference(poly)?)
                 }
             }
             ast::Expression::PublicReference(name) => Expression::PublicReference(name.clone()),
             ast::Expression::Number(n) => Expression::Number(n.clone()),
             ast::Expression::String(value) => Expression::String(value.clone()),
-            ast::Expression::Tuple(items) => Expression::Tuple(self.process_expressions(items)),
+            ast::Expression::Tuple(items) => Expression::Tuple(self.process_expressions(items)?),
             ast::Expression::BinaryOperation(left, op, right) => {
-                if let Some(value) = self.evaluate_binary_operation(left, op, right) {
+                if let Some(value) = self.evaluate_binary_operation(left, op, right)? {
                     Expression::Number(value)
                 } else {
                     Expression::BinaryOperation(
-                        Box::new(self.process_expression(left)),
+                        Box::new(self.process_expression(left)?),
                         *op,
-                        Box::new(self.process_expression(right)),
+                        Box::new(self.process_expression(right)?),
                     )
                 }
             }
             ast::Expression::UnaryOperation(op, value) => {
-                if let Some(value) = self.evaluate_unary_operation(op, value) {
+                if let Some(value) = self.evaluate_unary_operation(op, value)? {
                     Expression::Number(value)
                 } else {
-                    Expression::UnaryOperation(*op, Box::new(self.process_expression(value)))
+                    Expression::UnaryOperation(*op, Box::new(self.process_expression(value)?))
                 }
             }
             ast::Expression::FunctionCall(name, arguments) if self.macros.contains_key(name) => {
-                self.process_macro_call(name, arguments)
-                    .expect("Invoked a macro in expression context with empty expression.")
+                self.process_macro_call(name, arguments)?.ok_or_else(|| {
+                    "Invoked a macro in expression context with empty expression.".to_string()
+                })?
             }
             ast::Expression::FunctionCall(name, arguments) => {
-                Expression::FunctionCall(self.namespaced(name), self.process_expressions(arguments))
+                // Names that already contain a namespace refer to other namespaces.
+                let name = if name.contains('.') {
+                    name.clone()
+                } else {
+                    self.namespaced(name)
+                };
+                Expression::FunctionCall(name, self.process_expressions(arguments)?)
             }
-            ast::Expression::FreeInput(_) => panic!(),
-        }
+            ast::Expression::FreeInput(_) => return Err(free_input_error()),
+            ast::Expression::IfElse(condition, if_true, if_false) => {
+                match self.evaluate_expression(condition)? {
+                    Some(c) if is_zero(&c) => self.process_expression(if_false)?,
+                    Some(_) => self.process_expression(if_true)?,
+                    None => Expression::IfElse(
+                        Box::new(self.process_expression(condition)?),
+                        Box::new(self.process_expression(if_true)?),
+                        Box::new(self.process_expression(if_false)?),
+                    ),
+                }
+            }
+        })
     }
 
     fn process_macro_call(
         &mut self,
         name: &str,
         arguments: &[ast::Expression],
-    ) -> Option<Expression> {
-        let arguments = Some(self.process_expressions(arguments));
+    ) -> Result<Option<Expression>, String> {
+        let arguments = Some(self.process_expressions(arguments)?);
         let old_arguments = std::mem::replace(&mut self.macro_arguments, arguments);
 
         let old_locals = std::mem::take(&mut self.local_variables);
@@ -504,7 +698,7 @@ impl PILContext {
         let mac = &self
             .macros
             .get(name)
-            .unwrap_or_else(|| panic!("Macro {name} not found."));
+            .ok_or_else(|| format!("Macro {name} not found."))?;
         self.local_variables = mac
             .parameters
             .iter()
@@ -515,46 +709,69 @@ impl PILContext {
         let expression = mac.expression.clone();
         let identities = mac.identities.clone();
         for identity in &identities {
-            self.handle_identity_statement(identity);
+            self.handle_identity_statement(identity)?;
         }
-        let result = expression.map(|expr| self.process_expression(&expr));
+        let result = expression
+            .map(|expr| self.process_expression(&expr))
+            .transpose()?;
         self.macro_arguments = old_arguments;
         self.local_variables = old_locals;
-        result
+        Ok(result)
     }
 
-    fn process_polynomial_reference(&self, poly: &ast::PolynomialReference) -> PolynomialReference {
+    fn process_polynomial_reference(
+        &self,
+        poly: &ast::PolynomialReference,
+    ) -> Result<PolynomialReference, String> {
         let index = poly
             .index
             .as_ref()
-            .map(|i| self.evaluate_expression(i).unwrap())
-            .map(|i| abstract_to_degree(&i));
-        PolynomialReference {
+            .map(|i| self.evaluate_to_degree(i, &format!("Index into {}", poly.name)))
+            .transpose()?;
+        Ok(PolynomialReference {
             name: self.namespaced_ref(&poly.namespace, &poly.name),
             index,
             next: poly.next,
-        }
+        })
+    }
+
+    /// Evaluates an expression that has to be constant and fit into a degree,
+    /// like the degree of a namespace, which is described by `what` in the error.
+    fn evaluate_to_degree(&self, expr: &ast::Expression, what: &str) -> Result<DegreeType, String> {
+        let value = self
+            .evaluate_expression(expr)?
+            .ok_or_else(|| format!("{what} is not constant."))?;
+        to_u64(&value).map_err(|e| format!("{what}: {e}"))
     }
 
-    fn evaluate_expression(&self, expr: &ast::Expression) -> Option<AbstractNumberType> {
+    fn evaluate_expression(
+        &self,
+        expr: &ast::Expression,
+    ) -> Result<Option<AbstractNumberType>, String> {
         match expr {
-            ast::Expression::Constant(name) => Some(
-                self.constants
-                    .get(name)
-                    .unwrap_or_else(|| panic!("Constant {name} not found."))
-                    .clone(),
-            ),
-            ast::Expression::PolynomialReference(_) => None,
-            ast::Expression::PublicReference(_) => None,
-            ast::Expression::Number(n) => Some(n.clone()),
-            ast::Expression::String(_) => None,
-            ast::Expression::Tuple(_) => None,
+            ast::Expression::Constant(name) => self
+                .constants
+                .get(name)
+                .map(|value| Some(value.clone()))
+                .ok_or_else(|| format!("Constant {name} not found.")),
+            ast::Expression::PolynomialReference(_) => Ok(None),
+            ast::Expression::PublicReference(_) => Ok(None),
+            ast::Expression::Number(n) => Ok(Some(n.clone())),
+            ast::Expression::String(_) => Ok(None),
+            ast::Expression::Tuple(_) => Ok(None),
             ast::Expression::BinaryOperation(left, op, right) => {
                 self.evaluate_binary_operation(left, op, right)
             }
             ast::Expression::UnaryOperation(op, value) => self.evaluate_unary_operation(op, value),
-            ast::Expression::FunctionCall(_, _) => None,
-            ast::Expression::FreeInput(_) => panic!(),
+            ast::Expression::FunctionCall(_, _) => Ok(None),
+            ast::Expression::FreeInput(_) => Err(free_input_error()),
+            ast::Expression::IfElse(condition, if_true, if_false) => {
+                match self.evaluate_expression(condition)? {
+                    None => Ok(None),
+                    Some(c) if is_zero(&c) => self.evaluate_expression(if_false),
+                    Some(_) => self.evaluate_expression(if_true),
+                }
+            }
         }
     }
 
@@ -563,39 +780,48 @@ impl PILContext {
         left: &ast::Expression,
         op: &BinaryOperator,
         right: &ast::Expression,
-    ) -> Option<AbstractNumberType> {
-        if let (Some(left), Some(right)) = (
-            self.evaluate_expression(left),
-            self.evaluate_expression(right),
-        ) {
-            Some(match op {
-                BinaryOperator::Add => left + right,
-                BinaryOperator::Sub => left - right,
-                BinaryOperator::Mul => left * right,
-                BinaryOperator::Div => left / right,
-                BinaryOperator::Pow => {
-                    assert!(AbstractNumberType::from(0) <= right && right <= u32::MAX.into());
-                    left.pow(abstract_to_degree(&right) as u32)
-                }
-                BinaryOperator::Mod => left % right,
-                BinaryOperator::BinaryAnd => left & right,
-                BinaryOperator::BinaryOr => left | right,
-                BinaryOperator::ShiftLeft => left << abstract_to_degree(&right),
-                BinaryOperator::ShiftRight => left >> abstract_to_degree(&right),
-            })
-        } else {
-            None
+    ) -> Result<Option<AbstractNumberType>, String> {
+        let Some(left) = self.evaluate_expression(left)? else {
+            return Ok(None);
+        };
+        let Some(right) = self.evaluate_expression(right)? else {
+            return Ok(None);
+        };
+        if matches!(
+            op,
+            BinaryOperator::Pow | BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight
+        ) && to_u64(&right).map_or(true, |r| r > u32::MAX.into())
+        {
+            return Err(format!("Operand {right} of {op} is out of range."));
         }
+        Ok(Some(evaluator::evaluate_binary_operation(left, op, right)))
     }
 
     fn evaluate_unary_operation(
         &self,
         op: &UnaryOperator,
         value: &ast::Expression,
-    ) -> Option<AbstractNumberType> {
-        self.evaluate_expression(value).map(|v| match op {
-            UnaryOperator::Plus => v,
-            UnaryOperator::Minus => -v,
-        })
+    ) -> Result<Option<AbstractNumberType>, String> {
+        Ok(self
+            .evaluate_expression(value)?
+            .map(|v| evaluator::evaluate_unary_operation(op, v)))
     }
 }
+
+fn free_input_error() -> String {
+    "Free inputs are only allowed in assembly code.".to_string()
+}
+
+/// @returns `count` pseudo-random field elements derived from the seed.
+/// The sequence only depends on the seed (and not on the platform or the run),
+/// it uses SplitMix64 followed by a reduction modulo the field size.
+fn random_values(seed: u64, count: DegreeType) -> impl Iterator<Item = AbstractNumberType> {
+    let mut state = seed;
+    (0..count).map(move |_| {
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        ((z ^ (z >> 31)) % GOLDILOCKS_MOD).into()
+    })
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use crate::parser::asm_ast::*;
+use crate::parser::ast::*;
+
+use super::evaluate_constant;
+
+/// Removes all macro definitions and replaces macro invocations
+/// and loops by the code they expand to.
+pub fn expand(input: ASMFile) -> ASMFile {
+    let mut expander = MacroExpander::default();
+    ASMFile(expander.expand_statements(input.0, &HashMap::new()))
+}
+
+#[derive(Default)]
+struct MacroExpander {
+    macros: HashMap<String, (Vec<String>, Vec<ASMStatement>)>,
+    /// Macros that are currently being expanded, to detect recursion.
+    active: Vec<String>,
+    /// Number of macro and loop body expansions so far.
+    expansions: usize,
+}
+
+impl MacroExpander {
+    fn expand_statements(
+        &mut self,
+        statements: Vec<ASMStatement>,
+        arguments: &HashMap<String, Expression>,
+    ) -> Vec<ASMStatement> {
+        let mut result = vec![];
+        for statement in statements {
+            match statement {
+                ASMStatement::MacroDefinition(_start, name, params, body) => {
+                    let is_new = self.macros.insert(name.clone(), (params, body)).is_none();
+                    assert!(is_new, "Macro {name} defined more than once.");
+                }
+                ASMStatement::Instruction(start, name, args) => {
+                    let args = args.iter().map(|a| substitute(a, arguments)).collect();
+                    if self.macros.contains_key(&name) {
+                        result.extend(self.expand_macro(&name, args));
+                    } else {
+                        result.push(ASMStatement::Instruction(start, name, args));
+                    }
+                }
+                ASMStatement::Assignment(start, write_regs, assign_reg, value) => {
+                    result.push(ASMStatement::Assignment(
+                        start,
+                        write_regs
+                            .iter()
+                            .map(|reg| substitute_register(reg, arguments))
+                            .collect(),
+                        assign_reg,
+                        Box::new(substitute(&value, arguments)),
+                    ))
+                }
+                ASMStatement::Loop(_start, variable, from, to, body) => {
+                    let [from, to] = [from, to].map(|e| {
+                        evaluate_constant(&substitute(&e, arguments))
+                            .expect("Loop bounds have to be constant.")
+                    });
+                    let mut i = from;
+                    while i < to {
+                        let mut arguments = arguments.clone();
+                        arguments.insert(variable.clone(), Expression::Number(i.clone()));
+                        self.rename_labels(&body, &mut arguments);
+                        result.extend(self.expand_statements(body.clone(), &arguments));
+                        i += 1;
+                    }
+                }
+                ASMStatement::Function(start, name, body) => result.push(ASMStatement::Function(
+                    start,
+                    name,
+                    self.expand_statements(body, arguments),
+                )),
+                ASMStatement::Label(start, name) => result.push(ASMStatement::Label(
+                    start,
+                    substitute_register(&name, arguments),
+                )),
+                ASMStatement::RegisterDeclaration(..)
+                | ASMStatement::InstructionDeclaration(..)
+                | ASMStatement::InlinePil(..) => result.push(statement),
+            }
+        }
+        result
+    }
+
+    fn expand_macro(&mut self, name: &str, args: Vec<Expression>) -> Vec<ASMStatement> {
+        assert!(
+            !self.active.iter().any(|n| n == name),
+            "Macro {name} is used recursively."
+        );
+        let (params, body) = self.macros[name].clone();
+        assert_eq!(
+            params.len(),
+            args.len(),
+            "Macro {name} expects {} arguments.",
+            params.len()
+        );
+        let mut arguments = params.into_iter().zip(args).collect();
+        self.rename_labels(&body, &mut arguments);
+        self.active.push(name.to_string());
+        let result = self.expand_statements(body, &arguments);
+        self.active.pop();
+        result
+    }
+
+    /// Gives the labels defined in the statements a name that is unique to
+    /// this expansion (and cannot clash with user-defined labels), so that
+    /// macros and loops containing labels can be expanded more than once.
+    fn rename_labels(
+        &mut self,
+        statements: &[ASMStatement],
+        arguments: &mut HashMap<String, Expression>,
+    ) {
+        self.expansions += 1;
+        for statement in statements {
+            if let ASMStatement::Label(_, name) = statement {
+                let reference = PolynomialReference {
+                    namespace: None,
+                    name: format!("{name}#{}", self.expansions),
+                    index: None,
+                    next: false,
+                };
+                arguments.insert(name.clone(), Expression::PolynomialReference(reference));
+            }
+        }
+    }
+}
+
+/// Replaces references to parameters by the arguments.
+fn substitute(input: &Expression, arguments: &HashMap<String, Expression>) -> Expression {
+    match input {
+        Expression::PolynomialReference(PolynomialReference {
+            namespace: None,
+            name,
+            index: None,
+            next: false,
+        }) if arguments.contains_key(name) => arguments[name].clone(),
+        Expression::BinaryOperation(left, op, right) => Expression::BinaryOperation(
+            Box::new(substitute(left, arguments)),
+            *op,
+            Box::new(substitute(right, arguments)),
+        ),
+        Expression::UnaryOperation(op, e) => {
+            Expression::UnaryOperation(*op, Box::new(substitute(e, arguments)))
+        }
+        Expression::FreeInput(e) => Expression::FreeInput(Box::new(substitute(e, arguments))),
+        Expression::FunctionCall(name, args) => Expression::FunctionCall(
+            name.clone(),
+            args.iter().map(|e| substitute(e, arguments)).collect(),
+        ),
+        Expression::Tuple(items) => {
+            Expression::Tuple(items.iter().map(|e| substitute(e, arguments)).collect())
+        }
+        Expression::IfElse(condition, if_true, if_false) => Expression::IfElse(
+            Box::new(substitute(condition, arguments)),
+            Box::new(substitute(if_true, arguments)),
+            Box::new(substitute(if_false, arguments)),
+        ),
+        Expression::PolynomialReference(_)
+        | Expression::Constant(_)
+        | Expression::PublicReference(_)
+        | Expression::Number(_)
+        | Expression::String(_) => input.clone(),
+    }
+}
+
+/// Replaces a register that is a parameter by the register passed as argument.
+fn substitute_register(reg: &str, arguments: &HashMap<String, Expression>) -> String {
+    match arguments.get(reg) {
+        None => reg.to_string(),
+        Some(Expression::PolynomialReference(r)) => r.name.clone(),
+        Some(_) => panic!("Expected a register as argument for {reg}."),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::compile;
+
+    const HEADER: &str = r#"
+reg pc[@pc];
+reg X[<=];
+reg A;
+reg B;
+instr add_to_A c { A' = A + c }
+instr jmp l: label { pc' = l }
+"#;
+
+    fn compile_code(code: &str) -> String {
+        let input = format!("{HEADER}{code}");
+        format!("{}", compile(None, &input).unwrap())
+    }
+
+    #[test]
+    fn macro_and_loop() {
+        let expanded = compile_code(
+            r#"
+A <=X= ${ ("input", 0) };
+B <=X= A + 1;
+add_to_A 0;
+add_to_A 2;
+add_to_A 4;
+B <=X= A + 1;
+add_to_A 0;
+add_to_A 2;
+add_to_A 4;
+"#,
+        );
+        let with_macros = compile_code(
+            r#"
+macro copy(to, source, offset) {
+  to <=X= source + offset;
+}
+macro add_even(n) {
+  for i in 0..n {
+    add_to_A 2 * i;
+  }
+}
+A <=X= ${ ("input", 0) };
+for j in 0..2 {
+  copy B, A, 1;
+  add_even 3;
+}
+"#,
+        );
+        assert_eq!(with_macros, expanded);
+    }
+
+    #[test]
+    fn labels_in_macros_and_loops() {
+        let expanded = compile_code(
+            r#"
+jmp skip1;
+A <=X= 1;
+skip1::
+jmp skip2;
+A <=X= 1;
+skip2::
+jmp skip3;
+add_to_A 0;
+skip3::
+jmp skip4;
+add_to_A 1;
+skip4::
+"#,
+        );
+        let with_macros = compile_code(
+            r#"
+macro skip_assignment(value) {
+  jmp skip;
+  A <=X= value;
+  skip::
+}
+skip_assignment 1;
+skip_assignment 1;
+for i in 0..2 {
+  jmp skip;
+  add_to_A i;
+  skip::
+}
+"#,
+        );
+        assert_eq!(with_macros, expanded);
+    }
+
+    #[test]
+    #[should_panic(expected = "Macro f is used recursively.")]
+    fn recursive_macro() {
+        compile_code("macro f(x) { f x; }\nf 1;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Loop bounds have to be constant.")]
+    fn non_constant_loop() {
+        compile_code("for i in 0..A { add_to_A i; }");
+    }
+}
@@ -1,13 +1,28 @@
 use std::collections::{BTreeMap, HashMap};
 
+use crate::analyzer::evaluator::{evaluate_binary_operation, evaluate_unary_operation};
 use crate::number::AbstractNumberType;
 use crate::parser::asm_ast::*;
 use crate::parser::ast::*;
 use crate::parser::{self, ParseError};
 
+mod macro_expander;
+mod optimizer;
+
 pub fn compile<'a>(file_name: Option<&str>, input: &'a str) -> Result<PILFile, ParseError<'a>> {
+    compile_with_optimizer(file_name, input, true)
+}
+
+/// Like `compile`, but code lines are only removed and merged
+/// by the optimizer if `optimize` is true.
+pub fn compile_with_optimizer<'a>(
+    file_name: Option<&str>,
+    input: &'a str,
+    optimize: bool,
+) -> Result<PILFile, ParseError<'a>> {
     let max_steps = 1024;
-    parser::parse_asm(file_name, input).map(|ast| ASMPILConverter::new().convert(ast, max_steps))
+    parser::parse_asm(file_name, input)
+        .map(|ast| ASMPILConverter::new().convert(macro_expander::expand(ast), max_steps, optimize))
 }
 
 #[derive(Default)]
@@ -29,7 +44,7 @@ impl ASMPILConverter {
         Default::default()
     }
 
-    fn convert(&mut self, input: ASMFile, max_steps: usize) -> PILFile {
+    fn convert(&mut self, input: ASMFile, max_steps: usize, optimize: bool) -> PILFile {
         // TODO configure the degree
         self.pil.push(Statement::Namespace(
             0,
@@ -51,16 +66,8 @@ impl ASMPILConverter {
                     self.handle_instruction_def(start, body, name, params);
                 }
                 ASMStatement::InlinePil(_start, statements) => self.pil.extend(statements.clone()),
-                ASMStatement::Assignment(start, write_regs, assign_reg, value) => {
-                    self.handle_assignment(*start, write_regs, assign_reg, value.as_ref())
-                }
-                ASMStatement::Instruction(_start, instr_name, args) => {
-                    self.handle_instruction(instr_name, args)
-                }
-                ASMStatement::Label(_start, name) => self.code_lines.push(CodeLine {
-                    label: Some(name.clone()),
-                    ..Default::default()
-                }),
+                ASMStatement::Function(_start, name, body) => self.handle_function(name, body),
+                _ => self.handle_code_statement(statement),
             }
         }
         self.create_constraints_for_assignment_reg();
@@ -74,6 +81,22 @@ impl ASMPILConverter {
                 }),
         );
 
+        if optimize {
+            // All registers but the pc and the assignment register are zero in the first row.
+            let zero_registers = self
+                .registers
+                .keys()
+                .filter(|name| {
+                    Some(*name) != self.pc_name.as_ref() && *name != self.default_assignment_reg()
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            self.code_lines = optimizer::optimize(
+                std::mem::take(&mut self.code_lines),
+                &self.instructions,
+                &zero_registers,
+            );
+        }
         self.translate_code_lines();
 
         self.pil.push(Statement::PlookupIdentity(
@@ -99,6 +122,48 @@ impl ASMPILConverter {
         PILFile(std::mem::take(&mut self.pil))
     }
 
+    /// Functions can only be entered through jumps (or calls) to their label,
+    /// so the code before and the function itself have to end in a jump.
+    fn handle_function(&mut self, name: &str, body: &[ASMStatement]) {
+        self.assert_no_fall_through(&format!("Code falls through into function {name}"));
+        self.code_lines.push(CodeLine {
+            label: Some(name.to_string()),
+            ..Default::default()
+        });
+        for statement in body {
+            self.handle_code_statement(statement);
+        }
+        self.assert_no_fall_through(&format!("Function {name} does not end in a jump"));
+    }
+
+    fn handle_code_statement(&mut self, statement: &ASMStatement) {
+        match statement {
+            ASMStatement::Assignment(start, write_regs, assign_reg, value) => {
+                self.handle_assignment(*start, write_regs, assign_reg, value.as_ref())
+            }
+            ASMStatement::Instruction(_start, instr_name, args) => {
+                self.handle_instruction(instr_name, args)
+            }
+            ASMStatement::Label(_start, name) => self.code_lines.push(CodeLine {
+                label: Some(name.clone()),
+                ..Default::default()
+            }),
+            _ => panic!("Expected an assignment, instruction or label."),
+        }
+    }
+
+    /// Panics with the message if there is code and its last line
+    /// could continue with the next line.
+    fn assert_no_fall_through(&self, message: &str) {
+        if let Some(line) = self.code_lines.last() {
+            let jumps = line
+                .instruction
+                .as_ref()
+                .is_some_and(|instr| self.instructions[instr].jumps_unconditionally);
+            assert!(jumps, "{message}.");
+        }
+    }
+
     fn handle_register_declaration(
         &mut self,
         flags: &Option<RegisterFlag>,
@@ -171,6 +236,13 @@ impl ASMPILConverter {
         // it's part of the lookup!
         //self.pil.push(constrain_zero_one(&col_name));
 
+        let mut jumps_unconditionally = false;
+        let mut pc_update = None;
+        let mut saves_pc = false;
+        let mut relative_jump = false;
+        let mut jump_registers = vec![];
+        let mut return_registers = vec![];
+        let mut updated_registers = vec![];
         let mut substitutions = HashMap::new();
         for p in params {
             if p.assignment_reg.0.is_none() && p.assignment_reg.1.is_none() {
@@ -187,6 +259,32 @@ impl ASMPILConverter {
                     let expr = substitute(expr, &substitutions);
                     match extract_update(expr) {
                         (Some(var), expr) => {
+                            if Some(&var) == self.pc_name.as_ref() {
+                                // Either a jump to a label or register, or a loop on
+                                // the current line, but nothing that depends on `pc + 1`.
+                                jumps_unconditionally = expr == direct_reference(&var)
+                                    || !contains_reference(&expr, &var);
+                                relative_jump |= expr != direct_reference(&var)
+                                    && !only_refers_to_next_line(&expr, &var);
+                                jump_registers.extend(
+                                    self.registers
+                                        .keys()
+                                        .filter(|reg| {
+                                            **reg != var && contains_reference(&expr, reg)
+                                        })
+                                        .cloned(),
+                                );
+                                pc_update = Some(expr.clone());
+                            } else {
+                                if let Some(pc) = &self.pc_name {
+                                    saves_pc |= contains_reference(&expr, pc);
+                                    relative_jump |= !only_refers_to_next_line(&expr, pc);
+                                    if expr == next_line(pc) {
+                                        return_registers.push(var.clone());
+                                    }
+                                }
+                                updated_registers.push(var.clone());
+                            }
                             self.registers
                                 .get_mut(&var)
                                 .unwrap()
@@ -213,8 +311,22 @@ impl ASMPILConverter {
                 }
             }
         }
+        let is_jump = match (&params[..], &pc_update) {
+            ([param], Some(update)) if body.len() == 1 => {
+                param.param_type == Some("label".to_string())
+                    && *update == direct_reference(&format!("instr_{name}_param_{}", param.name))
+            }
+            _ => false,
+        };
         let instr = Instruction {
             params: params.clone(),
+            jumps_unconditionally,
+            is_jump,
+            saves_pc,
+            relative_jump,
+            jump_registers,
+            return_registers,
+            updated_registers,
         };
         self.instructions.insert(name.to_string(), instr);
     }
@@ -222,7 +334,7 @@ impl ASMPILConverter {
     fn handle_assignment(
         &mut self,
         _start: usize,
-        write_regs: &Vec<String>,
+        write_regs: &[String],
         _assign_reg: &Option<String>,
         value: &Expression,
     ) {
@@ -237,7 +349,10 @@ impl ASMPILConverter {
     }
 
     fn handle_instruction(&mut self, instr_name: &str, args: &Vec<Expression>) {
-        let instr = &self.instructions[instr_name];
+        let instr = self
+            .instructions
+            .get(instr_name)
+            .unwrap_or_else(|| panic!("Instruction {instr_name} not declared."));
         assert_eq!(instr.params.len(), args.len());
         let mut value = vec![];
         let mut instruction_literal_args = vec![];
@@ -265,10 +380,18 @@ impl ASMPILConverter {
                 instruction_literal_args.push(None);
             } else if p.param_type == Some("label".to_string()) {
                 if let Expression::PolynomialReference(r) = a {
-                    instruction_literal_args.push(Some(r.name.clone()))
+                    instruction_literal_args.push(Some(LiteralArgument::Label(r.name.clone())))
                 } else {
-                    panic!();
+                    panic!("Expected a label as argument {} of {instr_name}.", p.name);
                 }
+            } else if p.param_type.is_none() {
+                let value = evaluate_constant(a).unwrap_or_else(|| {
+                    panic!(
+                        "Expected a constant as argument {} of {instr_name}.",
+                        p.name
+                    )
+                });
+                instruction_literal_args.push(Some(LiteralArgument::Number(value)));
             } else {
                 todo!("Param type not supported.");
             }
@@ -320,18 +443,13 @@ impl ASMPILConverter {
                     self.negate_assignment_value(self.process_assignment_value(right)),
                 ),
                 BinaryOperator::Mul => todo!(),
-                BinaryOperator::Div => panic!(),
-                BinaryOperator::Mod => panic!(),
-                BinaryOperator::Pow => panic!(),
-                BinaryOperator::BinaryAnd => panic!(),
-                BinaryOperator::BinaryOr => panic!(),
-                BinaryOperator::ShiftLeft => panic!(),
-                BinaryOperator::ShiftRight => panic!(),
+                _ => panic!(),
             },
             Expression::UnaryOperation(op, expr) => {
                 assert!(*op == UnaryOperator::Minus);
                 self.negate_assignment_value(self.process_assignment_value(expr))
             }
+            Expression::IfElse(_, _, _) => panic!(),
         }
     }
 
@@ -457,10 +575,16 @@ impl ASMPILConverter {
                     .zip(&self.instructions[instr].params)
                 {
                     if let Some(arg) = arg {
-                        // TODO has to be label for now
                         program_constants
                             .get_mut(&format!("p_instr_{instr}_param_{}", param.name))
-                            .unwrap()[i] = (label_positions[arg] as i64).into();
+                            .unwrap()[i] = match arg {
+                            LiteralArgument::Label(label) => (*label_positions
+                                .get(label)
+                                .unwrap_or_else(|| panic!("Undefined label: {label}"))
+                                as i64)
+                                .into(),
+                            LiteralArgument::Number(value) => value.clone(),
+                        };
                     }
                 }
             } else {
@@ -486,11 +610,15 @@ impl ASMPILConverter {
     }
 
     fn compute_label_positions(&self) -> HashMap<String, usize> {
-        self.code_lines
-            .iter()
-            .enumerate()
-            .filter_map(|(i, line)| line.label.as_ref().map(|l| (l.clone(), i)))
-            .collect()
+        let mut positions = HashMap::new();
+        for (i, line) in self.code_lines.iter().enumerate() {
+            if let Some(label) = &line.label {
+                if positions.insert(label.clone(), i).is_some() {
+                    panic!("Label {label} defined more than once.");
+                }
+            }
+        }
+        positions
     }
 
     /// Creates a pair of witness and fixed column and matches them in the lookup.
@@ -550,6 +678,23 @@ impl Register {
 
 struct Instruction {
     params: Vec<InstructionParam>,
+    /// True if the instruction never continues with the next line.
+    jumps_unconditionally: bool,
+    /// True if the only effect of the instruction is to jump to its label argument.
+    is_jump: bool,
+    /// True if the instruction stores the pc in another register,
+    /// i.e. execution might return to the next line.
+    saves_pc: bool,
+    /// True if the pc or another register is set relative to the pc
+    /// other than to `pc + 1`, so that the target of a jump depends on
+    /// the number of lines in between.
+    relative_jump: bool,
+    /// The registers (other than the pc) the pc update depends on.
+    jump_registers: Vec<String>,
+    /// The registers that are set to `pc + 1`.
+    return_registers: Vec<String>,
+    /// The registers (other than the pc) the instruction assigns to.
+    updated_registers: Vec<String>,
 }
 
 #[derive(Default)]
@@ -558,8 +703,13 @@ struct CodeLine {
     value: Vec<(AbstractNumberType, AffineExpressionComponent)>,
     label: Option<String>,
     instruction: Option<String>,
-    // TODO we only support labels for now.
-    instruction_literal_args: Vec<Option<String>>,
+    instruction_literal_args: Vec<Option<LiteralArgument>>,
+}
+
+/// An instruction argument that is stored in the program.
+enum LiteralArgument {
+    Label(String),
+    Number(AbstractNumberType),
 }
 
 enum AffineExpressionComponent {
@@ -642,6 +792,61 @@ fn extract_update(expr: Expression) -> (Option<String>, Expression) {
     }
 }
 
+/// @returns the value of an expression that only consists of numbers.
+fn evaluate_constant(expr: &Expression) -> Option<AbstractNumberType> {
+    match expr {
+        Expression::Number(value) => Some(value.clone()),
+        Expression::UnaryOperation(op, e) => {
+            Some(evaluate_unary_operation(op, evaluate_constant(e)?))
+        }
+        Expression::BinaryOperation(left, op, right) => Some(evaluate_binary_operation(
+            evaluate_constant(left)?,
+            op,
+            evaluate_constant(right)?,
+        )),
+        _ => None,
+    }
+}
+
+/// @returns `pc + 1`.
+fn next_line(pc: &str) -> Expression {
+    build_add(direct_reference(pc), build_number(1.into()))
+}
+
+/// @returns true if the expression only refers to the pc in the form `pc + 1`.
+fn only_refers_to_next_line(expr: &Expression, pc: &str) -> bool {
+    if *expr == next_line(pc) {
+        return true;
+    }
+    match expr {
+        Expression::BinaryOperation(left, _, right) => {
+            only_refers_to_next_line(left, pc) && only_refers_to_next_line(right, pc)
+        }
+        Expression::UnaryOperation(_, e) => only_refers_to_next_line(e, pc),
+        _ => !contains_reference(expr, pc),
+    }
+}
+
+fn contains_reference(expr: &Expression, name: &str) -> bool {
+    match expr {
+        Expression::PolynomialReference(r) => r.name == name,
+        Expression::BinaryOperation(left, _, right) => {
+            contains_reference(left, name) || contains_reference(right, name)
+        }
+        Expression::UnaryOperation(_, e) | Expression::FreeInput(e) => contains_reference(e, name),
+        Expression::FunctionCall(_, items) | Expression::Tuple(items) => {
+            items.iter().any(|e| contains_reference(e, name))
+        }
+        Expression::IfElse(condition, if_true, if_false) => [condition, if_true, if_false]
+            .into_iter()
+            .any(|e| contains_reference(e, name)),
+        Expression::Constant(_)
+        | Expression::PublicReference(_)
+        | Expression::Number(_)
+        | Expression::String(_) => false,
+    }
+}
+
 fn substitute(input: &Expression, substitution: &HashMap<String, String>) -> Expression {
     match input {
         // TODO namespace
@@ -664,6 +869,11 @@ fn substitute(input: &Expression, substitution: &HashMap<String, String>) -> Exp
         Expression::Tuple(items) => {
             Expression::Tuple(items.iter().map(|e| substitute(e, substitution)).collect())
         }
+        Expression::IfElse(condition, if_true, if_false) => Expression::IfElse(
+            Box::new(substitute(condition, substitution)),
+            Box::new(substitute(if_true, substitution)),
+            Box::new(substitute(if_false, substitution)),
+        ),
         Expression::Constant(_)
         | Expression::PublicReference(_)
         | Expression::Number(_)
@@ -756,4 +966,82 @@ pol constant p_reg_write_CNT = [1, 0, 0, 0, 0, 0, 0, 0, 0];
         let pil = compile(Some(file_name), &contents).unwrap();
         assert_eq!(format!("{pil}").trim(), expectation.trim());
     }
+
+    const HEADER: &str = r#"
+reg pc[@pc];
+reg X[<=];
+reg A;
+reg RA;
+instr jmp l: label { pc' = l }
+instr jmpz <=X= c, l: label { pc' = XIsZero * l + (1 - XIsZero) * (pc + 1) }
+instr call l: label { RA' = pc + 1, pc' = l }
+instr ret { pc' = RA }
+"#;
+
+    fn compile_code(code: &str) -> String {
+        let input = format!("{HEADER}{code}");
+        format!("{}", compile(None, &input).unwrap())
+    }
+
+    #[test]
+    pub fn function_call() {
+        let pil = compile_code(
+            r#"
+call f;
+jmp end;
+function f {
+  A <=X= 7;
+  ret;
+}
+end::
+"#,
+        );
+        assert!(pil.contains("pol constant p_instr_call = [1, 0, 0, 0, 0, 0];"));
+        assert!(pil.contains("pol constant p_instr_call_param_l = [2, 0, 0, 0, 0, 0];"));
+        assert!(pil.contains("pol constant p_instr_jmp_param_l = [0, 5, 0, 0, 0, 0];"));
+        assert!(pil.contains("pol constant p_instr_ret = [0, 0, 0, 0, 1, 0];"));
+    }
+
+    #[test]
+    pub fn constant_arguments() {
+        let pil = compile_code(
+            r#"
+instr add_to_A c { A' = A + c }
+add_to_A 2**8 - 1;
+add_to_A -3;
+"#,
+        );
+        assert!(pil.contains("pol constant p_instr_add_to_A_param_c = [255, -3];"));
+        assert!(pil.contains("(instr_add_to_A * (A + instr_add_to_A_param_c))"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected a constant as argument c of add_to_A.")]
+    pub fn non_constant_argument() {
+        compile_code("instr add_to_A c { A' = A + c }\nadd_to_A A;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Code falls through into function f.")]
+    pub fn fall_through_into_function() {
+        compile_code("X <=X= 1;\nfunction f { ret; }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Function f does not end in a jump.")]
+    pub fn function_without_return() {
+        compile_code("function f { jmpz X, f; }");
+    }
+
+    #[test]
+    #[should_panic(expected = "Undefined label: g")]
+    pub fn undefined_label() {
+        compile_code("call g;");
+    }
+
+    #[test]
+    #[should_panic(expected = "Label f defined more than once.")]
+    pub fn duplicate_label() {
+        compile_code("f::\njmp f;\nfunction f { ret; }");
+    }
 }
@@ -0,0 +1,440 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::number::AbstractNumberType;
+
+use super::{AffineExpressionComponent, CodeLine, Instruction, LiteralArgument};
+
+/// Removes and merges code lines without changing the effect of the program
+/// on the registers, to shorten the execution trace:
+/// - assignments of a register to itself are removed,
+/// - assignments that are overwritten in the next line are removed,
+/// - jumps to the immediately following line are removed,
+/// - lines that cannot be reached from the first line are removed,
+/// - registers with values known from earlier lines in straight-line code
+///   are replaced by constants, and assignments that do not change the
+///   value of a register are removed.
+///
+/// Labels occupy their own lines, so two assignments can only be merged
+/// if nothing can jump between them. Jumps relative to the pc can reach
+/// lines without a label, so the program is not changed if it contains any.
+pub(super) fn optimize(
+    mut lines: Vec<CodeLine>,
+    instructions: &BTreeMap<String, Instruction>,
+    zero_registers: &[String],
+) -> Vec<CodeLine> {
+    if !jump_targets_are_labels(&lines, instructions) {
+        return lines;
+    }
+    loop {
+        let len = lines.len();
+        lines = propagate_constants(lines, instructions, zero_registers);
+        lines = remove_redundant_moves(lines);
+        lines = remove_overwritten_assignments(lines);
+        lines = remove_jumps_to_next_line(lines, instructions);
+        lines = remove_unreachable_lines(lines, instructions);
+        if lines.len() == len {
+            return lines;
+        }
+    }
+}
+
+/// @returns true if all jumps go to the next line, to a label or to the line
+/// after an instruction that stored the pc, i.e. every pc update is `pc + 1`,
+/// a label or a register that is only set to `pc + 1` by instructions.
+fn jump_targets_are_labels(
+    lines: &[CodeLine],
+    instructions: &BTreeMap<String, Instruction>,
+) -> bool {
+    let used_instructions = lines
+        .iter()
+        .filter_map(|line| line.instruction.as_ref())
+        .map(|name| &instructions[name])
+        .collect::<Vec<_>>();
+    used_instructions.iter().all(|instr| !instr.relative_jump)
+        && used_instructions
+            .iter()
+            .flat_map(|instr| &instr.jump_registers)
+            .all(|reg| {
+                lines
+                    .iter()
+                    .all(|line| line.write_reg.as_ref() != Some(reg))
+                    && used_instructions.iter().all(|instr| {
+                        !instr.updated_registers.contains(reg)
+                            || instr.return_registers.contains(reg)
+                    })
+            })
+}
+
+/// Replaces registers with known values by constants. Values are only known
+/// in straight-line code, i.e. until the next label or the line after an
+/// instruction that might return there. This requires that no line without
+/// a label is reached by a jump, see `jump_targets_are_labels`.
+/// The registers in `zero_registers` are known to be zero on the first line.
+fn propagate_constants(
+    lines: Vec<CodeLine>,
+    instructions: &BTreeMap<String, Instruction>,
+    zero_registers: &[String],
+) -> Vec<CodeLine> {
+    let mut known = zero_registers
+        .iter()
+        .map(|reg| (reg.clone(), AbstractNumberType::from(0)))
+        .collect::<HashMap<_, _>>();
+    let mut result = vec![];
+    for mut line in lines {
+        if line.label.is_some() {
+            known.clear();
+        }
+        line.value = substitute_known(std::mem::take(&mut line.value), &known);
+        match (&line.instruction, &line.write_reg) {
+            (None, Some(reg)) => {
+                let value = constant_value(&line.value);
+                if value.is_some() && value.as_ref() == known.get(reg) {
+                    continue;
+                }
+                match value {
+                    Some(value) => known.insert(reg.clone(), value),
+                    None => known.remove(reg),
+                };
+            }
+            (Some(instr), write_reg) => {
+                let instr = &instructions[instr];
+                if instr.saves_pc {
+                    known.clear();
+                }
+                for reg in instr.updated_registers.iter().chain(write_reg) {
+                    known.remove(reg);
+                }
+            }
+            (None, None) => {}
+        }
+        result.push(line);
+    }
+    result
+}
+
+/// Replaces known registers by constants and combines all constants
+/// into a single component.
+fn substitute_known(
+    value: Vec<(AbstractNumberType, AffineExpressionComponent)>,
+    known: &HashMap<String, AbstractNumberType>,
+) -> Vec<(AbstractNumberType, AffineExpressionComponent)> {
+    let mut constant = AbstractNumberType::from(0);
+    let mut result = vec![];
+    for (coeff, component) in value {
+        match component {
+            AffineExpressionComponent::Constant => constant += coeff,
+            AffineExpressionComponent::Register(reg) if known.contains_key(&reg) => {
+                constant += coeff * &known[&reg]
+            }
+            component => result.push((coeff, component)),
+        }
+    }
+    if constant != 0.into() {
+        result.push((constant, AffineExpressionComponent::Constant));
+    }
+    result
+}
+
+/// @returns the value if it does not depend on registers or free inputs.
+fn constant_value(
+    value: &[(AbstractNumberType, AffineExpressionComponent)],
+) -> Option<AbstractNumberType> {
+    value.iter().try_fold(
+        AbstractNumberType::from(0),
+        |acc, (coeff, component)| match component {
+            AffineExpressionComponent::Constant => Some(acc + coeff),
+            _ => None,
+        },
+    )
+}
+
+fn remove_redundant_moves(lines: Vec<CodeLine>) -> Vec<CodeLine> {
+    lines
+        .into_iter()
+        .filter(|line| {
+            !matches!((&line.instruction, &line.write_reg, &line.value[..]),
+                (None, Some(reg), [(coeff, AffineExpressionComponent::Register(source))])
+                    if reg == source && *coeff == 1.into())
+        })
+        .collect()
+}
+
+/// Removes assignments to a register that is assigned again
+/// in the next line without being read. Assignments from free inputs
+/// are kept, since the prover still has to be queried for their values.
+fn remove_overwritten_assignments(lines: Vec<CodeLine>) -> Vec<CodeLine> {
+    let mut result: Vec<CodeLine> = vec![];
+    for line in lines {
+        if let (Some(previous), None, Some(reg)) =
+            (result.last(), &line.instruction, &line.write_reg)
+        {
+            let reads_register = line
+                .value
+                .iter()
+                .any(|(_, c)| matches!(c, AffineExpressionComponent::Register(r) if r == reg));
+            let reads_free_input = previous
+                .value
+                .iter()
+                .any(|(_, c)| matches!(c, AffineExpressionComponent::FreeInput(_)));
+            if previous.instruction.is_none()
+                && previous.write_reg.as_ref() == Some(reg)
+                && !reads_register
+                && !reads_free_input
+            {
+                result.pop();
+            }
+        }
+        result.push(line);
+    }
+    result
+}
+
+fn remove_jumps_to_next_line(
+    lines: Vec<CodeLine>,
+    instructions: &BTreeMap<String, Instruction>,
+) -> Vec<CodeLine> {
+    let jumps_to_next_line = |i: usize| {
+        let line = &lines[i];
+        let target = match (&line.instruction, &line.instruction_literal_args[..]) {
+            (Some(instr), [Some(LiteralArgument::Label(target))])
+                if instructions[instr].is_jump =>
+            {
+                target
+            }
+            _ => return false,
+        };
+        lines[i + 1..]
+            .iter()
+            .map_while(|line| line.label.as_ref())
+            .any(|label| label == target)
+    };
+    let keep = (0..lines.len())
+        .map(|i| !jumps_to_next_line(i))
+        .collect::<Vec<_>>();
+    lines
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(line, keep)| keep.then_some(line))
+        .collect()
+}
+
+/// Removes all lines that cannot be reached from the first line.
+/// Jumps to registers are assumed to only return to the line after
+/// an instruction that stored the pc.
+fn remove_unreachable_lines(
+    lines: Vec<CodeLine>,
+    instructions: &BTreeMap<String, Instruction>,
+) -> Vec<CodeLine> {
+    let label_positions = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| line.label.as_ref().map(|l| (l.as_str(), i)))
+        .collect::<HashMap<_, _>>();
+    let mut reachable = vec![false; lines.len()];
+    let mut to_visit = vec![0];
+    while let Some(i) = to_visit.pop() {
+        if i >= lines.len() || reachable[i] {
+            continue;
+        }
+        reachable[i] = true;
+        let instr = lines[i]
+            .instruction
+            .as_ref()
+            .map(|name| &instructions[name]);
+        if instr.is_none_or(|instr| !instr.jumps_unconditionally || instr.saves_pc) {
+            to_visit.push(i + 1);
+        }
+        for arg in &lines[i].instruction_literal_args {
+            if let Some(LiteralArgument::Label(label)) = arg {
+                // Undefined labels are reported later.
+                to_visit.extend(label_positions.get(label.as_str()));
+            }
+        }
+    }
+    lines
+        .into_iter()
+        .zip(reachable)
+        .filter_map(|(line, reachable)| reachable.then_some(line))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{compile, compile_with_optimizer};
+
+    const HEADER: &str = r#"
+reg pc[@pc];
+reg X[<=];
+reg A;
+reg B;
+instr jmp l: label { pc' = l }
+instr jmpz <=X= c, l: label { pc' = XIsZero * l + (1 - XIsZero) * (pc + 1) }
+"#;
+
+    fn compile_code(code: &str) -> String {
+        let input = format!("{HEADER}{code}");
+        format!("{}", compile(None, &input).unwrap())
+    }
+
+    #[test]
+    fn optimize() {
+        let optimized = compile_code(
+            r#"
+A <=X= 2;
+A <=X= A;
+A <=X= A + 3;
+A <=X= A - 1;
+jmp next;
+next::
+B <=X= A + 1;
+jmpz A, after;
+after::
+B <=X= B + 1;
+"#,
+        );
+        let expected = compile_code(
+            r#"
+A <=X= 4;
+next::
+B <=X= A + 1;
+jmpz A, after;
+after::
+B <=X= B + 1;
+"#,
+        );
+        assert_eq!(optimized, expected);
+    }
+
+    #[test]
+    fn constant_propagation() {
+        let optimized = compile_code(
+            r#"
+A <=X= 0;
+B <=X= A + 2;
+A <=X= B + 4;
+jmpz A - B, end;
+A <=X= 6;
+B <=X= ${ ("input", 0) };
+A <=X= A + B;
+end::
+A <=X= A + 1;
+"#,
+        );
+        let expected = compile_code(
+            r#"
+B <=X= 2;
+A <=X= 6;
+jmpz 4, end;
+B <=X= ${ ("input", 0) };
+A <=X= 6 + B;
+end::
+A <=X= A + 1;
+"#,
+        );
+        assert_eq!(optimized, expected);
+    }
+
+    #[test]
+    fn overwritten_free_input() {
+        let code = r#"
+B <=X= ${ ("input", 0) };
+B <=X= 3;
+A <=X= 1;
+A <=X= 2;
+"#;
+        let expected = r#"
+B <=X= ${ ("input", 0) };
+B <=X= 3;
+A <=X= 2;
+"#;
+        let input = format!("{HEADER}{expected}");
+        let unoptimized = format!("{}", compile_with_optimizer(None, &input, false).unwrap());
+        assert_eq!(compile_code(code), unoptimized);
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let optimized = compile_code(
+            r#"
+reg RA;
+instr call l: label { RA' = pc + 1, pc' = l }
+instr ret { pc' = RA }
+call f;
+A <=X= 1;
+jmp end;
+B <=X= 2;
+jmp end;
+function f {
+  B <=X= 3;
+  ret;
+}
+function g {
+  B <=X= 4;
+  jmp end;
+}
+end::
+"#,
+        );
+        let expected = compile_code(
+            r#"
+reg RA;
+instr call l: label { RA' = pc + 1, pc' = l }
+instr ret { pc' = RA }
+call f;
+A <=X= 1;
+jmp end;
+function f {
+  B <=X= 3;
+  ret;
+}
+end::
+"#,
+        );
+        assert_eq!(optimized, expected);
+    }
+
+    #[test]
+    fn relative_jump() {
+        let code = r#"
+instr skip { pc' = pc + 2 }
+skip;
+A <=X= 1;
+A <=X= 2;
+A <=X= A + 5;
+"#;
+        let input = format!("{HEADER}{code}");
+        let unoptimized = format!("{}", compile_with_optimizer(None, &input, false).unwrap());
+        assert_eq!(compile_code(code), unoptimized);
+    }
+
+    #[test]
+    fn no_constant_propagation_across_relative_jumps() {
+        let code = r#"
+instr skip { pc' = pc + 2 }
+A <=X= 1;
+skip;
+A <=X= 5;
+B <=X= A;
+"#;
+        let input = format!("{HEADER}{code}");
+        let unoptimized = format!("{}", compile_with_optimizer(None, &input, false).unwrap());
+        assert_eq!(compile_code(code), unoptimized);
+    }
+
+    #[test]
+    fn jump_to_assigned_register() {
+        let code = r#"
+reg RA;
+instr call l: label { RA' = pc + 1, pc' = l }
+instr ret { pc' = RA }
+RA <=X= 4;
+ret;
+A <=X= 1;
+A <=X= 2;
+A <=X= 3;
+"#;
+        let input = format!("{HEADER}{code}");
+        let unoptimized = format!("{}", compile_with_optimizer(None, &input, false).unwrap());
+        assert_eq!(compile_code(code), unoptimized);
+    }
+}
@@ -0,0 +1,186 @@
+use std::path::Path;
+
+use crate::analyzer::Analyzed;
+use crate::constant_evaluator::FixedColumn;
+use crate::constraint_checker;
+use crate::number::{AbstractNumberType, DegreeType};
+use crate::witness_io::Witness;
+
+pub use self::proof::{Proof, PROOF_FORMAT_VERSION};
+pub use self::public_values::PublicValues;
+
+mod proof;
+mod public_values;
+
+/// A proof system the pipeline can hand the generated columns to.
+/// Backends write their setup artifacts (keys) to the output directory,
+/// next to the json file and the fixed and witness column files.
+pub trait Backend {
+    /// Creates the artifacts that only depend on the constraints and
+    /// the fixed columns.
+    fn setup(
+        &self,
+        _analyzed: &Analyzed,
+        _fixed: &[(&str, FixedColumn)],
+        _degree: DegreeType,
+        _output_dir: &Path,
+    ) {
+    }
+
+    /// Proves that the witness satisfies the constraints.
+    fn prove(
+        &self,
+        analyzed: &Analyzed,
+        fixed: &[(&str, FixedColumn)],
+        witness: &[(&str, Vec<AbstractNumberType>)],
+        degree: DegreeType,
+    ) -> Result<Proof, String>;
+
+    /// Verifies the proof for the public values contained in it.
+    fn verify(
+        &self,
+        analyzed: &Analyzed,
+        fixed: &[(&str, FixedColumn)],
+        degree: DegreeType,
+        proof: &Proof,
+    ) -> Result<(), String>;
+
+    /// Verifies several proofs for the same constraints.
+    /// Backends that support batch verification can override this.
+    /// @returns the result for each proof, in the given order.
+    fn verify_batch(
+        &self,
+        analyzed: &Analyzed,
+        fixed: &[(&str, FixedColumn)],
+        degree: DegreeType,
+        proofs: &[&Proof],
+    ) -> Vec<Result<(), String>> {
+        proofs
+            .iter()
+            .map(|proof| self.verify(analyzed, fixed, degree, proof))
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum BackendType {
+    /// Checks all identities against the full witness, without any cryptography.
+    Mock,
+}
+
+impl BackendType {
+    pub fn backend(&self) -> Box<dyn Backend> {
+        match self {
+            BackendType::Mock => Box::new(MockBackend),
+        }
+    }
+
+    /// @returns the name used in serialized proofs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackendType::Mock => "mock",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "mock" => Ok(BackendType::Mock),
+            _ => Err(format!("Unknown backend {name}.")),
+        }
+    }
+}
+
+/// A backend without cryptography, for debugging: The proof data is the
+/// witness itself (in the format of `commits.bin`) and proving and
+/// verifying check all identities against it, reporting the violating rows.
+pub struct MockBackend;
+
+impl MockBackend {
+    fn check(
+        &self,
+        analyzed: &Analyzed,
+        fixed: &[(&str, FixedColumn)],
+        witness: &[(&str, Vec<AbstractNumberType>)],
+        degree: DegreeType,
+    ) -> Result<PublicValues, String> {
+        constraint_checker::check(analyzed, fixed, witness, degree)?;
+        Ok(PublicValues::compute(analyzed, fixed, witness, degree))
+    }
+}
+
+impl Backend for MockBackend {
+    fn prove(
+        &self,
+        analyzed: &Analyzed,
+        fixed: &[(&str, FixedColumn)],
+        witness: &[(&str, Vec<AbstractNumberType>)],
+        degree: DegreeType,
+    ) -> Result<Proof, String> {
+        let publics = self.check(analyzed, fixed, witness, degree)?;
+        let witness = Witness {
+            first_row: 0,
+            columns: witness
+                .iter()
+                .map(|(name, values)| (name.to_string(), values.clone()))
+                .collect(),
+        };
+        Ok(Proof {
+            backend: BackendType::Mock,
+            publics,
+            data: witness.to_binary(analyzed)?,
+        })
+    }
+
+    fn verify(
+        &self,
+        analyzed: &Analyzed,
+        fixed: &[(&str, FixedColumn)],
+        degree: DegreeType,
+        proof: &Proof,
+    ) -> Result<(), String> {
+        let witness = Witness::from_binary(analyzed, &proof.data)?;
+        if let Some((name, values)) = witness
+            .columns
+            .iter()
+            .find(|(_, values)| values.len() as DegreeType != degree)
+        {
+            return Err(format!(
+                "The proof contains {} rows of {name} instead of {degree}.",
+                values.len()
+            ));
+        }
+        let witness = witness
+            .columns
+            .iter()
+            .map(|(name, values)| (name.as_str(), values.clone()))
+            .collect::<Vec<_>>();
+        self.check(analyzed, fixed, &witness, degree)?
+            .check(&proof.publics)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+
+    use super::*;
+
+    #[test]
+    fn mock_verify_invalid_proofs() {
+        let analyzed = analyze_string("namespace F(4); pol commit x; x * (x - 1) = 0;").unwrap();
+        let witness = [("F.x", vec![0.into(), 1.into(), 1.into(), 0.into()])];
+        let proof = MockBackend.prove(&analyzed, &[], &witness, 4).unwrap();
+        assert_eq!(MockBackend.verify(&analyzed, &[], 4, &proof), Ok(()));
+        let mut truncated = proof.clone();
+        truncated.data.truncate(3 * 8);
+        assert_eq!(
+            MockBackend.verify(&analyzed, &[], 4, &truncated),
+            Err("The proof contains 3 rows of F.x instead of 4.".to_string())
+        );
+        truncated.data.pop();
+        assert!(MockBackend.verify(&analyzed, &[], 4, &truncated).is_err());
+        let mut tampered = proof;
+        tampered.data[0] = 2;
+        assert!(MockBackend.verify(&analyzed, &[], 4, &tampered).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use json::{object, JsonValue};
+
+use crate::number::{from_le_bytes, to_le_bytes};
+
+use super::{BackendType, PublicValues};
+
+/// Identifies the binary proof format.
+const MAGIC: &[u8; 8] = b"powdrprf";
+/// Version of the binary and the json format, to be increased on
+/// every incompatible change.
+pub const PROOF_FORMAT_VERSION: u64 = 1;
+
+/// A proof together with the public values it proves.
+/// The data is specific to the backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub backend: BackendType,
+    pub publics: PublicValues,
+    pub data: Vec<u8>,
+}
+
+impl Proof {
+    /// Encodes the proof as: the magic bytes `powdrprf`, the version,
+    /// the backend name, the number of public values followed by name
+    /// and value of each, and the length of the data followed by the data.
+    /// Numbers are 8 bytes little-endian, strings are prefixed by their length.
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        write_u64(&mut out, PROOF_FORMAT_VERSION);
+        write_bytes(&mut out, self.backend.name().as_bytes());
+        write_u64(&mut out, self.publics.0.len() as u64);
+        for (name, value) in &self.publics.0 {
+            write_bytes(&mut out, name.as_bytes());
+            out.extend(to_le_bytes(value));
+        }
+        write_bytes(&mut out, &self.data);
+        out
+    }
+
+    /// Parses the format produced by `to_binary`.
+    pub fn from_binary(contents: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader(contents);
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err("Not a proof file.".to_string());
+        }
+        check_version(reader.u64()?)?;
+        let backend = BackendType::from_name(&reader.string()?)?;
+        let publics = (0..reader.u64()?)
+            .map(|_| Ok((reader.string()?, from_le_bytes(reader.bytes(8)?))))
+            .collect::<Result<_, String>>()?;
+        let len = reader.u64()? as usize;
+        let data = reader.bytes(len)?.to_vec();
+        if !reader.0.is_empty() {
+            return Err("Unexpected data at the end of the proof file.".to_string());
+        }
+        Ok(Proof {
+            backend,
+            publics: PublicValues(publics),
+            data,
+        })
+    }
+
+    /// Formats the proof as json, the data is hex-encoded.
+    pub fn to_json(&self) -> JsonValue {
+        object! {
+            version: PROOF_FORMAT_VERSION,
+            backend: self.backend.name(),
+            publics: self.publics.to_json(),
+            data: self.data.iter().map(|b| format!("{b:02x}")).collect::<String>(),
+        }
+    }
+
+    /// Parses the format produced by `to_json`.
+    pub fn from_json(json: &JsonValue) -> Result<Self, String> {
+        check_version(json["version"].as_u64().ok_or("Missing version.")?)?;
+        let backend = BackendType::from_name(json["backend"].as_str().ok_or("Missing backend.")?)?;
+        let data = json["data"].as_str().ok_or("Missing data.")?;
+        if !data.len().is_multiple_of(2) {
+            return Err("Invalid proof data.".to_string());
+        }
+        let data = (0..data.len())
+            .step_by(2)
+            .map(|i| {
+                u8::from_str_radix(&data[i..i + 2], 16)
+                    .map_err(|_| "Invalid proof data.".to_string())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Proof {
+            backend,
+            publics: PublicValues::from_json(&json["publics"])?,
+            data,
+        })
+    }
+}
+
+fn check_version(version: u64) -> Result<(), String> {
+    if version == PROOF_FORMAT_VERSION {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unsupported proof format version {version}, expected {PROOF_FORMAT_VERSION}."
+        ))
+    }
+}
+
+fn write_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend(value.to_le_bytes());
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_u64(out, bytes.len() as u64);
+    out.extend(bytes);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < len {
+            return Err("Unexpected end of proof file.".to_string());
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.u64()? as usize;
+        String::from_utf8(self.bytes(len)?.to_vec()).map_err(|_| "Invalid string.".to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::number::GOLDILOCKS_MOD;
+
+    use super::*;
+
+    fn proof() -> Proof {
+        Proof {
+            backend: BackendType::Mock,
+            publics: PublicValues(vec![
+                ("out".to_string(), 1597.into()),
+                ("neg".to_string(), (GOLDILOCKS_MOD - 1).into()),
+            ]),
+            data: vec![0, 1, 0xff],
+        }
+    }
+
+    #[test]
+    fn binary() {
+        let binary = proof().to_binary();
+        assert_eq!(binary[..8], *b"powdrprf");
+        assert_eq!(Proof::from_binary(&binary), Ok(proof()));
+        assert_eq!(
+            Proof::from_binary(&binary[..binary.len() - 1]),
+            Err("Unexpected end of proof file.".to_string())
+        );
+        let mut future = binary;
+        future[8] = 2;
+        assert_eq!(
+            Proof::from_binary(&future),
+            Err("Unsupported proof format version 2, expected 1.".to_string())
+        );
+    }
+
+    #[test]
+    fn json() {
+        let json = proof().to_json();
+        assert_eq!(
+            json.dump(),
+            r#"{"version":1,"backend":"mock","publics":{"out":"1597","neg":"-1"},"data":"0001ff"}"#
+        );
+        assert_eq!(Proof::from_json(&json), Ok(proof()));
+    }
+}
@@ -0,0 +1,198 @@
+use json::JsonValue;
+
+use crate::analyzer::{Analyzed, Expression};
+use crate::constant_evaluator::FixedColumn;
+use crate::constraint_checker::ConstraintChecker;
+use crate::number::{
+    format_number, from_le_bytes, parse_number, to_field_element, to_le_bytes, AbstractNumberType,
+    DegreeType,
+};
+
+/// The values of the public declarations, in the order of their IDs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicValues(pub Vec<(String, AbstractNumberType)>);
+
+impl PublicValues {
+    /// Computes the public values from the fixed and witness columns.
+    pub fn compute(
+        analyzed: &Analyzed,
+        fixed: &[(&str, FixedColumn)],
+        witness: &[(&str, Vec<AbstractNumberType>)],
+        degree: DegreeType,
+    ) -> Self {
+        let checker = ConstraintChecker::from_columns(analyzed, fixed, witness, degree);
+        PublicValues(
+            analyzed
+                .public_declarations_in_id_order()
+                .into_iter()
+                .map(|public| {
+                    let value = checker.evaluate(
+                        &Expression::PolynomialReference(public.polynomial.clone()),
+                        public.index,
+                    );
+                    (public.name.clone(), value)
+                })
+                .collect(),
+        )
+    }
+
+    /// Checks that the values are equal to the expected values.
+    /// @returns an error listing all mismatching or missing values.
+    pub fn check(&self, expected: &PublicValues) -> Result<(), String> {
+        let errors = expected
+            .0
+            .iter()
+            .filter_map(
+                |(name, expected)| match self.0.iter().find(|(n, _)| n == name) {
+                    Some((_, value)) if to_field_element(value) == to_field_element(expected) => {
+                        None
+                    }
+                    Some((_, value)) => Some(format!(
+                        "Public value {name} is {}, but expected {}.",
+                        format_number(value),
+                        format_number(expected)
+                    )),
+                    None => Some(format!("Public value {name} is missing.")),
+                },
+            )
+            .chain(
+                self.0
+                    .iter()
+                    .filter(|(name, _)| !expected.0.iter().any(|(n, _)| n == name))
+                    .map(|(name, _)| format!("Unexpected public value {name}.")),
+            )
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// Formats the values as a json object from name to value (as a string).
+    pub fn to_json(&self) -> JsonValue {
+        let mut out = JsonValue::new_object();
+        for (name, value) in &self.0 {
+            out[name.as_str()] = format_number(value).into();
+        }
+        out
+    }
+
+    /// Parses the format produced by `to_json`, the values are
+    /// reduced to field elements.
+    pub fn from_json(json: &JsonValue) -> Result<Self, String> {
+        if !json.is_object() {
+            return Err("Public values have to be a json object.".to_string());
+        }
+        Ok(PublicValues(
+            json.entries()
+                .map(|(name, value)| {
+                    let value = value
+                        .as_str()
+                        .ok_or(format!("Public value {name} has to be a string."))?;
+                    let value = parse_number(value)
+                        .ok_or(format!("Invalid number for public value {name}: {value}"))?;
+                    Ok((name.to_string(), to_field_element(&value)))
+                })
+                .collect::<Result<_, String>>()?,
+        ))
+    }
+
+    /// Encodes the values as field elements in 8 little-endian bytes each.
+    pub fn to_binary(&self) -> Vec<u8> {
+        self.0.iter().flat_map(|(_, v)| to_le_bytes(v)).collect()
+    }
+
+    /// Parses the format produced by `to_binary`, the names are taken
+    /// from the public declarations.
+    pub fn from_binary(analyzed: &Analyzed, contents: &[u8]) -> Result<Self, String> {
+        let publics = analyzed.public_declarations_in_id_order();
+        if contents.len() != publics.len() * 8 {
+            return Err(format!(
+                "Expected {} public values in {} bytes, but got {} bytes.",
+                publics.len(),
+                publics.len() * 8,
+                contents.len()
+            ));
+        }
+        Ok(PublicValues(
+            publics
+                .into_iter()
+                .zip(contents.chunks(8))
+                .map(|(public, value)| (public.name.clone(), from_le_bytes(value)))
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::number::GOLDILOCKS_MOD;
+
+    use super::*;
+
+    #[test]
+    fn check_mismatches() {
+        let publics = PublicValues(vec![
+            ("a".to_string(), 1.into()),
+            ("b".to_string(), 2.into()),
+        ]);
+        let json = publics.to_json();
+        assert_eq!(json.dump(), r#"{"a":"1","b":"2"}"#);
+        assert_eq!(
+            PublicValues::from_json(&json).unwrap().check(&publics),
+            Ok(())
+        );
+        let expected = PublicValues(vec![
+            ("a".to_string(), (-1).into()),
+            ("c".to_string(), 3.into()),
+        ]);
+        assert_eq!(
+            publics.check(&expected),
+            Err("Public value a is 1, but expected -1.\nPublic value c is missing.\nUnexpected public value b.".to_string())
+        );
+    }
+
+    #[test]
+    fn round_trip_above_half_modulus() {
+        let publics = PublicValues(vec![
+            ("a".to_string(), (GOLDILOCKS_MOD - 1).into()),
+            ("b".to_string(), (GOLDILOCKS_MOD / 2 + 1).into()),
+        ]);
+        let json = publics.to_json();
+        assert_eq!(json["a"], "-1");
+        assert_eq!(PublicValues::from_json(&json), Ok(publics.clone()));
+        assert_eq!(
+            publics.check(&PublicValues(vec![
+                ("a".to_string(), (-1).into()),
+                ("b".to_string(), (GOLDILOCKS_MOD / 2 + 1).into())
+            ])),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(
+            PublicValues::from_json(&json::parse(r#"{"a":"x"}"#).unwrap()),
+            Err("Invalid number for public value a: x".to_string())
+        );
+        assert_eq!(
+            PublicValues::from_json(&json::parse(r#"{"a":1}"#).unwrap()),
+            Err("Public value a has to be a string.".to_string())
+        );
+        assert!(PublicValues::from_json(&json::parse("[]").unwrap()).is_err());
+        let analyzed = crate::analyzer::analyze_string(
+            "namespace F(2); pol commit x; x = 1; public a = x(0);",
+        )
+        .unwrap();
+        assert_eq!(
+            PublicValues::from_binary(&analyzed, &[1, 0, 0, 0, 0, 0, 0, 0]),
+            Ok(PublicValues(vec![("a".to_string(), 1.into())]))
+        );
+        assert_eq!(
+            PublicValues::from_binary(&analyzed, &[1, 0]),
+            Err("Expected 1 public values in 8 bytes, but got 2 bytes.".to_string())
+        );
+    }
+}
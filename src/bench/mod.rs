@@ -0,0 +1,200 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use json::{object, JsonValue};
+
+use crate::backend::BackendType;
+use crate::compiler::{asm_input_query, pil_input_query};
+use crate::error_codes::{self, Error};
+use crate::number::AbstractNumberType;
+use crate::{analyzer, asm_compiler, commit_evaluator, constant_evaluator, parser};
+
+/// Runs the pipeline on a .pil or .asm file without writing any columns and
+/// measures the wall time and the peak memory of each stage: parsing,
+/// compilation to PIL (only for .asm files), analysis (which parses included
+/// files again), constant evaluation, witness generation and, if a backend
+/// is given, setup and proof generation. The time spent in witness generation
+/// is further split into the machines extracted from the identities and the
+/// main evaluator.
+/// The peak memory is the maximal resident set size during the stage, which
+/// is only available on Linux and `null` otherwise.
+/// @returns the measurements as json.
+pub fn run(
+    file: &Path,
+    include_paths: &[PathBuf],
+    inputs: &[AbstractNumberType],
+    backend: Option<BackendType>,
+) -> Result<JsonValue, Error> {
+    let file_name = file.to_string_lossy();
+    let contents = fs::read_to_string(file).map_err(|e| {
+        Error::new(
+            error_codes::INVALID_INPUT,
+            format!("Could not read {file_name}: {e}"),
+        )
+    })?;
+    let is_asm = file.extension() == Some("asm".as_ref());
+    let syntax_error = |e: parser::ParseError| Error::new(error_codes::SYNTAX_ERROR, e.to_string());
+    let mut stages = vec![];
+
+    let analyzed = if is_asm {
+        measure(&mut stages, "parse", || {
+            parser::parse_asm(Some(&file_name), &contents).map(|_| ())
+        })
+        .map_err(syntax_error)?;
+        let pil = measure(&mut stages, "asm compilation", || {
+            asm_compiler::compile(Some(&file_name), &contents)
+        })
+        .map_err(syntax_error)?;
+        measure(&mut stages, "analysis", || {
+            analyzer::analyze_string(&format!("{pil}"))
+        })
+    } else {
+        measure(&mut stages, "parse", || {
+            parser::parse(Some(&file_name), &contents).map(|_| ())
+        })
+        .map_err(syntax_error)?;
+        measure(&mut stages, "analysis", || {
+            analyzer::analyze(file, include_paths)
+        })
+    }
+    .map_err(|e| Error::new(error_codes::ANALYZER_ERROR, e))?;
+
+    let (constants, degree) = measure(&mut stages, "constant evaluation", || {
+        constant_evaluator::generate(&analyzed)
+    });
+    if analyzed.constant_count() != constants.len() {
+        return Err(Error::new(
+            error_codes::UNDEFINED_FIXED_COLUMNS,
+            "Not all declared constants are defined.",
+        ));
+    }
+
+    let query_callback = |query: &str| {
+        if is_asm {
+            asm_input_query(inputs, query)
+        } else {
+            pil_input_query(inputs, query)
+        }
+    };
+    let (commits, machine_times) = measure(&mut stages, "witness generation", || {
+        commit_evaluator::generate_with_machine_times(
+            &analyzed,
+            degree,
+            &constants,
+            Some(query_callback),
+        )
+    });
+    let witgen = stages.last_mut().unwrap();
+    let total = witgen["timeMs"].as_f64().unwrap();
+    let in_machines = machine_times.iter().map(|(_, t)| millis(*t)).sum::<f64>();
+    witgen["machines"] = std::iter::once(object! { name: "main", timeMs: total - in_machines })
+        .chain(
+            machine_times
+                .iter()
+                .map(|(name, time)| object! { name: *name, timeMs: millis(*time) }),
+        )
+        .collect::<Vec<_>>()
+        .into();
+
+    if let Some(backend) = backend {
+        let backend_failure = |e| Error::new(error_codes::BACKEND_FAILURE, e);
+        let output_dir = mktemp::Temp::new_dir().map_err(|e| backend_failure(e.to_string()))?;
+        measure(&mut stages, "backend", || {
+            let backend = backend.backend();
+            backend.setup(&analyzed, &constants, degree, &output_dir);
+            backend.prove(&analyzed, &constants, &commits, degree)
+        })
+        .map_err(|e| backend_failure(format!("Proof generation failed:\n{e}")))?;
+    }
+
+    Ok(object! {
+        file: file_name.as_ref(),
+        degree: degree,
+        stages: stages,
+    })
+}
+
+/// Runs `f` and appends its wall time and peak memory to `stages`.
+fn measure<T>(stages: &mut Vec<JsonValue>, name: &str, f: impl FnOnce() -> T) -> T {
+    reset_peak_memory();
+    let start = Instant::now();
+    let result = f();
+    let time = start.elapsed();
+    stages.push(object! {
+        name: name,
+        timeMs: millis(time),
+        peakMemoryBytes: peak_memory(),
+    });
+    result
+}
+
+fn millis(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
+
+/// Resets the peak resident set size reported by `peak_memory`.
+fn reset_peak_memory() {
+    // Not supported by all kernels, in which case the peak is the one
+    // of the whole process so far.
+    let _ = fs::write("/proc/self/clear_refs", "5");
+}
+
+/// @returns the peak resident set size of the process in bytes.
+fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kilobytes = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim();
+    Some(kilobytes.parse::<u64>().ok()? * 1024)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stages_of_asm_file() {
+        let report = run(
+            Path::new("./tests/mem_read_write.asm"),
+            &[],
+            &[],
+            Some(BackendType::Mock),
+        )
+        .unwrap();
+        let names = report["stages"]
+            .members()
+            .map(|s| s["name"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "parse",
+                "asm compilation",
+                "analysis",
+                "constant evaluation",
+                "witness generation",
+                "backend"
+            ]
+        );
+        let witgen = &report["stages"][4];
+        assert_eq!(witgen["machines"][0]["name"], "main");
+        assert!(witgen["machines"]
+            .members()
+            .any(|m| m["name"] == "DoubleSortedWitnesses"));
+        assert!(witgen["machines"]
+            .members()
+            .all(|m| m["timeMs"].as_f64().unwrap() >= 0.0));
+    }
+
+    #[test]
+    fn syntax_error() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        let file = dir.join("invalid.pil");
+        fs::write(&file, "namespace Main(4);\npol commit;\n").unwrap();
+        assert!(run(&file, &[], &[], None).is_err());
+    }
+}
@@ -1,40 +1,92 @@
-use clap::{Parser, Subcommand};
-use powdr::compiler::no_callback;
-use powdr::number::AbstractNumberType;
-use std::{fs, path::Path};
+use clap::{Parser, Subcommand, ValueEnum};
+use log::{Level, LevelFilter};
+use powdr::backend::BackendType;
+use powdr::config::{self, Config};
+use powdr::error_codes::{self, Error};
+use powdr::field_fit::TargetField;
+use powdr::linter::Severity;
+use powdr::number::{parse_number, AbstractNumberType, DegreeType};
+use powdr::query_callbacks::{self, QuerySource};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Format of the log messages on stderr. The log levels can be set per
+    /// module in the RUST_LOG environment variable,
+    /// e.g. RUST_LOG=info,powdr::commit_evaluator=debug
+    #[arg(long, global = true)]
+    #[arg(value_enum)]
+    #[arg(default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum MessageFormat {
+    Human,
+    Json,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Compiles assembly to PIL and generates fixed and witness columns.
     Asm {
-        /// Input file
-        file: String,
+        /// Input file (default: entry in powdr.toml)
+        file: Option<String>,
 
         /// Comma-separated list of free inputs (numbers).
         #[arg(short, long)]
-        inputs: String,
+        inputs: Option<String>,
+
+        /// File with inputs separated by commas or newlines, instead of --inputs.
+        #[arg(long)]
+        #[arg(conflicts_with = "inputs")]
+        inputs_file: Option<String>,
+
+        /// Where the answers to the input queries come from: the inputs,
+        /// prompts on stdin or the environment variables POWDR_INPUT_<index>.
+        #[arg(long)]
+        #[arg(value_enum)]
+        #[arg(default_value_t = QuerySource::Inputs)]
+        queries: QuerySource,
 
-        /// Output directory for PIL file, json file and fixed and witness column data.
+        /// Output directory for PIL file, json file and fixed and witness column data
+        /// (default: output_directory in powdr.toml or the current directory).
         #[arg(short, long)]
-        #[arg(default_value_t = String::from("."))]
-        output_directory: String,
+        output_directory: Option<String>,
 
         /// Force overwriting of PIL output file.
         #[arg(short, long)]
         #[arg(default_value_t = false)]
         force: bool,
 
+        /// Do not remove or merge lines of the assembly program.
+        #[arg(long)]
+        #[arg(default_value_t = false)]
+        no_optimize: bool,
+
         /// Verbose output (provides a full execution trace).
         #[arg(short, long)]
         #[arg(default_value_t = false)]
         verbose: bool,
+
+        /// Generate a proof with the given backend.
+        #[arg(long)]
+        #[arg(value_enum)]
+        prove_with: Option<BackendType>,
     },
 
     /// Parses and prints the PIL file on stdout.
@@ -43,39 +95,331 @@ enum Commands {
         file: String,
     },
 
+    /// Analyzes the PIL or assembly file without generating anything
+    /// and prints all diagnostics.
+    Check {
+        /// Input file (.pil or .asm, default: entry in powdr.toml)
+        file: Option<String>,
+        /// Also generate fixed and witness columns at this (small) degree,
+        /// without writing them (default: degree in powdr.toml).
+        #[arg(long)]
+        witgen_degree: Option<u64>,
+        /// Check again every time the input file or a file it includes changes.
+        #[arg(short, long)]
+        #[arg(default_value_t = false)]
+        watch: bool,
+        /// Print diagnostics as text on stderr or as one json object per line on stdout.
+        #[arg(long)]
+        #[arg(value_enum)]
+        #[arg(default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
+    },
+
     /// Compiles the PIL file to json and generates fixed and witness columns.
+    /// Assembly files are compiled to PIL first, like with `asm --force`.
     Compile {
+        /// Input file (default: entry in powdr.toml)
+        file: Option<String>,
+        /// Output directory for json file and fixed and witness column data
+        /// (default: output_directory in powdr.toml or the current directory).
+        #[arg(short, long)]
+        output_directory: Option<String>,
+        /// Comma-separated list of inputs (numbers), the free inputs of an
+        /// assembly program or the answers to `"input", <index>` queries.
+        #[arg(short, long)]
+        inputs: Option<String>,
+        /// File with inputs separated by commas or newlines, instead of --inputs.
+        #[arg(long)]
+        #[arg(conflicts_with = "inputs")]
+        inputs_file: Option<String>,
+        /// Where the answers to the input queries come from: the inputs,
+        /// prompts on stdin or the environment variables POWDR_INPUT_<index>.
+        #[arg(long)]
+        #[arg(value_enum)]
+        #[arg(default_value_t = QuerySource::Inputs)]
+        queries: QuerySource,
+        /// Generate a proof with the given backend.
+        #[arg(long)]
+        #[arg(value_enum)]
+        prove_with: Option<BackendType>,
+        /// Compile again every time the input file or a file it includes changes.
+        #[arg(short, long)]
+        #[arg(default_value_t = false)]
+        watch: bool,
+        /// Do not remove or merge lines of an assembly program.
+        #[arg(long)]
+        #[arg(default_value_t = false)]
+        no_optimize: bool,
+    },
+
+    /// Generates the fixed columns of the PIL file and writes them to constants.bin.
+    Fixed {
+        /// Input file (default: entry in powdr.toml)
+        file: Option<String>,
+        /// Output directory for constants.bin
+        /// (default: output_directory in powdr.toml or the current directory).
+        #[arg(short, long)]
+        output_directory: Option<String>,
+    },
+
+    /// Generates the witness columns of the PIL file from the fixed columns
+    /// in constants.bin and writes them to commits.bin in the same directory.
+    Witgen {
+        /// Input file (default: entry in powdr.toml)
+        file: Option<String>,
+        /// Directory containing constants.bin, output directory for commits.bin
+        /// (default: output_directory in powdr.toml or the current directory).
+        #[arg(short, long)]
+        output_directory: Option<String>,
+        /// Comma-separated list of inputs (numbers), the free inputs of an
+        /// assembly program or the answers to `"input", <index>` queries.
+        #[arg(short, long)]
+        inputs: Option<String>,
+        /// File with inputs separated by commas or newlines, instead of --inputs.
+        #[arg(long)]
+        #[arg(conflicts_with = "inputs")]
+        inputs_file: Option<String>,
+        /// Where the answers to the input queries come from: the inputs,
+        /// prompts on stdin or the environment variables POWDR_INPUT_<index>.
+        #[arg(long)]
+        #[arg(value_enum)]
+        #[arg(default_value_t = QuerySource::Inputs)]
+        queries: QuerySource,
+        /// Csv file with values for cells that witness generation cannot derive,
+        /// in the format written by `witness`, which may contain only some rows.
+        #[arg(long)]
+        witness_hints: Option<String>,
+        /// Writes the solver metrics of each row (iterations, identities evaluated,
+        /// machine lookups, queries and time) to the file, as csv if its name
+        /// ends in .csv and as json otherwise.
+        #[arg(long)]
+        telemetry: Option<String>,
+    },
+
+    /// Proves the columns in constants.bin and commits.bin and writes
+    /// proof.bin and publics.json to the same directory.
+    Prove {
+        /// Input file (default: entry in powdr.toml)
+        file: Option<String>,
+        /// Directory containing constants.bin and commits.bin
+        /// (default: output_directory in powdr.toml or the current directory).
+        #[arg(short, long)]
+        directory: Option<String>,
+        /// Backend to generate the proof with (default: backend in powdr.toml or mock).
+        #[arg(long)]
+        #[arg(value_enum)]
+        backend: Option<BackendType>,
+    },
+
+    /// Verifies a proof for the PIL file.
+    Verify {
+        /// Input file
+        file: String,
+        /// Proof file, in json if the name ends in .json, otherwise binary.
+        #[arg(long)]
+        #[arg(default_value_t = String::from("proof.bin"))]
+        proof: String,
+        /// File with the expected public values, as json or in binary.
+        #[arg(short, long)]
+        publics: Option<String>,
+    },
+
+    /// Verifies several proofs for the PIL file, sharing the setup.
+    VerifyBatch {
+        /// Input file
+        file: String,
+        /// Proof files, in json if the name ends in .json, otherwise binary.
+        #[arg(required = true)]
+        proofs: Vec<String>,
+    },
+
+    /// Exports the PIL file to json in the format of pilcom,
+    /// without generating fixed or witness columns.
+    Export {
+        /// Input file (default: entry in powdr.toml)
+        file: Option<String>,
+        /// Output directory for the json file
+        /// (default: output_directory in powdr.toml or the current directory).
+        #[arg(short, long)]
+        output_directory: Option<String>,
+        /// Check that all constants and fixed column values fit into
+        /// the field of the backend, instead of silently wrapping
+        /// (default: field in powdr.toml).
+        #[arg(long)]
+        #[arg(value_enum)]
+        field: Option<TargetField>,
+    },
+
+    /// Generates fixed and witness columns for the PIL file and exports them,
+    /// together with the constraints, public values and the expected
+    /// verification result, as a test vector bundle.
+    Bundle {
+        /// Input file
+        file: String,
+        /// Output directory for the bundle.
+        #[arg(short, long)]
+        output_directory: String,
+    },
+
+    /// Checks a test vector bundle against the built-in constraint checker.
+    CheckBundle {
+        /// Bundle directory
+        directory: String,
+    },
+
+    /// Exports the constraints of the PIL file as SMT-LIB assertions
+    /// over the Goldilocks field, unrolled for all rows.
+    Smt {
+        /// Input file
+        file: String,
+        /// Output directory for the .smt2 file.
+        #[arg(short, long)]
+        #[arg(default_value_t = String::from("."))]
+        output_directory: String,
+    },
+
+    /// Evaluates PIL expressions interactively at a chosen row of a witness.
+    Repl {
+        /// PIL file the witness belongs to
+        file: String,
+        /// Witness file (binary, csv, json or .col), has to contain all rows.
+        witness: String,
+    },
+
+    /// Explains the value of a witness cell: what determined it during
+    /// witness generation and all identities that reference it,
+    /// with the values substituted.
+    Explain {
+        /// PIL or assembly file the witness belongs to
+        file: String,
+        /// Witness file (binary, csv, json or .col), has to contain all rows.
+        witness: String,
+        /// Name of the witness column, including the namespace.
+        column: String,
+        row: u64,
+        /// Comma-separated list of inputs (numbers), the free inputs of an
+        /// assembly program or the answers to `"input", <index>` queries
+        /// (default: inputs in powdr.toml).
+        #[arg(short, long)]
+        inputs: Option<String>,
+    },
+
+    /// Searches for a second witness with the same public values by changing
+    /// single cells of the generated witness and solving again, which points
+    /// to missing constraints.
+    Explore {
+        /// Input file (default: entry in powdr.toml)
+        file: Option<String>,
+        /// Comma-separated list of inputs (numbers), the free inputs of an
+        /// assembly program or the answers to `"input", <index>` queries
+        /// (default: inputs in powdr.toml).
+        #[arg(short, long)]
+        inputs: Option<String>,
+        /// Number of changed cells to try.
+        #[arg(long)]
+        #[arg(default_value_t = 100)]
+        trials: usize,
+        /// Seed of the choice of cells and values.
+        #[arg(long)]
+        #[arg(default_value_t = 0)]
+        seed: u64,
+        /// Also writes the search as an SMT-LIB problem for a solver with
+        /// finite field support (e.g. cvc5), which is satisfiable if there
+        /// is a second witness.
+        #[arg(long)]
+        smt: Option<String>,
+    },
+
+    /// Browses the witness and fixed columns in a terminal UI.
+    View {
+        /// PIL file the witness belongs to
+        file: String,
+        /// Witness file (binary, csv, json or .col), has to contain all rows.
+        witness: String,
+    },
+
+    /// Exports the dependency graph between columns and identities
+    /// of the PIL file in the DOT language of Graphviz.
+    Dot {
         /// Input file
         file: String,
-        /// Output directory for json file and fixed and witness column data.
+        /// Output directory for the .dot file.
         #[arg(short, long)]
         #[arg(default_value_t = String::from("."))]
         output_directory: String,
+        /// Only show the identities that reference columns of this namespace.
+        #[arg(short, long)]
+        namespace: Option<String>,
+    },
+
+    /// Runs a language server for PIL files on stdin and stdout.
+    Lsp,
+
+    /// Converts a witness between the binary format (commits.bin), csv, json
+    /// and column files (.col), determined by the file extensions.
+    Witness {
+        /// PIL file the witness belongs to
+        file: String,
+        /// Input witness file
+        input: String,
+        /// Output witness file
+        output: String,
+        /// Comma-separated list of columns to convert (default: all).
+        #[arg(short, long)]
+        columns: Option<String>,
+        /// Range of rows to convert, as `start..end` (default: all).
+        #[arg(short, long)]
+        rows: Option<String>,
+    },
+
+    /// Runs all stages on a .pil or .asm file without writing anything
+    /// and prints the wall time and peak memory of each stage as json.
+    Bench {
+        /// Input file (.pil or .asm, default: entry in powdr.toml)
+        file: Option<String>,
+        /// Comma-separated list of inputs (numbers), the free inputs of an
+        /// assembly program or the answers to `"input", <index>` queries.
+        #[arg(short, long)]
+        inputs: Option<String>,
+        /// Also measure setup and proof generation with the given backend
+        /// (default: backend in powdr.toml).
+        #[arg(long)]
+        #[arg(value_enum)]
+        backend: Option<BackendType>,
     },
 }
 
 fn main() {
-    match Cli::parse().command {
+    let cli = Cli::parse();
+    let verbose = matches!(cli.command, Commands::Asm { verbose: true, .. });
+    init_logger(cli.log_format, verbose);
+    let log_format = cli.log_format;
+    let config = load_config();
+    let include_paths = &config.include_paths;
+    match cli.command {
         Commands::Asm {
             file,
             inputs,
+            inputs_file,
+            queries,
             output_directory,
             force,
-            verbose,
+            no_optimize,
+            prove_with,
+            ..
         } => {
-            let inputs = inputs
-                .split(',')
-                .map(|x| x.trim())
-                .filter(|x| !x.is_empty())
-                .map(|x| x.parse().unwrap())
-                .collect::<Vec<AbstractNumberType>>();
-            powdr::compiler::compile_asm(
-                &file,
-                inputs,
-                Path::new(&output_directory),
+            let file = entry_file(file, &config);
+            let inputs = inputs_or_file(inputs, inputs_file, &config);
+            if let Err(error) = powdr::compiler::compile_asm(
+                file.to_str().unwrap(),
+                Some(query_callbacks::callback(queries, inputs, true)),
+                &output_dir(output_directory, &config),
                 force,
-                verbose,
-            );
+                !no_optimize,
+                prove_with.or(config.backend),
+            ) {
+                fail(error, log_format);
+            }
         }
         Commands::Reformat { file } => {
             let contents = fs::read_to_string(&file).unwrap();
@@ -84,15 +428,473 @@ fn main() {
                 Err(err) => err.output_to_stderr(),
             }
         }
+        Commands::Check {
+            file,
+            witgen_degree,
+            watch,
+            message_format,
+        } => {
+            let file = &entry_file(file, &config);
+            let witgen_degree = witgen_degree.or(config.degree);
+            let inputs = config.inputs.clone().unwrap_or_default();
+            let check = || {
+                let (diagnostics, sources) = powdr::compiler::check_file(file, include_paths);
+                for diagnostic in &diagnostics {
+                    match message_format {
+                        MessageFormat::Human => eprintln!("{diagnostic}"),
+                        MessageFormat::Json => println!("{}", diagnostic.to_json()),
+                    }
+                }
+                let mut exit_status = diagnostics
+                    .iter()
+                    .find(|d| d.severity == Severity::Error)
+                    .map(|d| error_codes::exit_status(d.code));
+                if let (None, Some(degree)) = (exit_status, witgen_degree) {
+                    if let Err(e) =
+                        powdr::compiler::witgen_smoke_test(file, include_paths, &inputs, degree)
+                    {
+                        let error = Error::new(error_codes::WITGEN_STUCK, e);
+                        match message_format {
+                            MessageFormat::Human => eprintln!("{error}"),
+                            MessageFormat::Json => println!("{}", error.to_json()),
+                        }
+                        exit_status = Some(error.exit_status());
+                    }
+                }
+                (exit_status, sources)
+            };
+            if watch {
+                powdr::compiler::watch(file, || check().1);
+            }
+            if let Some(exit_status) = check().0 {
+                std::process::exit(exit_status);
+            }
+        }
         Commands::Compile {
             file,
             output_directory,
+            inputs,
+            inputs_file,
+            queries,
+            prove_with,
+            watch,
+            no_optimize,
+        } => {
+            let file = &entry_file(file, &config);
+            let output_directory = output_dir(output_directory, &config);
+            let inputs = inputs_or_file(inputs, inputs_file, &config);
+            let prove_with = prove_with.or(config.backend);
+            let compile = || {
+                let query_callback =
+                    query_callbacks::callback(queries, inputs.clone(), is_asm(file));
+                if is_asm(file) {
+                    powdr::compiler::compile_asm(
+                        file.to_str().unwrap(),
+                        Some(query_callback),
+                        &output_directory,
+                        true,
+                        !no_optimize,
+                        prove_with,
+                    )
+                } else {
+                    powdr::compiler::compile_pil(
+                        file,
+                        include_paths,
+                        &output_directory,
+                        Some(query_callback),
+                        prove_with,
+                    )
+                }
+            };
+            if watch {
+                powdr::compiler::watch(file, || {
+                    if let Err(error) = compile() {
+                        report(&error, log_format);
+                    }
+                    powdr::compiler::check_file(file, include_paths).1
+                });
+            }
+            if let Err(error) = compile() {
+                fail(error, log_format);
+            }
+        }
+        Commands::Fixed {
+            file,
+            output_directory,
+        } => {
+            let output_directory = output_dir(output_directory, &config);
+            let file = pil_file(entry_file(file, &config), &output_directory);
+            if let Err(error) =
+                powdr::compiler::generate_fixed(&file, include_paths, &output_directory)
+            {
+                fail(error, log_format);
+            }
+        }
+        Commands::Witgen {
+            file,
+            output_directory,
+            inputs,
+            inputs_file,
+            queries,
+            witness_hints,
+            telemetry,
+        } => {
+            let output_directory = output_dir(output_directory, &config);
+            let file = entry_file(file, &config);
+            let inputs = inputs_or_file(inputs, inputs_file, &config);
+            let query_callback = query_callbacks::callback(queries, inputs, is_asm(&file));
+            if let Err(error) = powdr::compiler::generate_witness(
+                &pil_file(file.clone(), &output_directory),
+                include_paths,
+                &output_directory,
+                Some(query_callback),
+                witness_hints.as_ref().map(Path::new),
+                telemetry.as_ref().map(Path::new),
+            ) {
+                fail(error, log_format);
+            }
+        }
+        Commands::Prove {
+            file,
+            directory,
+            backend,
+        } => {
+            let directory = output_dir(directory, &config);
+            let file = pil_file(entry_file(file, &config), &directory);
+            let backend = backend.or(config.backend).unwrap_or(BackendType::Mock);
+            if let Err(error) =
+                powdr::compiler::prove_pil(&file, include_paths, &directory, backend)
+            {
+                fail(error, log_format);
+            }
+        }
+        Commands::Verify {
+            file,
+            proof,
+            publics,
+        } => {
+            match powdr::compiler::verify_pil(
+                Path::new(&file),
+                include_paths,
+                Path::new(&proof),
+                publics.as_ref().map(Path::new),
+            ) {
+                Ok(publics) => println!("Proof verified, public values: {}", publics.to_json()),
+                Err(error) => fail(error, log_format),
+            }
+        }
+        Commands::VerifyBatch { file, proofs } => {
+            let proof_files = proofs.iter().map(Path::new).collect::<Vec<_>>();
+            let results =
+                powdr::compiler::verify_pil_batch(Path::new(&file), include_paths, &proof_files)
+                    .unwrap_or_else(|error| fail(error, log_format));
+            let mut failures = 0;
+            for (proof, result) in proofs.iter().zip(results) {
+                match result {
+                    Ok(publics) => {
+                        println!("{proof}: verified, public values: {}", publics.to_json())
+                    }
+                    Err(errors) => {
+                        eprintln!("{proof}: verification failed:\n{errors}");
+                        failures += 1;
+                    }
+                }
+            }
+            if failures > 0 {
+                fail(
+                    Error::new(
+                        error_codes::VERIFICATION_FAILURE,
+                        format!(
+                            "{failures} of {} proofs could not be verified.",
+                            proofs.len()
+                        ),
+                    ),
+                    log_format,
+                );
+            }
+        }
+        Commands::Export {
+            file,
+            output_directory,
+            field,
+        } => {
+            let output_directory = output_dir(output_directory, &config);
+            let file = pil_file(entry_file(file, &config), &output_directory);
+            if let Err(error) = powdr::compiler::export_pil(
+                &file,
+                include_paths,
+                &output_directory,
+                field.or(config.field),
+            ) {
+                fail(error, log_format);
+            }
+        }
+        Commands::Bundle {
+            file,
+            output_directory,
+        } => {
+            if let Err(error) = powdr::compiler::export_bundle(
+                Path::new(&file),
+                include_paths,
+                Path::new(&output_directory),
+            ) {
+                fail(error, log_format);
+            }
+        }
+        Commands::CheckBundle { directory } => match powdr::bundle::check(Path::new(&directory)) {
+            Ok(()) => println!("Bundle behaves as expected."),
+            Err(errors) => fail(
+                Error::new(
+                    error_codes::VERIFICATION_FAILURE,
+                    format!("Bundle check failed:\n{errors}"),
+                ),
+                log_format,
+            ),
+        },
+        Commands::Smt {
+            file,
+            output_directory,
+        } => {
+            if let Err(error) = powdr::compiler::export_smt(
+                Path::new(&file),
+                include_paths,
+                Path::new(&output_directory),
+            ) {
+                fail(error, log_format);
+            }
+        }
+        Commands::Dot {
+            file,
+            output_directory,
+            namespace,
         } => {
-            powdr::compiler::compile_pil(
+            if let Err(error) = powdr::compiler::export_dot(
                 Path::new(&file),
+                include_paths,
                 Path::new(&output_directory),
-                no_callback(),
-            );
+                namespace.as_deref(),
+            ) {
+                fail(error, log_format);
+            }
         }
+        Commands::Repl { file, witness } => {
+            if let Err(error) =
+                powdr::compiler::repl(Path::new(&file), include_paths, Path::new(&witness))
+            {
+                fail(error, log_format);
+            }
+        }
+        Commands::Explore {
+            file,
+            inputs,
+            trials,
+            seed,
+            smt,
+        } => match powdr::compiler::find_second_witness(
+            &entry_file(file, &config),
+            include_paths,
+            &inputs_or_config(inputs, &config),
+            trials,
+            seed,
+            smt.as_ref().map(Path::new),
+        ) {
+            Ok(None) => println!("No second witness found in {trials} trials."),
+            Ok(Some(report)) => fail(
+                Error::new(error_codes::SECOND_WITNESS, report.trim_end()),
+                log_format,
+            ),
+            Err(error) => fail(error, log_format),
+        },
+        Commands::Explain {
+            file,
+            witness,
+            column,
+            row,
+            inputs,
+        } => match powdr::compiler::explain(
+            Path::new(&file),
+            include_paths,
+            Path::new(&witness),
+            &inputs_or_config(inputs, &config),
+            &column,
+            row,
+        ) {
+            Ok(explanation) => print!("{explanation}"),
+            Err(error) => fail(error, log_format),
+        },
+        Commands::View { file, witness } => {
+            if let Err(error) =
+                powdr::compiler::view_trace(Path::new(&file), include_paths, Path::new(&witness))
+            {
+                fail(error, log_format);
+            }
+        }
+        Commands::Lsp => {
+            if let Err(error) = powdr::language_server::run(include_paths) {
+                fail(Error::new(error_codes::IO_FAILURE, error), log_format);
+            }
+        }
+        Commands::Witness {
+            file,
+            input,
+            output,
+            columns,
+            rows,
+        } => {
+            let columns = columns.map(|c| {
+                c.split(',')
+                    .map(|x| x.trim().to_string())
+                    .collect::<Vec<_>>()
+            });
+            let rows = rows
+                .map(|r| parse_row_range(&r))
+                .transpose()
+                .unwrap_or_else(|e| fail(Error::new(error_codes::INVALID_INPUT, e), log_format));
+            if let Err(error) = powdr::compiler::convert_witness(
+                Path::new(&file),
+                include_paths,
+                Path::new(&input),
+                Path::new(&output),
+                columns.as_deref(),
+                rows,
+            ) {
+                fail(error, log_format);
+            }
+        }
+        Commands::Bench {
+            file,
+            inputs,
+            backend,
+        } => match powdr::bench::run(
+            &entry_file(file, &config),
+            include_paths,
+            &inputs_or_config(inputs, &config),
+            backend.or(config.backend),
+        ) {
+            Ok(report) => println!("{}", report.pretty(2)),
+            Err(error) => fail(error, log_format),
+        },
+    }
+}
+
+/// @returns the settings in powdr.toml in the current directory
+/// or one of its parents, or the defaults if there is none.
+fn load_config() -> Config {
+    match Config::find(&env::current_dir().unwrap()) {
+        Ok(config) => config.unwrap_or_default(),
+        Err(error) => {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// @returns the file given on the command line or the entry file in the config.
+fn entry_file(file: Option<String>, config: &Config) -> PathBuf {
+    match file.map(PathBuf::from).or_else(|| config.entry.clone()) {
+        Some(file) => file,
+        None => {
+            eprintln!("No input file given and no entry in {}.", config::FILE_NAME);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn output_dir(dir: Option<String>, config: &Config) -> PathBuf {
+    dir.map(PathBuf::from)
+        .or_else(|| config.output_directory.clone())
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn is_asm(file: &Path) -> bool {
+    file.extension() == Some("asm".as_ref())
+}
+
+/// @returns the file itself for PIL files and the PIL file generated
+/// in the output directory for assembly files.
+fn pil_file(file: PathBuf, output_dir: &Path) -> PathBuf {
+    if is_asm(&file) {
+        output_dir.join(file.with_extension("pil").file_name().unwrap())
+    } else {
+        file
+    }
+}
+
+/// Parses the comma-separated list of numbers given on the command line
+/// or returns the inputs in the config.
+fn inputs_or_config(inputs: Option<String>, config: &Config) -> Vec<AbstractNumberType> {
+    match inputs {
+        Some(inputs) => inputs
+            .split(',')
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .map(|x| parse_number(x).unwrap_or_else(|| panic!("Invalid input: {x}")))
+            .collect(),
+        None => config.inputs.clone().unwrap_or_default(),
+    }
+}
+
+/// Parses a range of rows given as `start..end`.
+fn parse_row_range(rows: &str) -> Result<Range<DegreeType>, String> {
+    let invalid = || format!("Invalid row range {rows}, expected start..end.");
+    let (start, end) = rows.split_once("..").ok_or_else(invalid)?;
+    let parse = |x: &str| x.trim().parse::<DegreeType>().map_err(|_| invalid());
+    Ok(parse(start)?..parse(end)?)
+}
+
+/// Prints the error, as json if the log format is json,
+/// and exits with the exit status of its code.
+fn fail(error: Error, log_format: LogFormat) -> ! {
+    report(&error, log_format);
+    std::process::exit(error.exit_status());
+}
+
+/// Prints the error, as json if the log format is json.
+fn report(error: &Error, log_format: LogFormat) {
+    match log_format {
+        LogFormat::Text => eprintln!("{error}"),
+        LogFormat::Json => eprintln!("{}", error.to_json()),
+    }
+}
+
+/// Reads the inputs from the file if one is given, otherwise
+/// see `inputs_or_config`.
+fn inputs_or_file(
+    inputs: Option<String>,
+    inputs_file: Option<String>,
+    config: &Config,
+) -> Vec<AbstractNumberType> {
+    match inputs_file {
+        Some(file) => query_callbacks::read_inputs(Path::new(&file)).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }),
+        None => inputs_or_config(inputs, config),
+    }
+}
+
+/// Logs at level info, unless specified differently in RUST_LOG.
+/// The verbose flag enables the full execution trace of witness generation.
+fn init_logger(format: LogFormat, verbose: bool) {
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    if verbose {
+        builder.filter_module("powdr::commit_evaluator", LevelFilter::Trace);
     }
+    match format {
+        LogFormat::Text => builder.format(|buf, record| match record.level() {
+            Level::Info => writeln!(buf, "{}", record.args()),
+            level => writeln!(buf, "{}: {}", level.as_str().to_lowercase(), record.args()),
+        }),
+        LogFormat::Json => builder.format(|buf, record| {
+            let entry = json::object! {
+                timestamp: buf.timestamp().to_string(),
+                level: record.level().as_str(),
+                target: record.target(),
+                message: record.args().to_string(),
+            };
+            writeln!(buf, "{entry}")
+        }),
+    };
+    builder.init();
 }
@@ -0,0 +1,179 @@
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+use json::object;
+
+use crate::analyzer::Analyzed;
+use crate::backend::PublicValues;
+use crate::column_file::{self, ColumnFile};
+use crate::constant_evaluator::FixedColumn;
+use crate::constraint_checker;
+use crate::number::{AbstractNumberType, DegreeType};
+use crate::{json_exporter, json_importer};
+
+/// Writes a self-contained test vector for prover implementations to the
+/// directory: the constraints in the json format of pilcom (`constraints.json`),
+/// the fixed and witness columns as column files (`fixed.col`, `witness.col`),
+/// the public values (`publics.json`) and whether the witness satisfies
+/// the constraints (`expected.json`).
+pub fn write(
+    dir: &Path,
+    analyzed: &Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    witness: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+) {
+    fs::create_dir_all(dir).unwrap();
+    json_exporter::export(analyzed)
+        .write(&mut fs::File::create(dir.join("constraints.json")).unwrap())
+        .unwrap();
+    let fixed_values = fixed
+        .iter()
+        .map(|(name, column)| (*name, column.to_values(degree)))
+        .collect::<Vec<_>>();
+    for (file, columns) in [("fixed.col", &fixed_values[..]), ("witness.col", witness)] {
+        column_file::write_columns(
+            &mut BufWriter::new(fs::File::create(dir.join(file)).unwrap()),
+            degree,
+            columns,
+        )
+        .unwrap();
+    }
+    let (result, publics) = check_columns(analyzed, fixed, witness, degree);
+    fs::write(dir.join("publics.json"), publics.to_json().pretty(2)).unwrap();
+    let expected = match result {
+        Ok(()) => object! { valid: true },
+        Err(errors) => object! { valid: false, errors: errors },
+    };
+    fs::write(dir.join("expected.json"), expected.pretty(2)).unwrap();
+}
+
+/// Reads a test vector written by `write` and checks that the witness
+/// satisfies the constraints exactly if it is expected to, and that
+/// it results in the expected public values.
+pub fn check(dir: &Path) -> Result<(), String> {
+    let read_json = |file: &str| {
+        let path = dir.join(file);
+        let contents =
+            fs::read_to_string(&path).map_err(|e| format!("Could not read {path:?}: {e}"))?;
+        json::parse(&contents).map_err(|e| format!("Invalid json in {path:?}: {e}"))
+    };
+    let analyzed = json_importer::import(&read_json("constraints.json")?)
+        .map_err(|e| format!("Invalid constraints.json: {e}"))?;
+    let read_columns = |file: &str| -> Result<_, String> {
+        let mut columns = ColumnFile::open(&dir.join(file))?;
+        let values = columns
+            .names()
+            .to_vec()
+            .into_iter()
+            .map(|name| {
+                let values = columns.column(&name)?;
+                Ok((name, values))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok((values, columns.degree()))
+    };
+    let (fixed, degree) = read_columns("fixed.col")?;
+    let (witness, witness_degree) = read_columns("witness.col")?;
+    if degree != witness_degree {
+        return Err(format!(
+            "The fixed columns have degree {degree}, but the witness columns have degree {witness_degree}."
+        ));
+    }
+    let fixed = fixed
+        .iter()
+        .map(|(name, values)| (name.as_str(), FixedColumn::Dense(values.clone())))
+        .collect::<Vec<_>>();
+    let witness = witness
+        .iter()
+        .map(|(name, values)| (name.as_str(), values.clone()))
+        .collect::<Vec<_>>();
+
+    let (result, publics) = check_columns(&analyzed, &fixed, &witness, degree);
+    let valid = read_json("expected.json")?["valid"]
+        .as_bool()
+        .ok_or("Missing validity in expected.json.")?;
+    match (valid, result) {
+        (true, Err(errors)) => return Err(format!("Expected a valid witness, but:\n{errors}")),
+        (false, Ok(())) => return Err("Expected an invalid witness.".to_string()),
+        _ => {}
+    }
+    publics.check(&PublicValues::from_json(&read_json("publics.json")?)?)
+}
+
+/// Checks the identities and computes the public values.
+fn check_columns(
+    analyzed: &Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    witness: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+) -> (Result<(), String>, PublicValues) {
+    (
+        constraint_checker::check(analyzed, fixed, witness, degree),
+        PublicValues::compute(analyzed, fixed, witness, degree),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::constant_evaluator;
+
+    use super::*;
+
+    const SRC: &str = r#"
+        namespace F(4);
+        pol constant FIRST = [1, 0, 0, 0];
+        pol commit x;
+        pol next = x + 1;
+        (1 - FIRST') * (x' - next) = 0;
+        public last = x(3);
+    "#;
+
+    fn write_bundle(dir: &Path, x: Vec<u32>) {
+        let analyzed = analyze_string(SRC).unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        let witness = vec![("F.x", x.into_iter().map(|v| v.into()).collect())];
+        write(dir, &analyzed, &fixed, &witness, degree);
+    }
+
+    #[test]
+    fn valid_bundle() {
+        let temp_dir = mktemp::Temp::new_dir().unwrap();
+        write_bundle(&temp_dir, vec![5, 6, 7, 8]);
+        assert_eq!(
+            fs::read_to_string(temp_dir.join("publics.json")).unwrap(),
+            "{\n  \"last\": \"8\"\n}"
+        );
+        assert_eq!(check(&temp_dir), Ok(()));
+    }
+
+    #[test]
+    fn invalid_bundle() {
+        let temp_dir = mktemp::Temp::new_dir().unwrap();
+        write_bundle(&temp_dir, vec![5, 6, 8, 9]);
+        let expected =
+            json::parse(&fs::read_to_string(temp_dir.join("expected.json")).unwrap()).unwrap();
+        assert_eq!(expected["valid"], false);
+        assert!(expected["errors"]
+            .as_str()
+            .unwrap()
+            .contains("is not satisfied in row 1"));
+        assert_eq!(check(&temp_dir), Ok(()));
+
+        fs::write(temp_dir.join("expected.json"), r#"{"valid": true}"#).unwrap();
+        assert!(check(&temp_dir)
+            .unwrap_err()
+            .starts_with("Expected a valid witness"));
+
+        let constraints = temp_dir.join("constraints.json");
+        let mut json = json::parse(&fs::read_to_string(&constraints).unwrap()).unwrap();
+        json["expressions"][0]["op"] = "div".into();
+        fs::write(&constraints, json.dump()).unwrap();
+        assert_eq!(
+            check(&temp_dir),
+            Err("Invalid constraints.json: Unsupported operation div.".to_string())
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::number::{from_le_bytes, to_le_bytes, AbstractNumberType, DegreeType, GOLDILOCKS_MOD};
+
+/// Identifies the column file format.
+const MAGIC: &[u8; 8] = b"powdrcol";
+const VERSION: u64 = 1;
+
+/// Writes columns to a self-describing binary container:
+/// The magic bytes `powdrcol`, the version, the field modulus, the degree,
+/// the number of columns, the name of each column (prefixed by its length
+/// and padded to a multiple of 8 bytes) and then the values column by column,
+/// each value as a field element in 8 little-endian bytes.
+/// All numbers are 8 bytes little-endian, so the data is 8-byte aligned
+/// and the file can be memory-mapped.
+pub fn write_columns(
+    file: &mut impl Write,
+    degree: DegreeType,
+    columns: &[(&str, Vec<AbstractNumberType>)],
+) -> std::io::Result<()> {
+    file.write_all(MAGIC)?;
+    for value in [VERSION, GOLDILOCKS_MOD, degree, columns.len() as u64] {
+        file.write_all(&value.to_le_bytes())?;
+    }
+    for (name, values) in columns {
+        assert_eq!(
+            values.len() as DegreeType,
+            degree,
+            "Column {name} does not have {degree} values."
+        );
+        file.write_all(&(name.len() as u64).to_le_bytes())?;
+        file.write_all(name.as_bytes())?;
+        file.write_all(&vec![0; padding(name.len())])?;
+    }
+    for (_, values) in columns {
+        for value in values {
+            file.write_all(&to_le_bytes(value))?;
+        }
+    }
+    Ok(())
+}
+
+/// A column file (see `write_columns`) opened for random access,
+/// only the header is read when opening.
+pub struct ColumnFile {
+    file: File,
+    names: Vec<String>,
+    degree: DegreeType,
+    data_offset: u64,
+}
+
+impl ColumnFile {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("Could not open {path:?}: {e}"))?;
+        let read_u64 = |file: &mut File| -> Result<u64, String> {
+            let mut buf = [0; 8];
+            file.read_exact(&mut buf)
+                .map_err(|e| format!("Invalid column file {path:?}: {e}"))?;
+            Ok(u64::from_le_bytes(buf))
+        };
+        if read_u64(&mut file)?.to_le_bytes() != *MAGIC {
+            return Err(format!("{path:?} is not a column file."));
+        }
+        let version = read_u64(&mut file)?;
+        if version != VERSION {
+            return Err(format!(
+                "Unsupported column file version {version}, expected {VERSION}."
+            ));
+        }
+        let modulus = read_u64(&mut file)?;
+        if modulus != GOLDILOCKS_MOD {
+            return Err(format!(
+                "Column file {path:?} is for the field of size {modulus}, expected {GOLDILOCKS_MOD}."
+            ));
+        }
+        let degree = read_u64(&mut file)?;
+        let file_len = file.metadata().unwrap().len();
+        // The lengths in the header are checked against the file size before
+        // anything is allocated, so that invalid files cannot exhaust the memory.
+        let remaining = |file: &mut File| file_len.saturating_sub(file.stream_position().unwrap());
+        let column_count = read_u64(&mut file)?;
+        if column_count > remaining(&mut file) / 8 {
+            return Err(format!("Invalid column count in {path:?}."));
+        }
+        let names = (0..column_count)
+            .map(|_| {
+                let len = read_u64(&mut file)?;
+                if len > remaining(&mut file) {
+                    return Err(format!("Invalid column name length in {path:?}."));
+                }
+                let len = len as usize;
+                let mut name = vec![0; len + padding(len)];
+                file.read_exact(&mut name)
+                    .map_err(|e| format!("Invalid column file {path:?}: {e}"))?;
+                name.truncate(len);
+                String::from_utf8(name).map_err(|_| format!("Invalid column name in {path:?}."))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let data_offset = file.stream_position().unwrap();
+        let expected_len = (names.len() as u64)
+            .checked_mul(degree)
+            .and_then(|values| values.checked_mul(8))
+            .and_then(|bytes| bytes.checked_add(data_offset));
+        if expected_len != Some(file_len) {
+            return Err(match expected_len {
+                Some(expected_len) => format!(
+                    "Column file {path:?} does not have the expected size of {expected_len} bytes."
+                ),
+                None => format!("Invalid degree in {path:?}."),
+            });
+        }
+        Ok(ColumnFile {
+            file,
+            names,
+            degree,
+            data_offset,
+        })
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn degree(&self) -> DegreeType {
+        self.degree
+    }
+
+    /// @returns the value of the column in the given row.
+    pub fn value(&mut self, name: &str, row: DegreeType) -> Result<AbstractNumberType, String> {
+        if row >= self.degree {
+            return Err(format!("Row {row} out of range."));
+        }
+        let mut buf = [0; 8];
+        self.read_at(name, row, &mut buf)?;
+        Ok(from_le_bytes(&buf))
+    }
+
+    /// @returns all values of the column.
+    pub fn column(&mut self, name: &str) -> Result<Vec<AbstractNumberType>, String> {
+        let mut buf = vec![0; self.degree as usize * 8];
+        self.read_at(name, 0, &mut buf)?;
+        Ok(buf.chunks(8).map(from_le_bytes).collect())
+    }
+
+    /// Reads the values of the column starting at the given row into the buffer.
+    fn read_at(&mut self, name: &str, row: DegreeType, buf: &mut [u8]) -> Result<(), String> {
+        let index = self
+            .names
+            .iter()
+            .position(|n| n == name)
+            .ok_or_else(|| format!("Column {name} not found."))?;
+        let offset = self.data_offset + (index as u64 * self.degree + row) * 8;
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.file.read_exact(buf))
+            .map_err(|e| format!("Could not read column {name}: {e}"))
+    }
+}
+
+/// @returns the number of bytes needed to pad `len` to a multiple of 8.
+fn padding(len: usize) -> usize {
+    (8 - len % 8) % 8
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn write_and_read() {
+        let temp_dir = mktemp::Temp::new_dir().unwrap();
+        let path = temp_dir.join("columns.col");
+        let columns = vec![
+            ("F.x", vec![1.into(), 2.into(), 3.into()]),
+            ("Long.name", vec![4.into(), (-1).into(), 6.into()]),
+        ];
+        write_columns(&mut File::create(&path).unwrap(), 3, &columns).unwrap();
+        // Header: 5 numbers, two names of 3 and 9 bytes with padding and length.
+        assert_eq!(
+            std::fs::metadata(&path).unwrap().len(),
+            5 * 8 + (8 + 8) + (8 + 16) + 6 * 8
+        );
+
+        let mut file = ColumnFile::open(&path).unwrap();
+        assert_eq!(file.names(), ["F.x", "Long.name"]);
+        assert_eq!(file.degree(), 3);
+        assert_eq!(file.value("Long.name", 1), Ok((GOLDILOCKS_MOD - 1).into()));
+        assert_eq!(file.value("F.x", 2), Ok(3.into()));
+        assert_eq!(file.column("F.x"), Ok(columns[0].1.clone()));
+        assert_eq!(file.value("F.x", 3), Err("Row 3 out of range.".to_string()));
+        assert_eq!(file.column("F.y"), Err("Column F.y not found.".to_string()));
+    }
+
+    #[test]
+    fn invalid_file() {
+        let temp_dir = mktemp::Temp::new_dir().unwrap();
+        let path = temp_dir.join("columns.col");
+        let columns = vec![("F.x", vec![1.into(), 2.into()])];
+        let mut contents = vec![];
+        write_columns(&mut contents, 2, &columns).unwrap();
+        std::fs::write(&path, &contents[..contents.len() - 1]).unwrap();
+        assert!(ColumnFile::open(&path)
+            .err()
+            .unwrap()
+            .ends_with("does not have the expected size of 72 bytes."));
+        std::fs::write(&path, b"something else").unwrap();
+        assert!(ColumnFile::open(&path)
+            .err()
+            .unwrap()
+            .ends_with("is not a column file."));
+    }
+
+    #[test]
+    fn invalid_header() {
+        let temp_dir = mktemp::Temp::new_dir().unwrap();
+        let path = temp_dir.join("columns.col");
+        let header = |degree: u64, columns: u64, name_len: u64| {
+            [
+                u64::from_le_bytes(*MAGIC),
+                VERSION,
+                GOLDILOCKS_MOD,
+                degree,
+                columns,
+                name_len,
+            ]
+            .into_iter()
+            .flat_map(|v| v.to_le_bytes())
+            .chain(*b"F.x\0\0\0\0\0")
+            .collect::<Vec<_>>()
+        };
+        let error = |contents: Vec<u8>| {
+            std::fs::write(&path, contents).unwrap();
+            ColumnFile::open(&path).err().unwrap()
+        };
+        assert!(error(header(1, u64::MAX, 3)).contains("Invalid column count"));
+        assert!(error(header(1, 1, u64::MAX - 4)).contains("Invalid column name length"));
+        assert!(error(header(u64::MAX, 1, 3)).contains("Invalid degree"));
+    }
+}
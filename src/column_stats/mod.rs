@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+
+use crate::number::{format_number, is_zero, to_field_element, to_signed, AbstractNumberType};
+
+/// Statistics about the values of a column, which help to spot
+/// columns that are constant or unused, are missing a range constraint
+/// or could be stored more compactly.
+#[derive(Debug, PartialEq)]
+pub struct ColumnStats {
+    pub name: String,
+    /// Smallest value, where field elements above half the modulus are negative.
+    pub min: AbstractNumberType,
+    /// Largest value, where field elements above half the modulus are negative.
+    pub max: AbstractNumberType,
+    pub distinct: usize,
+    /// Fraction of the rows that are zero.
+    pub zeros: f64,
+    /// Number of bits needed to store every value as a field element,
+    /// which is 64 as soon as there is a negative value.
+    pub bit_width: u64,
+}
+
+impl ColumnStats {
+    pub fn new(name: &str, values: &[AbstractNumberType]) -> ColumnStats {
+        let signed = values.iter().map(to_signed).collect::<Vec<_>>();
+        ColumnStats {
+            name: name.to_string(),
+            min: signed.iter().min().cloned().unwrap_or_default(),
+            max: signed.iter().max().cloned().unwrap_or_default(),
+            distinct: signed.iter().collect::<HashSet<_>>().len(),
+            zeros: if values.is_empty() {
+                0.0
+            } else {
+                values.iter().filter(|v| is_zero(v)).count() as f64 / values.len() as f64
+            },
+            bit_width: values
+                .iter()
+                .map(|v| to_field_element(v).bits())
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// @returns the statistics of all columns.
+pub fn compute(columns: &[(&str, Vec<AbstractNumberType>)]) -> Vec<ColumnStats> {
+    columns
+        .iter()
+        .map(|(name, values)| ColumnStats::new(name, values))
+        .collect()
+}
+
+/// Formats the statistics as a table with one line per column.
+pub struct Table<'a>(pub &'a [ColumnStats]);
+
+impl Display for Table<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let rows = self
+            .0
+            .iter()
+            .map(|s| {
+                [
+                    s.name.clone(),
+                    format_number(&s.min),
+                    format_number(&s.max),
+                    s.distinct.to_string(),
+                    format!("{:.1}%", s.zeros * 100.0),
+                    s.bit_width.to_string(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let header = ["column", "min", "max", "distinct", "zeros", "bits"].map(String::from);
+        let widths = (0..header.len())
+            .map(|i| {
+                rows.iter()
+                    .chain([&header])
+                    .map(|r| r[i].len())
+                    .max()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        for row in [&header].into_iter().chain(&rows) {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(i, (cell, width))| {
+                    // The name is aligned to the left, the numbers to the right.
+                    if i == 0 {
+                        format!("{cell:<width$}")
+                    } else {
+                        format!("{cell:>width$}")
+                    }
+                });
+            writeln!(f, "{}", cells.collect::<Vec<_>>().join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn column(values: &[i64]) -> Vec<AbstractNumberType> {
+        values
+            .iter()
+            .map(|v| to_field_element(&(*v).into()))
+            .collect()
+    }
+
+    #[test]
+    fn statistics() {
+        let stats = ColumnStats::new("Main.x", &column(&[0, 3, 0, 255]));
+        assert_eq!(stats.min, 0.into());
+        assert_eq!(stats.max, 255.into());
+        assert_eq!(stats.distinct, 3);
+        assert_eq!(stats.zeros, 0.5);
+        assert_eq!(stats.bit_width, 8);
+
+        let stats = ColumnStats::new("Main.y", &column(&[-1, 1]));
+        assert_eq!(stats.min, (-1).into());
+        assert_eq!(stats.max, 1.into());
+        assert_eq!(stats.bit_width, 64);
+    }
+
+    #[test]
+    fn table() {
+        let stats = compute(&[
+            ("Main.first", column(&[1, 0, 0, 0])),
+            ("Main.counter", column(&[0, 1, 2, 1000])),
+        ]);
+        assert_eq!(
+            Table(&stats).to_string(),
+            "column        min   max  distinct  zeros  bits\n\
+             Main.first      0     1         2  75.0%     1\n\
+             Main.counter    0  1000         4  25.0%    10\n"
+        );
+    }
+}
@@ -1,5 +1,8 @@
 // TODO this should probably rather be a finite field element.
-use crate::number::{format_number, is_zero, AbstractNumberType, GOLDILOCKS_MOD};
+use crate::number::{
+    abstract_to_degree, format_number, goldilocks_inv, goldilocks_mul, is_zero, AbstractNumberType,
+    GOLDILOCKS_MOD,
+};
 
 use super::util::WitnessColumnNamer;
 
@@ -63,9 +66,9 @@ impl AffineExpression {
     pub fn mul(mut self, factor: AbstractNumberType) -> AffineExpression {
         let fac = clamp(factor);
         for f in &mut self.coefficients {
-            *f = clamp(f.clone() * fac.clone());
+            *f = mul(f, &fac);
         }
-        self.offset = clamp(self.offset.clone() * fac);
+        self.offset = mul(&self.offset, &fac);
         self
     }
 
@@ -82,12 +85,7 @@ impl AffineExpression {
                 } else if *c == (-1).into() || *c == (GOLDILOCKS_MOD - 1).into() {
                     Some((i, self.offset.clone()))
                 } else {
-                    Some((
-                        i,
-                        clamp(-clamp(
-                            self.offset.clone() * inv(c.clone(), GOLDILOCKS_MOD.into()),
-                        )),
-                    ))
+                    Some((i, clamp(-mul(&self.offset, &inv(c)))))
                 }
             } else {
                 None
@@ -112,41 +110,28 @@ impl AffineExpression {
                     format!("{} * {name}", format_number(c))
                 }
             })
-            .chain(self.constant_value().map(|v| format!("{v}")))
+            .chain(self.constant_value().map(|v| format_number(&v)))
             .collect::<Vec<_>>()
             .join(" + ")
     }
 }
 
-fn clamp(mut x: AbstractNumberType) -> AbstractNumberType {
-    while x < 0.into() {
-        x += GOLDILOCKS_MOD
+fn clamp(x: AbstractNumberType) -> AbstractNumberType {
+    let x = x % GOLDILOCKS_MOD;
+    if x < 0.into() {
+        x + GOLDILOCKS_MOD
+    } else {
+        x
     }
-    x % GOLDILOCKS_MOD
 }
 
-fn pow(
-    mut x: AbstractNumberType,
-    mut y: AbstractNumberType,
-    m: AbstractNumberType,
-) -> AbstractNumberType {
-    assert!(y >= 0.into());
-    if y == 0.into() {
-        return 1.into();
-    }
-    let mut r: AbstractNumberType = 1.into();
-    while y >= 2.into() {
-        if y.bit(0) {
-            r = (r * x.clone()) % m.clone();
-        }
-        x = (x.clone() * x) % m.clone();
-        y = y.clone() >> 1;
-    }
-    (r * x) % m
+/// Multiplies two clamped values using fast modular arithmetic on u64.
+fn mul(x: &AbstractNumberType, y: &AbstractNumberType) -> AbstractNumberType {
+    goldilocks_mul(abstract_to_degree(x), abstract_to_degree(y)).into()
 }
 
-fn inv(x: AbstractNumberType, m: AbstractNumberType) -> AbstractNumberType {
-    pow(x, m.clone() - 2, m)
+fn inv(x: &AbstractNumberType) -> AbstractNumberType {
+    goldilocks_inv(abstract_to_degree(x)).into()
 }
 
 impl PartialEq for AffineExpression {
@@ -195,11 +180,19 @@ impl std::ops::Sub for AffineExpression {
 
 #[cfg(test)]
 mod test {
-    use super::*;
     use crate::number::AbstractNumberType;
 
+    use super::super::util::WitnessColumnNamer;
     use super::{AffineExpression, GOLDILOCKS_MOD};
 
+    struct Namer;
+
+    impl WitnessColumnNamer for Namer {
+        fn name(&self, i: usize) -> String {
+            format!("w{i}")
+        }
+    }
+
     fn convert(input: Vec<i32>) -> Vec<AbstractNumberType> {
         input.into_iter().map(|x| x.into()).collect()
     }
@@ -244,19 +237,16 @@ mod test {
     }
 
     #[test]
-    pub fn mod_arith() {
-        assert_eq!(pow(7.into(), 0.into(), GOLDILOCKS_MOD.into()), 1.into());
-        assert_eq!(pow(7.into(), 1.into(), GOLDILOCKS_MOD.into()), 7.into());
-        assert_eq!(
-            pow(7.into(), 2.into(), GOLDILOCKS_MOD.into()),
-            (7 * 7).into()
-        );
-        assert_eq!(inv(1.into(), GOLDILOCKS_MOD.into()), 1.into());
-        let inverse_of_four = 13835058052060938241u64;
-        assert_eq!(inv(4.into(), GOLDILOCKS_MOD.into()), inverse_of_four.into());
-        assert_eq!(
-            (4u128 * inverse_of_four as u128) % GOLDILOCKS_MOD as u128,
-            1
-        );
+    pub fn test_affine_format() {
+        let a = AffineExpression {
+            coefficients: convert(vec![1, -1, 2]),
+            offset: 0.into(),
+        };
+        assert_eq!(a.format(&Namer), "w0 + -w1 + 2 * w2");
+        let constant = AffineExpression {
+            coefficients: vec![],
+            offset: (GOLDILOCKS_MOD - 3).into(),
+        };
+        assert_eq!(constant.format(&Namer), "-3");
     }
 }
@@ -63,6 +63,10 @@ impl DoubleSortedWitnesses {
 }
 
 impl Machine for DoubleSortedWitnesses {
+    fn name(&self) -> &'static str {
+        "DoubleSortedWitnesses"
+    }
+
     fn process_plookup(
         &mut self,
         fixed_data: &FixedData,
@@ -124,7 +128,7 @@ impl Machine for DoubleSortedWitnesses {
             )
         })?;
 
-        println!(
+        log::trace!(
             "Query addr={addr}, step={step}, write: {is_write}, left: {}",
             left[2].format(fixed_data)
         );
@@ -136,9 +140,7 @@ impl Machine for DoubleSortedWitnesses {
                 Some(v) => v,
                 None => return Ok(LookupReturn::Assignments(vec![])),
             };
-            if fixed_data.verbose {
-                println!("Memory write: addr={addr}, step={step}, value={value}");
-            }
+            log::trace!("Memory write: addr={addr}, step={step}, value={value}");
             self.data.insert(addr.clone(), value.clone());
             self.trace
                 .insert((addr, step), Operation { is_write, value });
@@ -151,9 +153,7 @@ impl Machine for DoubleSortedWitnesses {
                     value: value.clone(),
                 },
             );
-            if fixed_data.verbose {
-                println!("Memory read: addr={addr}, step={step}, value={value}");
-            }
+            log::trace!("Memory read: addr={addr}, step={step}, value={value}");
             assignments.push(match (left[2].clone() - value.clone().into()).solve() {
                 Some(ass) => ass,
                 None => return Ok(LookupReturn::Assignments(vec![])),
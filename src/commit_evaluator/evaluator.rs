@@ -2,15 +2,17 @@ use crate::analyzer::{Expression, Identity, IdentityKind};
 use crate::number::format_number;
 use crate::utils::indent;
 use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
 // TODO should use finite field instead of abstract number
-use crate::number::{AbstractNumberType, DegreeType};
+use crate::number::{to_u64, AbstractNumberType, DegreeType, GOLDILOCKS_MOD};
+use num_bigint::Sign;
 
 use super::affine_expression::AffineExpression;
 use super::eval_error::EvalError;
 use super::expression_evaluator::{ExpressionEvaluator, SymbolicVariables};
 use super::machine::{LookupReturn, Machine};
 use super::util::contains_next_ref;
-use super::{EvalResult, FixedData, WitnessColumn};
+use super::{EvalResult, FixedData, Provenance, RowMetrics, WitnessColumn};
 
 pub struct Evaluator<'a, QueryCallback>
 where
@@ -19,6 +21,8 @@ where
     fixed_data: &'a FixedData<'a>,
     identities: Vec<&'a Identity>,
     machines: Vec<Box<dyn Machine>>,
+    /// Time spent inside each machine.
+    machine_times: Vec<Duration>,
     query_callback: Option<QueryCallback>,
     /// Maps the witness polynomial names to optional parameter and query string.
     witness_cols: BTreeMap<&'a str, &'a WitnessColumn<'a>>,
@@ -26,9 +30,19 @@ where
     current: Vec<Option<AbstractNumberType>>,
     /// Values of the witness polynomials in the next row
     next: Vec<Option<AbstractNumberType>>,
+    /// What determined the values of the witness polynomials in the current row
+    current_provenance: Vec<Option<Provenance<'a>>>,
+    /// What determined the values of the witness polynomials in the next row
+    next_provenance: Vec<Option<Provenance<'a>>>,
     next_row: DegreeType,
     failure_reasons: Vec<String>,
     progress: bool,
+    /// Values of witness polynomials per row that are used if they cannot be derived.
+    hints: HashMap<DegreeType, Vec<(usize, AbstractNumberType)>>,
+    /// Values of witness polynomials per row that are set before solving the row.
+    overrides: HashMap<DegreeType, Vec<(usize, AbstractNumberType)>>,
+    /// Solver metrics of each computed row.
+    row_metrics: Vec<RowMetrics>,
 }
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -54,19 +68,50 @@ where
         Evaluator {
             fixed_data,
             identities,
+            machine_times: vec![Duration::ZERO; machines.len()],
             machines,
             query_callback,
             witness_cols: witness_cols.iter().map(|p| (p.name, p)).collect(),
             current: vec![None; witness_cols.len()],
             next: vec![None; witness_cols.len()],
+            current_provenance: vec![None; witness_cols.len()],
+            next_provenance: vec![None; witness_cols.len()],
             next_row: 0,
             failure_reasons: vec![],
             progress: true,
+            hints: Default::default(),
+            overrides: Default::default(),
+            row_metrics: vec![],
         }
     }
 
+    /// Sets values of witness polynomials per row, which are used for the
+    /// cells whose values cannot be derived from the identities and queries.
+    pub fn set_hints(&mut self, hints: HashMap<DegreeType, Vec<(usize, AbstractNumberType)>>) {
+        self.hints = hints;
+    }
+
+    /// Sets values of witness polynomials per row, which are set before
+    /// anything is derived, so that the identities and queries have to
+    /// agree with them.
+    pub fn set_overrides(
+        &mut self,
+        overrides: HashMap<DegreeType, Vec<(usize, AbstractNumberType)>>,
+    ) {
+        self.overrides = overrides;
+    }
+
     pub fn compute_next_row(&mut self, next_row: DegreeType) -> Vec<AbstractNumberType> {
         self.next_row = next_row;
+        let start = Instant::now();
+        self.row_metrics.push(RowMetrics {
+            row: next_row,
+            ..Default::default()
+        });
+        for (id, value) in self.overrides.get(&next_row).into_iter().flatten() {
+            self.next[*id] = Some(value.clone());
+            self.next_provenance[*id] = Some(Provenance::Hint);
+        }
 
         // TODO maybe better to generate a dependency graph than looping multiple times.
         // TODO at least we could cache the affine expressions between loops.
@@ -76,9 +121,11 @@ where
             identity_failed = false;
             self.progress = false;
             self.failure_reasons.clear();
+            self.metrics().iterations += 1;
 
             // TODO avoid clone
             for identity in &self.identities.clone() {
+                self.metrics().identities_evaluated += 1;
                 let result = match identity.kind {
                     IdentityKind::Polynomial => {
                         self.process_polynomial_identity(identity.left.selector.as_ref().unwrap())
@@ -98,7 +145,7 @@ where
                 if result.is_err() {
                     identity_failed = true;
                 }
-                self.handle_eval_result(result);
+                self.handle_eval_result(result, Provenance::Identity(identity));
             }
             if self.query_callback.is_some() {
                 // TODO avoid clone
@@ -106,49 +153,57 @@ where
                     // TOOD we should acutally query even if it is already known, to check
                     // if the value would be different.
                     if !self.has_known_next_value(column.id) && column.query.is_some() {
+                        self.metrics().queries += 1;
                         let result = self.process_witness_query(column);
-                        self.handle_eval_result(result)
+                        self.handle_eval_result(result, Provenance::Query)
                     }
                 }
             }
-            if !self.progress {
+            if !self.progress && !self.apply_hints() {
                 break;
             }
             if self.next.iter().all(|v| v.is_some()) {
                 break;
             }
         }
+        self.metrics().time = start.elapsed();
         // Identity check failure on the first row is not fatal. We will proceed with
         // "unknown", report zero and re-check the wrap-around against the zero values at the end.
-        if identity_failed && next_row != 0 {
-            eprintln!(
-                "\nError: Row {next_row}: Identity check failer or unable to derive values for witness polynomials: {}\n",
-                self.next
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, v)| if v.is_none() {
-                        Some(self.fixed_data.witness_cols[i].name.to_string())
-                    } else {
-                        None
-                    })
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            );
-            eprintln!("Reasons:\n{}\n", self.failure_reasons.join("\n\n"));
-            eprintln!(
-                "Current values:\n{}",
-                indent(&self.format_next_values().join("\n"), "    ")
-            );
-            panic!();
-        } else {
-            if self.fixed_data.verbose {
-                println!(
-                    "===== Row {next_row}:\n{}",
+        // If all values are known but hints were used, the hints contradict the identities,
+        // which is reported after witness generation.
+        let contradicting_hints = self.next.iter().all(|v| v.is_some())
+            && self
+                .next_provenance
+                .iter()
+                .any(|p| matches!(p, Some(Provenance::Hint)));
+        if identity_failed && next_row != 0 && !contradicting_hints {
+            if !super::is_silent() {
+                log::error!(
+                    "Row {next_row}: Identity check failer or unable to derive values for witness polynomials: {}\nReasons:\n{}\nCurrent values:\n{}",
+                    self.next
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, v)| if v.is_none() {
+                            Some(self.fixed_data.witness_cols[i].name.to_string())
+                        } else {
+                            None
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    self.failure_reasons.join("\n\n"),
                     indent(&self.format_next_values().join("\n"), "    ")
                 );
             }
+            panic!("Witness generation failed in row {next_row}.");
+        } else {
+            log::debug!(
+                "===== Row {next_row}:\n{}",
+                indent(&self.format_next_values().join("\n"), "    ")
+            );
             std::mem::swap(&mut self.next, &mut self.current);
             self.next = vec![None; self.current.len()];
+            std::mem::swap(&mut self.next_provenance, &mut self.current_provenance);
+            self.next_provenance = vec![None; self.current.len()];
             // TODO check a bit better that "None" values do not
             // violate constraints.
             self.current
@@ -158,12 +213,53 @@ where
         }
     }
 
+    /// @returns what determined the value of each witness polynomial
+    /// in the row computed last.
+    pub fn current_provenance(&self) -> &[Option<Provenance<'a>>] {
+        &self.current_provenance
+    }
+
     pub fn machine_witness_col_values(&mut self) -> HashMap<String, Vec<AbstractNumberType>> {
-        let mut result: HashMap<_, _> = Default::default();
-        for m in &mut self.machines {
-            result.extend(m.witness_col_values(self.fixed_data));
-        }
-        result
+        self.witness_col_values_by_machine()
+            .into_iter()
+            .flat_map(|(_, values)| values)
+            .collect()
+    }
+
+    /// @returns the name of each machine and the values of the witness
+    /// columns it sets.
+    pub fn witness_col_values_by_machine(
+        &mut self,
+    ) -> Vec<(&'static str, HashMap<String, Vec<AbstractNumberType>>)> {
+        self.machines
+            .iter_mut()
+            .zip(&mut self.machine_times)
+            .map(|(m, time)| {
+                let start = Instant::now();
+                let values = m.witness_col_values(self.fixed_data);
+                *time += start.elapsed();
+                (m.name(), values)
+            })
+            .collect()
+    }
+
+    /// @returns the solver metrics of each computed row, in the order
+    /// in which the rows were computed.
+    pub fn row_metrics(&self) -> &[RowMetrics] {
+        &self.row_metrics
+    }
+
+    /// @returns the name of each machine and the time spent inside it so far.
+    pub fn machine_times(&self) -> Vec<(&'static str, Duration)> {
+        self.machines
+            .iter()
+            .map(|m| m.name())
+            .zip(self.machine_times.iter().cloned())
+            .collect()
+    }
+
+    fn metrics(&mut self) -> &mut RowMetrics {
+        self.row_metrics.last_mut().unwrap()
     }
 
     fn format_next_values(&self) -> Vec<String> {
@@ -188,7 +284,20 @@ where
     ) -> Result<Vec<(usize, AbstractNumberType)>, EvalError> {
         let query = self.interpolate_query(column.query.unwrap())?;
         if let Some(value) = self.query_callback.as_mut().and_then(|c| (c)(&query)) {
-            Ok(vec![(column.id, value)])
+            // Negative answers denote the field element `GOLDILOCKS_MOD + value`.
+            let canonical = if value.sign() == Sign::Minus {
+                &value + GOLDILOCKS_MOD
+            } else {
+                value.clone()
+            };
+            match to_u64(&canonical) {
+                Ok(v) if v < GOLDILOCKS_MOD => Ok(vec![(column.id, v.into())]),
+                _ => Err(format!(
+                    "Query answer {value} for {} query {query} is not a field element.",
+                    column.name
+                )
+                .into()),
+            }
         } else {
             Err(format!("No query answer for {} query: {query}.", column.name).into())
         }
@@ -274,11 +383,13 @@ where
         // Note that we should always query all machines that match, because they might
         // update their internal data, even if all values are already known.
         // TODO could it be that multiple machines match?
-        for m in &mut self.machines {
+        for (m, time) in self.machines.iter_mut().zip(&mut self.machine_times) {
             // TODO also consider the reasons above.
-            if let LookupReturn::Assignments(assignments) =
-                m.process_plookup(self.fixed_data, identity.kind, &left, &identity.right)?
-            {
+            self.row_metrics.last_mut().unwrap().machine_lookups += 1;
+            let start = Instant::now();
+            let result = m.process_plookup(self.fixed_data, identity.kind, &left, &identity.right);
+            *time += start.elapsed();
+            if let LookupReturn::Assignments(assignments) = result? {
                 return Ok(assignments);
             }
         }
@@ -288,10 +399,13 @@ where
             .into())
     }
 
-    fn handle_eval_result(&mut self, result: EvalResult) {
+    fn handle_eval_result(&mut self, result: EvalResult, provenance: Provenance<'a>) {
         match result {
             Ok(assignments) => {
                 for (id, value) in assignments {
+                    if self.next[id].is_none() {
+                        self.next_provenance[id] = Some(provenance.clone());
+                    }
                     self.next[id] = Some(value);
                     self.progress = true;
                 }
@@ -302,6 +416,20 @@ where
         }
     }
 
+    /// Sets the unknown values of the next row for which there are hints.
+    /// @returns true if there was such a value.
+    fn apply_hints(&mut self) -> bool {
+        let mut applied = false;
+        for (id, value) in self.hints.get(&self.next_row).into_iter().flatten() {
+            if self.next[*id].is_none() {
+                self.next[*id] = Some(value.clone());
+                self.next_provenance[*id] = Some(Provenance::Hint);
+                applied = true;
+            }
+        }
+        applied
+    }
+
     fn has_known_next_value(&self, id: usize) -> bool {
         self.next[id].is_some()
     }
@@ -374,7 +502,7 @@ impl<'a> SymbolicVariables for EvaluationData<'a> {
         } else {
             // Constant polynomial (or something else)
             let values = self.fixed_data.fixed_cols[name];
-            let degree = values.len() as DegreeType;
+            let degree = self.fixed_data.degree;
             let mut row = match self.evaluate_row {
                 EvaluationRow::Current => (self.next_row + degree - 1) % degree,
                 EvaluationRow::Next => self.next_row,
@@ -382,7 +510,7 @@ impl<'a> SymbolicVariables for EvaluationData<'a> {
             if next {
                 row = (row + 1) % degree;
             }
-            Ok(values[row as usize].clone().into())
+            Ok(values.get(row).into())
         }
     }
 
@@ -1,5 +1,5 @@
-use crate::analyzer::{BinaryOperator, Expression, UnaryOperator};
-use crate::number::abstract_to_degree;
+use crate::analyzer::{evaluator, BinaryOperator, Expression, UnaryOperator};
+use crate::number::{abstract_to_degree, is_zero};
 
 use super::affine_expression::AffineExpression;
 use super::eval_error::{self, EvalError};
@@ -47,6 +47,18 @@ impl<SV: SymbolicVariables> ExpressionEvaluator<SV> {
             Expression::FunctionCall(_, _) => {
                 Err("Function calls not implemented.".to_string().into())
             }
+            Expression::IfElse(condition, if_true, if_false) => {
+                let condition = self.evaluate(condition)?;
+                match condition.constant_value() {
+                    Some(c) if is_zero(&c) => self.evaluate(if_false),
+                    Some(_) => self.evaluate(if_true),
+                    None => Err(format!(
+                        "Condition is not constant: {}",
+                        self.variables.format(condition)
+                    )
+                    .into()),
+                }
+            }
         }
     }
 
@@ -104,23 +116,11 @@ impl<SV: SymbolicVariables> ExpressionEvaluator<SV> {
                         .into())
                     }
                 }
-                BinaryOperator::Mod
-                | BinaryOperator::BinaryAnd
-                | BinaryOperator::BinaryOr
-                | BinaryOperator::ShiftLeft
-                | BinaryOperator::ShiftRight => {
+                _ => {
                     if let (Some(left), Some(right)) =
                         (left.constant_value(), right.constant_value())
                     {
-                        let result = match op {
-                            BinaryOperator::Mod => left % right,
-                            BinaryOperator::BinaryAnd => left & right,
-                            BinaryOperator::BinaryOr => left | right,
-                            BinaryOperator::ShiftLeft => left << abstract_to_degree(&right),
-                            BinaryOperator::ShiftRight => left >> abstract_to_degree(&right),
-                            _ => panic!(),
-                        };
-                        Ok(result.into())
+                        Ok(evaluator::evaluate_binary_operation(left, op, right).into())
                     } else {
                         panic!()
                     }
@@ -136,9 +136,17 @@ impl<SV: SymbolicVariables> ExpressionEvaluator<SV> {
         op: &UnaryOperator,
         expr: &Expression,
     ) -> Result<AffineExpression, EvalError> {
-        self.evaluate(expr).map(|v| match op {
-            UnaryOperator::Plus => v,
-            UnaryOperator::Minus => -v,
+        self.evaluate(expr).and_then(|v| match op {
+            UnaryOperator::Plus => Ok(v),
+            UnaryOperator::Minus => Ok(-v),
+            UnaryOperator::LogicalNot => match v.constant_value() {
+                Some(v) => Ok(evaluator::evaluate_unary_operation(op, v).into()),
+                None => Err(format!(
+                    "Logical negation of a non-constant: !({})",
+                    self.variables.format(v)
+                )
+                .into()),
+            },
         })
     }
 }
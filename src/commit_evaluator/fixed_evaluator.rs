@@ -1,3 +1,5 @@
+use crate::number::DegreeType;
+
 use super::affine_expression::AffineExpression;
 use super::eval_error::EvalError;
 use super::expression_evaluator::SymbolicVariables;
@@ -23,13 +25,13 @@ impl<'a> SymbolicVariables for FixedEvaluator<'a> {
     fn value(&self, name: &str, next: bool) -> Result<AffineExpression, EvalError> {
         // TODO arrays
         if let Some(col_data) = self.fixed_data.fixed_cols.get(name) {
-            let degree = col_data.len();
+            let degree = self.fixed_data.degree as usize;
             let row = if next {
                 (self.row + 1) % degree
             } else {
                 self.row
             };
-            Ok(col_data[row].clone().into())
+            Ok(col_data.get(row as DegreeType).into())
         } else {
             Err("Can only accesss fixed columns in the fixed evaluator."
                 .to_string()
@@ -32,6 +32,10 @@ impl FixedLookup {
 }
 
 impl Machine for FixedLookup {
+    fn name(&self) -> &'static str {
+        "FixedLookup"
+    }
+
     fn process_plookup(
         &mut self,
         fixed_data: &FixedData,
@@ -74,13 +78,12 @@ impl Machine for FixedLookup {
             // TODO we really need a search index on this.
             fixed_data.fixed_cols
                 .get(poly.name.as_str())
-                .and_then(|values| values.iter().position(|v| *v == left_key))
+                .and_then(|values| values.position(&left_key))
                 .ok_or_else(|| {
                     format!(
                         "Unable to find matching row on the RHS where the first element is {left_key} - only fixed columns supported there."
                     )
                 })
-                .map(|i| i as DegreeType)
         } else {
             Err("First item on the RHS must be a polynomial reference.".to_string())
         }?;
@@ -166,13 +169,13 @@ impl<'a> SymbolicVariables for EvaluateFixedOnRow<'a> {
     fn value(&self, name: &str, next: bool) -> Result<AffineExpression, EvalError> {
         // TODO arrays
         let values = self.fixed_data.fixed_cols[name];
-        let degree = values.len() as DegreeType;
+        let degree = self.fixed_data.degree;
         let row = if next {
             (self.row + 1) % degree
         } else {
             self.row
         };
-        Ok(values[row as usize].clone().into())
+        Ok(values.get(row).into())
     }
 
     fn format(&self, expr: AffineExpression) -> String {
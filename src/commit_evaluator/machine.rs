@@ -8,6 +8,9 @@ use super::{affine_expression::AffineExpression, eval_error::EvalError, FixedDat
 /// A machine is a set of witness columns and identities where the columns
 /// are used on the righ-hand-side of lookups. It can process plookups.
 pub trait Machine {
+    /// The name of the machine type, used in reports.
+    fn name(&self) -> &'static str;
+
     // /// Tries to construct a new machine with the given subset of
     // /// witness columns and identities. If the identities do not
     // /// fit the pattern of the machine type, it can return None.
@@ -135,6 +135,9 @@ impl<'a> ReferenceExtractor<'a> {
             Expression::BinaryOperation(l, _, r) => &self.in_expression(l) | &self.in_expression(r),
             Expression::UnaryOperation(_, e) => self.in_expression(e),
             Expression::FunctionCall(_, args) => self.in_expressions(args),
+            Expression::IfElse(c, t, f) => {
+                &(&self.in_expression(c) | &self.in_expression(t)) | &self.in_expression(f)
+            }
             Expression::LocalVariableReference(_)
             | Expression::PublicReference(_)
             | Expression::Number(_)
@@ -1,7 +1,13 @@
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+use std::time::Duration;
 
-use crate::analyzer::{Analyzed, Expression, FunctionValueDefinition};
+use crate::analyzer::{Analyzed, Expression, FunctionValueDefinition, Identity};
+use crate::constant_evaluator::FixedColumn;
 use crate::number::{AbstractNumberType, DegreeType};
+use crate::witness_io::Witness;
 
 use self::eval_error::EvalError;
 use self::util::WitnessColumnNamer;
@@ -19,37 +25,158 @@ mod sorted_witness_machine;
 pub mod symbolic_evaluator;
 mod util;
 
+thread_local! {
+    /// Whether witness generation on this thread runs silently, see `silently`.
+    static SILENT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs witness generation without logging failures or reporting panics,
+/// for trials that are expected to fail often. Only this thread is
+/// affected: the panic hook installed on the first call passes the panics
+/// of all other threads and of non-silent code on to the previous hook.
+/// @returns the result or the payload of the panic.
+pub fn silently<T>(f: impl FnOnce() -> T) -> std::thread::Result<T> {
+    static INSTALL_HOOK: Once = Once::new();
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !is_silent() {
+                previous(info);
+            }
+        }));
+    });
+    SILENT.with(|silent| silent.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    SILENT.with(|silent| silent.set(false));
+    result
+}
+
+fn is_silent() -> bool {
+    SILENT.with(Cell::get)
+}
+
 /// Generates the committed polynomial values
 /// @returns the values (in source order) and the degree of the polynomials.
 pub fn generate<'a>(
     analyzed: &'a Analyzed,
     degree: DegreeType,
-    fixed_cols: &[(&str, Vec<AbstractNumberType>)],
+    fixed_cols: &[(&str, FixedColumn)],
     query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
-    verbose: bool,
 ) -> Vec<(&'a str, Vec<AbstractNumberType>)> {
-    let witness_cols: Vec<WitnessColumn> = analyzed
-        .committed_polys_in_source_order()
-        .iter()
-        .enumerate()
-        .map(|(i, (poly, value))| {
-            if poly.length.is_some() {
-                unimplemented!("Committed arrays not implemented.")
-            }
-            WitnessColumn::new(i, &poly.absolute_name, value)
-        })
-        .collect();
-    let fixed = FixedData {
+    generate_with_machine_times(analyzed, degree, fixed_cols, query_callback).0
+}
+
+/// Generates the committed polynomial values like `generate`.
+/// @returns the values and the name of each machine extracted from the
+/// identities together with the time spent inside it.
+pub fn generate_with_machine_times<'a>(
+    analyzed: &'a Analyzed,
+    degree: DegreeType,
+    fixed_cols: &[(&str, FixedColumn)],
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
+) -> (Vec<(&'a str, Vec<AbstractNumberType>)>, MachineTimes) {
+    let generated = generate_internal(analyzed, degree, fixed_cols, query_callback, None, None);
+    (generated.values, generated.machine_times)
+}
+
+/// Generates the committed polynomial values like `generate`. The values
+/// in `hints` are used for the cells that cannot be derived from the
+/// identities and queries, which allows to provide a partial witness.
+/// Derived values take precedence over the hints.
+pub fn generate_with_hints<'a>(
+    analyzed: &'a Analyzed,
+    degree: DegreeType,
+    fixed_cols: &[(&str, FixedColumn)],
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
+    hints: &Witness,
+) -> Vec<(&'a str, Vec<AbstractNumberType>)> {
+    generate_internal(
+        analyzed,
         degree,
-        constants: &analyzed.constants,
-        fixed_cols: fixed_cols.iter().map(|(n, v)| (*n, v)).collect(),
-        witness_cols: &witness_cols,
-        witness_ids: witness_cols.iter().map(|w| (w.name, w.id)).collect(),
-        verbose,
-    };
+        fixed_cols,
+        query_callback,
+        Some(hints),
+        None,
+    )
+    .values
+}
+
+/// Generates the committed polynomial values like `generate_with_hints`,
+/// but sets the cells in `overrides` to the given values before anything
+/// else is derived in their rows. The result does not necessarily satisfy
+/// the identities, because rows are also accepted if the identities
+/// contradict the given values.
+pub fn generate_with_overrides<'a>(
+    analyzed: &'a Analyzed,
+    degree: DegreeType,
+    fixed_cols: &[(&str, FixedColumn)],
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
+    hints: &Witness,
+    overrides: &Witness,
+) -> Vec<(&'a str, Vec<AbstractNumberType>)> {
+    generate_internal(
+        analyzed,
+        degree,
+        fixed_cols,
+        query_callback,
+        Some(hints),
+        Some(overrides),
+    )
+    .values
+}
+
+/// Generates the committed polynomial values like `generate`, using the
+/// hints like `generate_with_hints` if given.
+/// @returns the values and the solver metrics of each row.
+pub fn generate_with_telemetry<'a>(
+    analyzed: &'a Analyzed,
+    degree: DegreeType,
+    fixed_cols: &[(&str, FixedColumn)],
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
+    hints: Option<&Witness>,
+) -> (Vec<(&'a str, Vec<AbstractNumberType>)>, Vec<RowMetrics>) {
+    let generated = generate_internal(analyzed, degree, fixed_cols, query_callback, hints, None);
+    (generated.values, generated.row_metrics)
+}
+
+/// Solver metrics of computing one row of the witness.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RowMetrics {
+    pub row: DegreeType,
+    /// Number of passes over the identities and queries until a fixed point was reached.
+    pub iterations: usize,
+    pub identities_evaluated: usize,
+    /// Number of lookups passed to the machines.
+    pub machine_lookups: usize,
+    pub queries: usize,
+    pub time: Duration,
+}
+
+struct Generated<'a> {
+    values: Vec<(&'a str, Vec<AbstractNumberType>)>,
+    machine_times: MachineTimes,
+    row_metrics: Vec<RowMetrics>,
+}
+
+fn generate_internal<'a>(
+    analyzed: &'a Analyzed,
+    degree: DegreeType,
+    fixed_cols: &[(&str, FixedColumn)],
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
+    hints: Option<&Witness>,
+    overrides: Option<&Witness>,
+) -> Generated<'a> {
+    let witness_cols = witness_columns(analyzed);
+    let fixed = fixed_data(analyzed, degree, fixed_cols, &witness_cols);
     let (machines, identities) =
         machine_extractor::split_out_machines(&fixed, &analyzed.identities, &witness_cols);
     let mut evaluator = evaluator::Evaluator::new(&fixed, identities, machines, query_callback);
+    if let Some(hints) = hints {
+        evaluator.set_hints(values_by_row(&fixed, hints));
+    }
+    if let Some(overrides) = overrides {
+        evaluator.set_overrides(values_by_row(&fixed, overrides));
+    }
 
     let mut values: Vec<(&str, Vec<AbstractNumberType>)> =
         witness_cols.iter().map(|p| (p.name, Vec::new())).collect();
@@ -60,8 +187,8 @@ pub fn generate<'a>(
         }
     }
     for (col, v) in evaluator.compute_next_row(0).into_iter().enumerate() {
-        if v != values[col].1[0] {
-            eprintln!("Wrap-around value for column {} does not match: {} (wrap-around) vs. {} (first row).",
+        if v != values[col].1[0] && !is_silent() {
+            log::warn!("Wrap-around value for column {} does not match: {} (wrap-around) vs. {} (first row).",
             witness_cols[col].name, v, values[col].1[0]);
         }
     }
@@ -69,9 +196,130 @@ pub fn generate<'a>(
         let (_, col) = values.iter_mut().find(|(n, _)| *n == name).unwrap();
         *col = data;
     }
-    values
+    Generated {
+        values,
+        machine_times: evaluator.machine_times(),
+        // Leave out the second computation of the first row, which checks the wrap-around.
+        row_metrics: evaluator.row_metrics()[..degree as usize].to_vec(),
+    }
 }
 
+/// What determined the value of a witness cell during witness generation.
+#[derive(Clone, Debug)]
+pub enum Provenance<'a> {
+    /// Solving the identity or the lookup for the cell.
+    Identity(&'a Identity),
+    /// The answer to the query of the column.
+    Query,
+    /// A provided value, because the value could not be derived.
+    Hint,
+    /// The machine of the given type, which sets all values of its
+    /// columns after the last row.
+    Machine(&'static str),
+}
+
+/// Runs witness generation up to and including the given row.
+/// @returns the name of each witness column, its value in the row (if the
+/// column is not set by a machine) and what determined the value.
+pub fn generate_until_row<'a>(
+    analyzed: &'a Analyzed,
+    degree: DegreeType,
+    fixed_cols: &[(&str, FixedColumn)],
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
+    row: DegreeType,
+) -> Vec<(&'a str, Option<AbstractNumberType>, Option<Provenance<'a>>)> {
+    let witness_cols = witness_columns(analyzed);
+    let fixed = fixed_data(analyzed, degree, fixed_cols, &witness_cols);
+    let (machines, identities) =
+        machine_extractor::split_out_machines(&fixed, &analyzed.identities, &witness_cols);
+    let mut evaluator = evaluator::Evaluator::new(&fixed, identities, machines, query_callback);
+    let mut values = vec![];
+    for r in 0..=row {
+        values = evaluator.compute_next_row(r);
+    }
+    let mut result = witness_cols
+        .iter()
+        .zip(values)
+        .zip(evaluator.current_provenance())
+        .map(|((column, value), provenance)| {
+            // The evaluator only lives as long as this function,
+            // so we refer to the identity in `analyzed` instead.
+            let provenance = provenance.as_ref().map(|p| match p {
+                Provenance::Identity(identity) => Provenance::Identity(
+                    analyzed
+                        .identities
+                        .iter()
+                        .find(|i| std::ptr::eq(*i, *identity))
+                        .unwrap(),
+                ),
+                Provenance::Query => Provenance::Query,
+                Provenance::Hint => Provenance::Hint,
+                Provenance::Machine(name) => Provenance::Machine(name),
+            });
+            (column.name, Some(value), provenance)
+        })
+        .collect::<Vec<_>>();
+    for (machine, columns) in evaluator.witness_col_values_by_machine() {
+        for name in columns.keys() {
+            let (_, value, provenance) = result.iter_mut().find(|(n, _, _)| n == name).unwrap();
+            *value = None;
+            *provenance = Some(Provenance::Machine(machine));
+        }
+    }
+    result
+}
+
+/// @returns the values of the witness columns grouped by row,
+/// with the columns given by their ids.
+fn values_by_row(
+    fixed: &FixedData,
+    witness: &Witness,
+) -> HashMap<DegreeType, Vec<(usize, AbstractNumberType)>> {
+    let mut by_row: HashMap<_, Vec<_>> = HashMap::new();
+    for (name, values) in &witness.columns {
+        let id = *fixed
+            .witness_ids
+            .get(name.as_str())
+            .unwrap_or_else(|| panic!("Witness column {name} not found."));
+        for (row, value) in (witness.first_row..).zip(values) {
+            by_row.entry(row).or_default().push((id, value.clone()));
+        }
+    }
+    by_row
+}
+
+fn witness_columns(analyzed: &Analyzed) -> Vec<WitnessColumn<'_>> {
+    analyzed
+        .committed_polys_in_source_order()
+        .iter()
+        .enumerate()
+        .map(|(i, (poly, value))| {
+            if poly.length.is_some() {
+                unimplemented!("Committed arrays not implemented.")
+            }
+            WitnessColumn::new(i, &poly.absolute_name, value)
+        })
+        .collect()
+}
+
+fn fixed_data<'a>(
+    analyzed: &'a Analyzed,
+    degree: DegreeType,
+    fixed_cols: &'a [(&str, FixedColumn)],
+    witness_cols: &'a Vec<WitnessColumn<'a>>,
+) -> FixedData<'a> {
+    FixedData {
+        degree,
+        constants: &analyzed.constants,
+        fixed_cols: fixed_cols.iter().map(|(n, v)| (*n, v)).collect(),
+        witness_cols,
+        witness_ids: witness_cols.iter().map(|w| (w.name, w.id)).collect(),
+    }
+}
+
+/// The name of each machine and the time spent inside it.
+pub type MachineTimes = Vec<(&'static str, Duration)>;
+
 /// Result of evaluating an expression / lookup:
 /// A new assignment to a witness column identified by an ID or an error.
 type EvalResult = Result<Vec<(usize, AbstractNumberType)>, EvalError>;
@@ -80,20 +328,18 @@ type EvalResult = Result<Vec<(usize, AbstractNumberType)>, EvalError>;
 pub struct FixedData<'a> {
     degree: DegreeType,
     constants: &'a HashMap<String, AbstractNumberType>,
-    fixed_cols: HashMap<&'a str, &'a Vec<AbstractNumberType>>,
+    fixed_cols: HashMap<&'a str, &'a FixedColumn>,
     witness_cols: &'a Vec<WitnessColumn<'a>>,
     witness_ids: HashMap<&'a str, usize>,
-    verbose: bool,
 }
 
 impl<'a> FixedData<'a> {
     pub fn new(
         degree: DegreeType,
         constants: &'a HashMap<String, AbstractNumberType>,
-        fixed_cols: HashMap<&'a str, &'a Vec<AbstractNumberType>>,
+        fixed_cols: HashMap<&'a str, &'a FixedColumn>,
         witness_cols: &'a Vec<WitnessColumn<'a>>,
         witness_ids: HashMap<&'a str, usize>,
-        verbose: bool,
     ) -> Self {
         FixedData {
             degree,
@@ -101,7 +347,6 @@ impl<'a> FixedData<'a> {
             fixed_cols,
             witness_cols,
             witness_ids,
-            verbose,
         }
     }
 }
@@ -132,3 +377,18 @@ impl<'a> WitnessColumn<'a> {
         WitnessColumn { id, name, query }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn silently_only_affects_the_current_thread() {
+        assert_eq!(silently(is_silent).ok(), Some(true));
+        assert!(!is_silent());
+        assert!(silently(|| panic!("A silent panic.")).is_err());
+        assert!(!is_silent());
+        let other_thread = silently(|| std::thread::spawn(is_silent).join().unwrap());
+        assert_eq!(other_thread.ok(), Some(false));
+    }
+}
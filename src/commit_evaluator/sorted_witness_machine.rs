@@ -117,6 +117,10 @@ fn check_constraint<'a>(fixed_data: &'a FixedData, constraint: &Expression) -> O
 }
 
 impl Machine for SortedWitnesses {
+    fn name(&self) -> &'static str {
+        "SortedWitnesses"
+    }
+
     fn process_plookup(
         &mut self,
         fixed_data: &FixedData,
@@ -195,9 +199,7 @@ impl Machine for SortedWitnesses {
                     } else {
                         match constraint.solve() {
                             Some(assignment) => {
-                                if fixed_data.verbose {
-                                    println!("Read {} = {key_value} -> {r} = {v}", self.key_col);
-                                }
+                                log::trace!("Read {} = {key_value} -> {r} = {v}", self.key_col);
                                 assignments.push(assignment);
                             }
                             None => {
@@ -211,9 +213,7 @@ impl Machine for SortedWitnesses {
                 // There is no value stored yet.
                 None => match l.constant_value() {
                     Some(v) => {
-                        if fixed_data.verbose {
-                            println!("Stored {} = {key_value} -> {r} = {v}", self.key_col);
-                        }
+                        log::trace!("Stored {} = {key_value} -> {r} = {v}", self.key_col);
                         *stored_value = Some(v);
                     }
                     None => {
@@ -35,6 +35,7 @@ pub fn expr_any(expr: &Expression, f: &mut impl FnMut(&Expression) -> bool) -> b
             Expression::BinaryOperation(l, _, r) => expr_any(l, f) || expr_any(r, f),
             Expression::UnaryOperation(_, e) => expr_any(e, f),
             Expression::FunctionCall(_, args) => args.iter().any(|e| expr_any(e, f)),
+            Expression::IfElse(c, t, e) => expr_any(c, f) || expr_any(t, f) || expr_any(e, f),
             Expression::Constant(_)
             | Expression::PolynomialReference(_)
             | Expression::LocalVariableReference(_)
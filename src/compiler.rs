@@ -1,33 +1,59 @@
 use std::fs;
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{panic, thread};
 
 use itertools::Itertools;
-use num_bigint::Sign;
 
-use crate::number::{abstract_to_degree, AbstractNumberType, DegreeType};
+use crate::analyzer::{PolynomialType, SourceRef};
+use crate::backend::{BackendType, Proof, PublicValues};
+use crate::column_file::ColumnFile;
+use crate::column_stats;
+use crate::constant_evaluator::FixedColumn;
+use crate::constraint_checker::{self, ConstraintChecker};
+use crate::error_codes::{self, Error};
+use crate::explain;
+use crate::field_fit::{self, TargetField};
+use crate::linter::{self, Diagnostic, Severity};
+use crate::number::{to_le_bytes, AbstractNumberType, DegreeType};
 use crate::parser::ast::PILFile;
-use crate::{analyzer, asm_compiler, commit_evaluator, constant_evaluator, json_exporter};
+use crate::repl::{self, Repl};
+use crate::satisfiability;
+use crate::solver_telemetry;
+use crate::trace_viewer::{self, TraceViewer};
+use crate::witness_hints;
+use crate::witness_io::{self, Witness};
+use crate::{
+    analyzer, asm_compiler, bundle, commit_evaluator, constant_evaluator, dot_exporter,
+    json_exporter, parser, smt_exporter,
+};
 
 pub fn no_callback() -> Option<fn(&str) -> Option<AbstractNumberType>> {
     None
 }
 
 /// Compiles a .pil file to its json form and also tries to generate
-/// constants and committed polynomials.
-/// @returns true if all committed/witness and constant/fixed polynomials
-/// could be generated.
+/// constants and committed polynomials. Included files are searched for
+/// in the include paths like in `analyzer::analyze`.
+/// If a backend is given, it is used to prove the generated columns.
+/// @returns an error if the file has errors (see `analyze_checked`) or not
+/// all committed/witness and constant/fixed polynomials could be generated
+/// (and proven).
 pub fn compile_pil(
     pil_file: &Path,
+    include_paths: &[PathBuf],
     output_dir: &Path,
     query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
-) -> bool {
+    backend: Option<BackendType>,
+) -> Result<(), Error> {
     compile(
-        &analyzer::analyze(pil_file),
+        &analyze_checked(analyzer::analyze(pil_file, include_paths))?,
         pil_file.file_name().unwrap().to_str().unwrap(),
         output_dir,
         query_callback,
-        false,
+        backend,
     )
 }
 
@@ -36,69 +62,96 @@ pub fn compile_pil_ast(
     file_name: &str,
     output_dir: &Path,
     query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
-    verbose: bool,
-) -> bool {
+    backend: Option<BackendType>,
+) -> Result<(), Error> {
     // TODO exporting this to string as a hack because the parser
     // is tied into the analyzer due to imports.
     compile(
-        &analyzer::analyze_string(&format!("{pil}")),
+        &analyze_checked(analyzer::analyze_string(&format!("{pil}")))?,
         file_name,
         output_dir,
         query_callback,
-        verbose,
+        backend,
     )
 }
 
 /// Compiles a .asm file, outputs the PIL on stdout and tries to generate
-/// fixed and witness columns.
+/// fixed and witness columns. The query callback answers the queries for
+/// the free inputs, see `asm_input_query`. The code lines are only
+/// optimized if `optimize` is true.
+/// @returns an error like `compile_pil`.
 pub fn compile_asm(
     file_name: &str,
-    inputs: Vec<AbstractNumberType>,
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
     output_dir: &Path,
     force_overwrite: bool,
-    verbose: bool,
-) {
+    optimize: bool,
+    backend: Option<BackendType>,
+) -> Result<(), Error> {
     let contents = fs::read_to_string(file_name).unwrap();
-    let pil = asm_compiler::compile(Some(file_name), &contents).unwrap_or_else(|err| {
-        eprintln!("Error parsing .asm file:");
-        err.output_to_stderr();
-        panic!();
-    });
+    let pil = asm_compiler::compile_with_optimizer(Some(file_name), &contents, optimize)
+        .unwrap_or_else(|err| {
+            log::error!("Error parsing .asm file:");
+            err.output_to_stderr();
+            panic!();
+        });
     let pil_file_name = output_dir.join(format!(
         "{}.pil",
         Path::new(file_name).file_stem().unwrap().to_str().unwrap()
     ));
     if pil_file_name.exists() && !force_overwrite {
-        eprint!(
+        log::warn!(
             "Target file {} already exists. Not overwriting.",
             pil_file_name.to_str().unwrap()
         );
-        return;
+        return Ok(());
     }
     fs::write(pil_file_name.clone(), format!("{pil}")).unwrap();
 
-    let query_callback = |query: &str| -> Option<AbstractNumberType> {
-        let items = query.split(',').map(|s| s.trim()).collect::<Vec<_>>();
-        let mut it = items.iter();
-        let _current_step = it.next().unwrap();
-        let current_pc = it.next().unwrap();
-        assert!(it.clone().len() % 3 == 0);
-        for (pc_check, input, index) in it.tuples() {
-            if pc_check == current_pc {
-                assert_eq!(*input, "\"input\"");
-                let index: usize = index.parse().unwrap();
-                return inputs.get(index).cloned();
-            }
-        }
-        None
-    };
     compile_pil_ast(
         &pil,
         pil_file_name.to_str().unwrap(),
         output_dir,
-        Some(query_callback),
-        verbose,
-    );
+        query_callback,
+        backend,
+    )
+}
+
+/// Answers the queries for free inputs of an assembly program,
+/// which consist of the step, the pc and the inputs used at each pc.
+pub fn asm_input_query(inputs: &[AbstractNumberType], query: &str) -> Option<AbstractNumberType> {
+    inputs.get(asm_input_index(query)?).cloned()
+}
+
+/// @returns the index of the free input requested by a query of an
+/// assembly program, if an input is used at the current pc.
+pub fn asm_input_index(query: &str) -> Option<usize> {
+    let items = query.split(',').map(|s| s.trim()).collect::<Vec<_>>();
+    let mut it = items.iter();
+    let _current_step = it.next().unwrap();
+    let current_pc = it.next().unwrap();
+    assert!(it.clone().len() % 3 == 0);
+    for (pc_check, input, index) in it.tuples() {
+        if pc_check == current_pc {
+            assert_eq!(*input, "\"input\"");
+            return Some(index.parse().unwrap());
+        }
+    }
+    None
+}
+
+/// Answers queries of the form `"input", <index>` in PIL files.
+pub fn pil_input_query(inputs: &[AbstractNumberType], query: &str) -> Option<AbstractNumberType> {
+    inputs.get(pil_input_index(query)?).cloned()
+}
+
+/// @returns the index of a query of the form `"input", <index>`.
+pub fn pil_input_index(query: &str) -> Option<usize> {
+    let (kind, index) = query.split_once(',')?;
+    if kind.trim() != "\"input\"" {
+        return None;
+    }
+    index.trim().parse().ok()
 }
 
 fn compile(
@@ -106,52 +159,1083 @@ fn compile(
     file_name: &str,
     output_dir: &Path,
     query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
-    verbose: bool,
-) -> bool {
-    let mut success = true;
-    let (constants, degree) = constant_evaluator::generate(analyzed);
+    backend: Option<BackendType>,
+) -> Result<(), Error> {
+    let mut result = Ok(());
+    let (constants, degree) = generate_constants(analyzed);
     if analyzed.constant_count() == constants.len() {
-        write_polys_file(
-            &mut BufWriter::new(&mut fs::File::create(output_dir.join("constants.bin")).unwrap()),
+        constant_evaluator::check_identities(analyzed, &constants, degree);
+        write_constants(analyzed, &constants, degree, output_dir);
+        let commits = witgen_checked(|| {
+            commit_evaluator::generate(analyzed, degree, &constants, query_callback)
+        })?;
+        log_column_stats(&commits);
+        write_commits(analyzed, &commits, degree, output_dir);
+        if let Some(backend) = backend {
+            if let Err(errors) = prove(analyzed, &constants, &commits, degree, backend, output_dir)
+            {
+                result = Err(Error::new(
+                    error_codes::BACKEND_FAILURE,
+                    format!("Proof generation failed:\n{errors}"),
+                ));
+            }
+        }
+    } else {
+        log::warn!("Not writing constants.bin because not all declared constants are defined (or there are none).");
+        result = Err(Error::new(
+            error_codes::UNDEFINED_FIXED_COLUMNS,
+            "Not all declared constants are defined.",
+        ));
+    }
+    write_json(analyzed, file_name, output_dir);
+    result
+}
+
+/// Reports the failures of the analysis (E0005) and the errors of the linter
+/// (see `linter::lint`) as errors, so that the later stages do not panic
+/// on invalid input.
+fn analyze_checked(
+    analyzed: Result<analyzer::Analyzed, String>,
+) -> Result<analyzer::Analyzed, Error> {
+    let analyzed = analyzed.map_err(|e| Error::new(error_codes::ANALYZER_ERROR, e))?;
+    match linter::lint(&analyzed)
+        .into_iter()
+        .find(|d| d.severity == Severity::Error)
+    {
+        Some(d) => Err(Error::new(
+            d.code,
+            format!("{}:{}: {}", d.source.file, d.source.line, d.message),
+        )),
+        None => Ok(analyzed),
+    }
+}
+
+/// Runs witness generation and reports its failures as errors (E0100).
+fn witgen_checked<T>(generate: impl FnOnce() -> T) -> Result<T, Error> {
+    panic::catch_unwind(panic::AssertUnwindSafe(generate)).map_err(|payload| {
+        Error::new(
+            error_codes::WITGEN_STUCK,
+            error_codes::panic_message(&*payload)
+                .unwrap_or_else(|| "Witness generation failed.".to_string()),
+        )
+    })
+}
+
+/// First stage of `compile_pil`: generates the fixed columns of a .pil file
+/// and writes them to `constants.bin` in the output directory.
+pub fn generate_fixed(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    output_dir: &Path,
+) -> Result<(), Error> {
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let (constants, degree) = generate_constants(&analyzed);
+    if analyzed.constant_count() != constants.len() {
+        return Err(Error::new(
+            error_codes::UNDEFINED_FIXED_COLUMNS,
+            "Not all declared constants are defined.",
+        ));
+    }
+    constant_evaluator::check_identities(&analyzed, &constants, degree);
+    write_constants(&analyzed, &constants, degree, output_dir);
+    Ok(())
+}
+
+/// Second stage of `compile_pil`: generates the witness columns of a .pil file
+/// from the fixed columns in `constants.bin` in the output directory
+/// and writes them to `commits.bin` in the same directory.
+/// Values in the csv file `witness_hints` are used for cells that witness
+/// generation cannot derive; hints that are overridden by derived values or
+/// that violate an identity are logged as warnings.
+/// If a telemetry file is given, the solver metrics of each row are written
+/// to it, see `solver_telemetry::write`.
+pub fn generate_witness(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    output_dir: &Path,
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
+    witness_hints: Option<&Path>,
+    telemetry: Option<&Path>,
+) -> Result<(), Error> {
+    let invalid_input = |e| Error::new(error_codes::INVALID_INPUT, e);
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let degree = declared_degree(&analyzed);
+    let constants = read_constants(&analyzed, degree, output_dir).map_err(invalid_input)?;
+    let hints = match witness_hints {
+        Some(file) => {
+            let hints = fs::read_to_string(file)
+                .map_err(|e| format!("Could not read {file:?}: {e}"))
+                .and_then(|contents| {
+                    Witness::from_csv(&contents)
+                        .map_err(|e| format!("Invalid witness hints in {file:?}: {e}"))
+                })
+                .map_err(invalid_input)?;
+            witness_hints::validate(&analyzed, &hints, degree).map_err(invalid_input)?;
+            Some(hints)
+        }
+        None => None,
+    };
+    let (commits, row_metrics) = witgen_checked(|| {
+        commit_evaluator::generate_with_telemetry(
+            &analyzed,
             degree,
             &constants,
-        );
-        println!("Wrote constants.bin.");
-        let commits =
-            commit_evaluator::generate(analyzed, degree, &constants, query_callback, verbose);
-        write_polys_file(
-            &mut BufWriter::new(&mut fs::File::create(output_dir.join("commits.bin")).unwrap()),
-            degree,
-            &commits,
-        );
-        println!("Wrote commits.bin.");
+            query_callback,
+            hints.as_ref(),
+        )
+    })?;
+    if let Some(hints) = &hints {
+        for line in witness_hints::report(&analyzed, &constants, &commits, degree, hints) {
+            log::warn!("{line}");
+        }
+    }
+    if let Some(file) = telemetry {
+        log::info!("{}", solver_telemetry::summarize(&row_metrics));
+        solver_telemetry::write(&row_metrics, file)
+            .map_err(|e| Error::new(error_codes::INVALID_INPUT, e))?;
+        log::info!("Wrote {}.", file.display());
+    }
+    log_column_stats(&commits);
+    write_commits(&analyzed, &commits, degree, output_dir);
+    Ok(())
+}
+
+/// Last stage of `compile_pil`: proves the columns in `constants.bin` and
+/// `commits.bin` in the directory with the given backend and writes
+/// the proof and the public values to the same directory.
+pub fn prove_pil(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    dir: &Path,
+    backend: BackendType,
+) -> Result<(), Error> {
+    let invalid_input = |e| Error::new(error_codes::INVALID_INPUT, e);
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let degree = declared_degree(&analyzed);
+    let constants = read_constants(&analyzed, degree, dir).map_err(invalid_input)?;
+    let commit_names = witness_io::column_names(&analyzed, PolynomialType::Committed);
+    let commits =
+        read_columns(&commit_names, degree, &dir.join("commits.bin")).map_err(invalid_input)?;
+    prove(&analyzed, &constants, &commits, degree, backend, dir)
+        .map_err(|e| Error::new(error_codes::BACKEND_FAILURE, e))
+}
+
+/// @returns the degree of the polynomials declared in the .pil file.
+fn declared_degree(analyzed: &analyzer::Analyzed) -> DegreeType {
+    analyzed
+        .definitions
+        .values()
+        .map(|(poly, _)| poly.degree)
+        .max()
+        .unwrap_or_default()
+}
+
+/// Generates the fixed columns at the declared degree, which is
+/// also defined if there are no fixed columns.
+fn generate_constants(analyzed: &analyzer::Analyzed) -> (Vec<(&str, FixedColumn)>, DegreeType) {
+    let degree = declared_degree(analyzed);
+    (
+        constant_evaluator::generate_at_degree(analyzed, degree),
+        degree,
+    )
+}
+
+/// Reads the fixed columns from `constants.bin` in the directory.
+fn read_constants<'a>(
+    analyzed: &'a analyzer::Analyzed,
+    degree: DegreeType,
+    dir: &Path,
+) -> Result<Vec<(&'a str, FixedColumn)>, String> {
+    let names = witness_io::column_names(analyzed, PolynomialType::Constant);
+    Ok(read_columns(&names, degree, &dir.join("constants.bin"))?
+        .into_iter()
+        .map(|(name, values)| (name, FixedColumn::compress(values)))
+        .collect())
+}
+
+/// Reads columns written by `write_polys_file` and checks their degree.
+fn read_columns<'a>(
+    names: &[&'a str],
+    degree: DegreeType,
+    file: &Path,
+) -> Result<Vec<(&'a str, Vec<AbstractNumberType>)>, String> {
+    let columns = witness_io::read_binary_columns(names, &read(file)?)
+        .map_err(|e| format!("Invalid contents of {file:?}: {e}"))?;
+    match columns.first() {
+        Some((_, values)) if values.len() as DegreeType != degree => Err(format!(
+            "{file:?} contains {} rows, but the degree is {degree}.",
+            values.len()
+        )),
+        _ => Ok(columns),
+    }
+}
+
+fn write_constants(
+    analyzed: &analyzer::Analyzed,
+    constants: &[(&str, FixedColumn)],
+    degree: DegreeType,
+    output_dir: &Path,
+) {
+    write_polys_file(
+        &mut BufWriter::new(&mut fs::File::create(output_dir.join("constants.bin")).unwrap()),
+        analyzed,
+        PolynomialType::Constant,
+        degree,
+        constants,
+        |column, row| column.get(row),
+    );
+    log::info!("Wrote constants.bin.");
+}
+
+fn write_commits(
+    analyzed: &analyzer::Analyzed,
+    commits: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+    output_dir: &Path,
+) {
+    write_polys_file(
+        &mut BufWriter::new(&mut fs::File::create(output_dir.join("commits.bin")).unwrap()),
+        analyzed,
+        PolynomialType::Committed,
+        degree,
+        commits,
+        |column, row| column[row as usize].clone(),
+    );
+    log::info!("Wrote commits.bin.");
+}
+
+fn log_column_stats(commits: &[(&str, Vec<AbstractNumberType>)]) {
+    log::info!(
+        "Witness column statistics:\n{}",
+        column_stats::Table(&column_stats::compute(commits))
+    );
+}
+
+/// Proves the columns and writes the proof to `proof.bin` and the
+/// public values to `publics.json`.
+fn prove(
+    analyzed: &analyzer::Analyzed,
+    constants: &[(&str, FixedColumn)],
+    commits: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+    backend: BackendType,
+    output_dir: &Path,
+) -> Result<(), String> {
+    let backend = backend.backend();
+    backend.setup(analyzed, constants, degree, output_dir);
+    let proof = backend.prove(analyzed, constants, commits, degree)?;
+    fs::write(output_dir.join("proof.bin"), proof.to_binary()).unwrap();
+    log::info!("Wrote proof.bin.");
+    fs::write(
+        output_dir.join("publics.json"),
+        proof.publics.to_json().pretty(2),
+    )
+    .unwrap();
+    log::info!("Wrote publics.json.");
+    Ok(())
+}
+
+/// Analyzes a .pil file or, if the name ends in `.asm`, compiles an assembly
+/// file to PIL and analyzes that, without writing anything.
+/// Syntax errors in the input file itself and failures of the analyzer
+/// (E0005) are reported as diagnostics.
+/// @returns the diagnostics of the linter and the files the input consists of.
+pub fn check_file(file: &Path, include_paths: &[PathBuf]) -> (Vec<Diagnostic>, Vec<PathBuf>) {
+    let file_name = file.to_str().unwrap();
+    let contents = fs::read_to_string(file).unwrap();
+    let syntax_error = if file.extension() == Some("asm".as_ref()) {
+        parser::parse_asm(Some(file_name), &contents).err()
+    } else {
+        parser::parse(Some(file_name), &contents).err()
+    };
+    if let Some(err) = syntax_error {
+        return (vec![Diagnostic::from(&err)], vec![file.to_path_buf()]);
+    }
+    match analyze_file(file, include_paths) {
+        Ok((analyzed, sources)) => (linter::lint(&analyzed), sources),
+        Err(message) => {
+            let diagnostic = Diagnostic {
+                code: error_codes::ANALYZER_ERROR,
+                severity: Severity::Error,
+                source: SourceRef {
+                    file: file_name.to_string(),
+                    line: 1,
+                },
+                message,
+            };
+            (vec![diagnostic], vec![file.to_path_buf()])
+        }
+    }
+}
+
+/// Generates fixed and witness columns for a .pil or .asm file at the given
+/// (small) degree without writing them, as a quick smoke test.
+pub fn witgen_smoke_test(
+    file: &Path,
+    include_paths: &[PathBuf],
+    inputs: &[AbstractNumberType],
+    degree: DegreeType,
+) -> Result<(), String> {
+    witness_at_degree(file, include_paths, inputs, degree)?;
+    log::info!("Generated fixed and witness columns at degree {degree}.");
+    Ok(())
+}
+
+/// Generates the witness columns of a .pil or .asm file at the given
+/// (small) degree without writing them. The inputs are the free inputs of
+/// an assembly program or answer the queries `"input", <index>` in PIL.
+pub fn witness_at_degree(
+    file: &Path,
+    include_paths: &[PathBuf],
+    inputs: &[AbstractNumberType],
+    degree: DegreeType,
+) -> Result<Witness, String> {
+    let is_asm = file.extension() == Some("asm".as_ref());
+    let (analyzed, _) = analyze_file(file, include_paths)?;
+    panic::catch_unwind(|| {
+        let constants = constant_evaluator::generate_at_degree(&analyzed, degree);
+        let query_callback = |query: &str| {
+            if is_asm {
+                asm_input_query(inputs, query)
+            } else {
+                pil_input_query(inputs, query)
+            }
+        };
+        let columns =
+            commit_evaluator::generate(&analyzed, degree, &constants, Some(query_callback));
+        Witness {
+            first_row: 0,
+            columns: columns
+                .into_iter()
+                .map(|(name, values)| (name.to_string(), values))
+                .collect(),
+        }
+    })
+    .map_err(|_| format!("Witness generation at degree {degree} failed."))
+}
+
+/// @returns the analyzed .pil or .asm file and the files it consists of.
+fn analyze_file(
+    file: &Path,
+    include_paths: &[PathBuf],
+) -> Result<(analyzer::Analyzed, Vec<PathBuf>), String> {
+    if file.extension() == Some("asm".as_ref()) {
+        let contents =
+            fs::read_to_string(file).map_err(|e| format!("Could not read {file:?}: {e}"))?;
+        let pil = asm_compiler::compile(Some(&file.to_string_lossy()), &contents)
+            .map_err(|err| err.to_string())?;
+        Ok((
+            analyzer::analyze_string(&format!("{pil}"))?,
+            vec![file.to_path_buf()],
+        ))
+    } else {
+        analyzer::analyze_with_sources(file, include_paths)
+    }
+}
+
+/// Calls `run` and calls it again every time one of the files it returns
+/// is modified. If `run` panics, the files of the previous run are watched.
+pub fn watch(file: &Path, mut run: impl FnMut() -> Vec<PathBuf>) -> ! {
+    let mut files = vec![file.to_path_buf()];
+    loop {
+        if let Ok(sources) = panic::catch_unwind(panic::AssertUnwindSafe(&mut run)) {
+            files = sources;
+        }
+        log::info!("Watching {} file(s) for changes...", files.len());
+        let modification_times = |files: &[PathBuf]| {
+            files
+                .iter()
+                .map(|f| fs::metadata(f).and_then(|m| m.modified()).ok())
+                .collect::<Vec<_>>()
+        };
+        let initial = modification_times(&files);
+        while modification_times(&files) == initial {
+            thread::sleep(Duration::from_millis(300));
+        }
+    }
+}
+
+/// Verifies a proof for the .pil file, stored in binary or, if the file
+/// name ends in `.json`, in json.
+/// If a file with public values is given (json or, for all other extensions,
+/// 8 little-endian bytes per value), the public values of the proof have
+/// to match them.
+/// @returns the public values of the proof.
+pub fn verify_pil(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    proof_file: &Path,
+    publics_file: Option<&Path>,
+) -> Result<PublicValues, Error> {
+    let invalid_input = |e| Error::new(error_codes::INVALID_INPUT, e);
+    let rejected = |e| Error::new(error_codes::VERIFICATION_FAILURE, e);
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let proof = if is_json(proof_file) {
+        read_json(proof_file).and_then(|json| Proof::from_json(&json))
     } else {
-        println!("Not writing constants.bin because not all declared constants are defined (or there are none).");
-        success = false;
+        read(proof_file).and_then(|bytes| Proof::from_binary(&bytes))
+    }
+    .map_err(invalid_input)?;
+    if let Some(publics_file) = publics_file {
+        let expected = if is_json(publics_file) {
+            read_json(publics_file).and_then(|json| PublicValues::from_json(&json))
+        } else {
+            read(publics_file).and_then(|bytes| PublicValues::from_binary(&analyzed, &bytes))
+        }
+        .map_err(invalid_input)?;
+        proof.publics.check(&expected).map_err(rejected)?;
+    }
+    let (constants, degree) = generate_constants(&analyzed);
+    proof
+        .backend
+        .backend()
+        .verify(&analyzed, &constants, degree, &proof)
+        .map_err(rejected)?;
+    Ok(proof.publics)
+}
+
+/// Verifies several proofs for the same .pil file, analyzing it and
+/// generating the fixed columns only once.
+/// @returns the public values or the error for each proof, in the given order,
+/// or an error if the .pil file has errors.
+pub fn verify_pil_batch(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    proof_files: &[&Path],
+) -> Result<Vec<Result<PublicValues, String>>, Error> {
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let (constants, degree) = generate_constants(&analyzed);
+    let proofs = proof_files
+        .iter()
+        .map(|file| {
+            if is_json(file) {
+                Proof::from_json(&read_json(file)?)
+            } else {
+                Proof::from_binary(&read(file)?)
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut results = proofs
+        .iter()
+        .map(|proof| proof.as_ref().map(|_| ()).map_err(|e| e.clone()))
+        .collect::<Vec<_>>();
+    for backend_type in proofs.iter().flatten().map(|p| p.backend).unique() {
+        let (indices, batch): (Vec<_>, Vec<_>) = proofs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, proof)| proof.as_ref().ok().map(|proof| (i, proof)))
+            .filter(|(_, proof)| proof.backend == backend_type)
+            .unzip();
+        let batch_results = backend_type
+            .backend()
+            .verify_batch(&analyzed, &constants, degree, &batch);
+        for (i, result) in indices.into_iter().zip(batch_results) {
+            results[i] = result;
+        }
+    }
+    Ok(results
+        .into_iter()
+        .zip(proofs)
+        .map(|(result, proof)| result.and_then(|()| Ok(proof?.publics)))
+        .collect())
+}
+
+fn is_json(file: &Path) -> bool {
+    file.extension() == Some("json".as_ref())
+}
+
+fn read(file: &Path) -> Result<Vec<u8>, String> {
+    fs::read(file).map_err(|e| format!("Could not read {file:?}: {e}"))
+}
+
+fn read_json(file: &Path) -> Result<json::JsonValue, String> {
+    let contents = fs::read_to_string(file).map_err(|e| format!("Could not read {file:?}: {e}"))?;
+    json::parse(&contents).map_err(|e| format!("Invalid json in {file:?}: {e}"))
+}
+
+/// Exports a .pil file to the json format of pilcom, without
+/// generating witness columns.
+/// If a target field is given, the fixed columns are generated and nothing
+/// is written if any constant or fixed column value does not fit into it.
+pub fn export_pil(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    output_dir: &Path,
+    field: Option<TargetField>,
+) -> Result<(), Error> {
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    if let Some(field) = field {
+        let (constants, degree) = generate_constants(&analyzed);
+        let errors = field_fit::check(&analyzed, &constants, degree, field);
+        if !errors.is_empty() {
+            return Err(Error::new(
+                error_codes::INVALID_INPUT,
+                format!(
+                    "Values do not fit into the target field:\n{}",
+                    errors.join("\n")
+                ),
+            ));
+        }
+    }
+    write_json(
+        &analyzed,
+        pil_file.file_name().unwrap().to_str().unwrap(),
+        output_dir,
+    );
+    Ok(())
+}
+
+/// Generates fixed and witness columns for a .pil file and writes them,
+/// together with the constraints, the public values and the expected
+/// verification result, as a test vector bundle to the output directory.
+pub fn export_bundle(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    output_dir: &Path,
+) -> Result<(), Error> {
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let (constants, degree) = generate_constants(&analyzed);
+    if analyzed.constant_count() != constants.len() {
+        return Err(Error::new(
+            error_codes::UNDEFINED_FIXED_COLUMNS,
+            "Not all declared constants are defined.",
+        ));
+    }
+    let commits = witgen_checked(|| {
+        commit_evaluator::generate(&analyzed, degree, &constants, no_callback())
+    })?;
+    bundle::write(output_dir, &analyzed, &constants, &commits, degree);
+    log::info!("Wrote test vector bundle to {}.", output_dir.display());
+    Ok(())
+}
+
+/// Exports the constraints of a .pil file as SMT-LIB assertions,
+/// with the fixed columns substituted by their values.
+pub fn export_smt(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    output_dir: &Path,
+) -> Result<(), Error> {
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let (constants, degree) = generate_constants(&analyzed);
+    let smt_file = format!("{}.smt2", pil_file.file_name().unwrap().to_str().unwrap());
+    fs::write(
+        output_dir.join(&smt_file),
+        smt_exporter::export(&analyzed, &constants, degree),
+    )
+    .unwrap();
+    log::info!("Wrote {smt_file}.");
+    Ok(())
+}
+
+/// Exports the dependency graph between the columns of a .pil file
+/// in the DOT language of Graphviz, optionally restricted to a namespace.
+pub fn export_dot(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    output_dir: &Path,
+    namespace: Option<&str>,
+) -> Result<(), Error> {
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let dot_file = format!("{}.dot", pil_file.file_name().unwrap().to_str().unwrap());
+    fs::write(
+        output_dir.join(&dot_file),
+        dot_exporter::export(&analyzed, namespace),
+    )
+    .unwrap();
+    log::info!("Wrote {dot_file}.");
+    Ok(())
+}
+
+/// Converts a witness between the binary format (as in `commits.bin`),
+/// csv, json and column files (`.col`, see `column_file`),
+/// determined by the file extensions, optionally
+/// restricted to some columns and rows.
+/// The binary format always has to contain all columns and rows.
+pub fn convert_witness(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    input: &Path,
+    output: &Path,
+    columns: Option<&[String]>,
+    rows: Option<Range<DegreeType>>,
+) -> Result<(), Error> {
+    let invalid_input = |e| Error::new(error_codes::INVALID_INPUT, e);
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let witness = read_witness(&analyzed, input)
+        .and_then(|witness| witness.select(columns, rows))
+        .map_err(invalid_input)?;
+    match output.extension().and_then(|e| e.to_str()) {
+        Some("csv") => fs::write(output, witness.to_csv()),
+        Some("json") => fs::write(output, witness.to_json().pretty(2)),
+        Some("col") => fs::File::create(output)
+            .and_then(|file| witness.write_column_file(&mut BufWriter::new(file))),
+        _ => fs::write(output, witness.to_binary(&analyzed).map_err(invalid_input)?),
+    }
+    .map_err(|e| {
+        Error::new(
+            error_codes::IO_FAILURE,
+            format!("Could not write {output:?}: {e}"),
+        )
+    })?;
+    log::info!("Wrote {}.", output.display());
+    Ok(())
+}
+
+/// Loads the fixed columns of a .pil file and a complete witness for it
+/// (in any format supported by `convert_witness`) and starts an interactive
+/// session that evaluates expressions at a chosen row.
+pub fn repl(pil_file: &Path, include_paths: &[PathBuf], witness_file: &Path) -> Result<(), Error> {
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let (constants, degree) = generate_constants(&analyzed);
+    let witness = read_complete_witness(&analyzed, witness_file, degree)
+        .map_err(|e| Error::new(error_codes::INVALID_INPUT, e))?;
+    let witness = witness
+        .columns
+        .iter()
+        .map(|(name, values)| (name.as_str(), values.clone()))
+        .collect::<Vec<_>>();
+    repl::run(&mut Repl::new(&analyzed, &constants, &witness, degree));
+    Ok(())
+}
+
+/// Explains the value of a cell of a complete witness (in any format supported
+/// by `convert_witness`) of a .pil or .asm file, see `explain::explain`.
+/// The inputs answer the queries like in `witness_at_degree`.
+pub fn explain(
+    file: &Path,
+    include_paths: &[PathBuf],
+    witness_file: &Path,
+    inputs: &[AbstractNumberType],
+    column: &str,
+    row: DegreeType,
+) -> Result<String, Error> {
+    let invalid_input = |e| Error::new(error_codes::INVALID_INPUT, e);
+    let is_asm = file.extension() == Some("asm".as_ref());
+    let analyzed = analyze_checked(analyze_file(file, include_paths).map(|(a, _)| a))?;
+    let (constants, degree) = generate_constants(&analyzed);
+    let witness = read_complete_witness(&analyzed, witness_file, degree).map_err(invalid_input)?;
+    let witness = witness
+        .columns
+        .iter()
+        .map(|(name, values)| (name.as_str(), values.clone()))
+        .collect::<Vec<_>>();
+    let query_callback = |query: &str| {
+        if is_asm {
+            asm_input_query(inputs, query)
+        } else {
+            pil_input_query(inputs, query)
+        }
+    };
+    explain::explain(
+        &analyzed,
+        &constants,
+        &witness,
+        degree,
+        Some(query_callback),
+        column,
+        row,
+    )
+    .map_err(invalid_input)
+}
+
+/// Generates the witness of a .pil or .asm file with the inputs answering the
+/// queries like in `witness_at_degree` and searches for a second witness
+/// with the same public values, see `satisfiability::explore`.
+/// If an smt file is given, the search is also exported as an SMT problem
+/// to it, see `satisfiability::export_smt`.
+/// @returns the report of the second witness found, if any.
+pub fn find_second_witness(
+    file: &Path,
+    include_paths: &[PathBuf],
+    inputs: &[AbstractNumberType],
+    trials: usize,
+    seed: u64,
+    smt_file: Option<&Path>,
+) -> Result<Option<String>, Error> {
+    let is_asm = file.extension() == Some("asm".as_ref());
+    let analyzed = analyze_checked(analyze_file(file, include_paths).map(|(a, _)| a))?;
+    let (constants, degree) = generate_constants(&analyzed);
+    let witness = witgen_checked(|| {
+        let query_callback = |query: &str| {
+            if is_asm {
+                asm_input_query(inputs, query)
+            } else {
+                pil_input_query(inputs, query)
+            }
+        };
+        commit_evaluator::generate(&analyzed, degree, &constants, Some(query_callback))
+    })?;
+    if let Some(smt_file) = smt_file {
+        fs::write(
+            smt_file,
+            satisfiability::export_smt(&analyzed, &constants, &witness, degree),
+        )
+        .map_err(|e| {
+            Error::new(
+                error_codes::INVALID_INPUT,
+                format!("Could not write {smt_file:?}: {e}"),
+            )
+        })?;
+        log::info!("Wrote {}.", smt_file.display());
+    }
+    Ok(
+        satisfiability::explore(&analyzed, &constants, &witness, degree, trials, seed)
+            .map(|found| found.report()),
+    )
+}
+
+/// Opens a terminal UI to browse the witness columns (in any format supported
+/// by `convert_witness`, with all rows) and the fixed columns of a .pil file.
+pub fn view_trace(
+    pil_file: &Path,
+    include_paths: &[PathBuf],
+    witness_file: &Path,
+) -> Result<(), Error> {
+    let analyzed = analyze_checked(analyzer::analyze(pil_file, include_paths))?;
+    let (constants, degree) = generate_constants(&analyzed);
+    let witness = read_complete_witness(&analyzed, witness_file, degree)
+        .map_err(|e| Error::new(error_codes::INVALID_INPUT, e))?;
+    let checker = ConstraintChecker::from_columns(&analyzed, &constants, &witness.columns, degree);
+    let columns = constraint_checker::column_values(&constants, &witness.columns);
+    let mut viewer = TraceViewer::new(checker, &analyzed.identities, columns, degree);
+    trace_viewer::run(&mut viewer)
+        .map_err(|e| Error::new(error_codes::IO_FAILURE, format!("Terminal error: {e}")))
+}
+
+/// Reads a witness like `read_witness` and checks that it contains all rows.
+fn read_complete_witness(
+    analyzed: &analyzer::Analyzed,
+    input: &Path,
+    degree: DegreeType,
+) -> Result<Witness, String> {
+    let witness = read_witness(analyzed, input)?;
+    if witness.first_row != 0
+        || witness
+            .columns
+            .iter()
+            .any(|(_, values)| values.len() as DegreeType != degree)
+    {
+        return Err(format!(
+            "The witness has to contain all {degree} rows of each column."
+        ));
     }
+    Ok(witness)
+}
+
+/// Reads a witness in the format determined by the file extension.
+fn read_witness(analyzed: &analyzer::Analyzed, input: &Path) -> Result<Witness, String> {
+    let witness = match input.extension().and_then(|e| e.to_str()) {
+        Some("csv") => {
+            let contents =
+                fs::read_to_string(input).map_err(|e| format!("Could not read {input:?}: {e}"))?;
+            Witness::from_csv(&contents)
+        }
+        Some("json") => Witness::from_json(&read_json(input)?),
+        Some("col") => Witness::from_column_file(&mut ColumnFile::open(input)?),
+        _ => Witness::from_binary(analyzed, &read(input)?),
+    };
+    witness.map_err(|e| format!("Invalid witness in {input:?}: {e}"))
+}
+
+fn write_json(analyzed: &analyzer::Analyzed, file_name: &str, output_dir: &Path) {
     let json_out = json_exporter::export(analyzed);
     let json_file = format!("{file_name}.json");
     json_out
         .write(&mut fs::File::create(output_dir.join(&json_file)).unwrap())
         .unwrap();
-    println!("Wrote {json_file}.");
-    success
+    log::info!("Wrote {json_file}.");
 }
 
-fn write_polys_file(
+/// Writes the columns in the binary layout of pilcom and zkevm-proverjs:
+/// row by row, each value as a field element in 8 little-endian bytes,
+/// the columns of a row ordered by their polynomial ID.
+/// Since the number of columns per row is taken from the json file,
+/// all columns of the given type have to be present.
+fn write_polys_file<T>(
     file: &mut impl Write,
+    analyzed: &analyzer::Analyzed,
+    poly_type: PolynomialType,
     degree: DegreeType,
-    polys: &Vec<(&str, Vec<AbstractNumberType>)>,
+    polys: &[(&str, T)],
+    value: impl Fn(&T, DegreeType) -> AbstractNumberType,
 ) {
-    for i in 0..degree as usize {
-        for (_name, constant) in polys {
-            let mut v = constant[i].clone();
-            if v.sign() == Sign::Minus {
-                // This hardcodes the goldilocks field
-                v += 0xffffffff00000001u64;
-            }
-            file.write_all(&abstract_to_degree(&v).to_le_bytes())
-                .unwrap();
+    let count = match poly_type {
+        PolynomialType::Committed => analyzed.commitment_count(),
+        PolynomialType::Constant => analyzed.constant_count(),
+        PolynomialType::Intermediate => analyzed.intermediate_count(),
+    };
+    let ids = polys
+        .iter()
+        .map(|(name, _)| analyzed.definitions[*name].0.id)
+        .collect::<Vec<_>>();
+    assert!(
+        ids.into_iter().eq(0..count as u64),
+        "Columns to write do not match the declared polynomials: {}",
+        polys.iter().map(|(name, _)| *name).join(", ")
+    );
+    for i in 0..degree {
+        for (_name, column) in polys {
+            file.write_all(&to_le_bytes(&value(column, i))).unwrap();
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::number::from_le_bytes;
+
+    use super::*;
+
+    #[test]
+    fn commits_file_layout() {
+        let temp_dir = mktemp::Temp::new_dir().unwrap();
+        compile_pil(
+            Path::new("tests/fibonacci.pil"),
+            &[],
+            &temp_dir,
+            no_callback(),
+            Some(BackendType::Mock),
+        )
+        .unwrap();
+        let commits = fs::read(temp_dir.join("commits.bin")).unwrap();
+        // 16 rows of the two columns x and y.
+        assert_eq!(commits.len(), 16 * 2 * 8);
+        let values = commits
+            .chunks(8)
+            .map(|v| from_le_bytes(v).try_into().unwrap())
+            .collect::<Vec<u64>>();
+        assert_eq!(values[..8], [1, 1, 1, 2, 2, 3, 3, 5]);
+        let constants = fs::read(temp_dir.join("constants.bin")).unwrap();
+        assert_eq!(constants.len(), 16 * 8);
+        assert_eq!(from_le_bytes(&constants[15 * 8..]), 1.into());
+        let proof_file = temp_dir.join("proof.bin");
+        let publics = verify_pil(Path::new("tests/fibonacci.pil"), &[], &proof_file, None).unwrap();
+        assert_eq!(publics.0, vec![("out".to_string(), 1597.into())]);
+        let wrong_publics = temp_dir.join("wrong_publics.bin");
+        fs::write(&wrong_publics, to_le_bytes(&7.into())).unwrap();
+        assert_eq!(
+            verify_pil(
+                Path::new("tests/fibonacci.pil"),
+                &[],
+                &proof_file,
+                Some(&wrong_publics)
+            ),
+            Err(Error::new(
+                error_codes::VERIFICATION_FAILURE,
+                "Public value out is 1597, but expected 7."
+            ))
+        );
+
+        let mut tampered = Proof::from_binary(&fs::read(&proof_file).unwrap()).unwrap();
+        tampered.data[0] = 2;
+        let tampered_file = temp_dir.join("tampered.json");
+        fs::write(&tampered_file, tampered.to_json().dump()).unwrap();
+        let results = verify_pil_batch(
+            Path::new("tests/fibonacci.pil"),
+            &[],
+            &[&proof_file, &tampered_file, &temp_dir.join("missing.bin")],
+        )
+        .unwrap();
+        assert_eq!(results[0], Ok(publics));
+        assert!(results[1]
+            .as_ref()
+            .unwrap_err()
+            .contains("is not satisfied in row 0"));
+        assert!(results[2]
+            .as_ref()
+            .unwrap_err()
+            .starts_with("Could not read"));
+    }
+
+    #[test]
+    fn convert_witness_errors() {
+        let temp_dir = mktemp::Temp::new_dir().unwrap();
+        let pil = Path::new("tests/fibonacci.pil");
+        let input = temp_dir.join("witness.csv");
+        let output = temp_dir.join("witness.json");
+        fs::write(&input, "row,Fibonacci.x\n0,1\n1,0xg\n").unwrap();
+        let error = convert_witness(pil, &[], &input, &output, None, None).unwrap_err();
+        assert_eq!(error.code, error_codes::INVALID_INPUT);
+        assert!(error
+            .message
+            .contains("Column Fibonacci.x, row 1: Invalid value \"0xg\""));
+        fs::write(&input, "row,Fibonacci.x\n0,1\n1,-1\n").unwrap();
+        let error = convert_witness(pil, &[], &input, &output, None, Some(1..3)).unwrap_err();
+        assert_eq!(error.code, error_codes::INVALID_INPUT);
+        assert_eq!(
+            convert_witness(pil, &[], &input, &temp_dir.join("commits.bin"), None, None)
+                .unwrap_err()
+                .message,
+            "Witness column Fibonacci.y is missing."
+        );
+        convert_witness(pil, &[], &input, &output, None, Some(1..2)).unwrap();
+        assert_eq!(
+            json::parse(&fs::read_to_string(&output).unwrap()).unwrap()["firstRow"],
+            1
+        );
+    }
+
+    #[test]
+    fn check_examples() {
+        for file in ["tests/fibonacci.pil", "tests/simple_sum.asm"] {
+            let (diagnostics, sources) = check_file(Path::new(file), &[]);
+            assert_eq!(diagnostics, vec![]);
+            assert_eq!(sources.len(), 1);
+        }
+        let (_, sources) = check_file(Path::new("tests/polygon-hermez/mem_align.pil"), &[]);
+        assert!(sources.len() > 1);
+        let temp_dir = mktemp::Temp::new_dir().unwrap();
+        let invalid = temp_dir.join("invalid.pil");
+        fs::write(&invalid, "namespace F(4);\npol commit x\nx = 1;\n").unwrap();
+        let (diagnostics, _) = check_file(&invalid, &[]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "E0002");
+        assert_eq!(diagnostics[0].source.line, 3);
+        let duplicate = temp_dir.join("duplicate.pil");
+        fs::write(
+            &duplicate,
+            "namespace F(4);\npol commit x;\npol commit x;\n",
+        )
+        .unwrap();
+        let (diagnostics, sources) = check_file(&duplicate, &[]);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].to_json().dump(),
+            format!(
+                r#"{{"code":"E0005","severity":"error","message":"Polynomial F.x was declared twice.","span":{{"file":"{0}","line":1}},"rendered":"{0}:1: error: Polynomial F.x was declared twice."}}"#,
+                duplicate.display()
+            )
+        );
+        assert_eq!(sources, vec![duplicate.clone()]);
+        assert_eq!(
+            witgen_smoke_test(Path::new("tests/fixed_from_file.pil"), &[], &[], 8),
+            Ok(())
+        );
+        assert_eq!(
+            witgen_smoke_test(Path::new("tests/fibonacci.pil"), &[], &[], 8),
+            Err("Witness generation at degree 8 failed.".to_string())
+        );
+    }
+
+    #[test]
+    fn separate_stages() {
+        let pil_file = Path::new("tests/fibonacci.pil");
+        let compiled = mktemp::Temp::new_dir().unwrap();
+        compile_pil(pil_file, &[], &compiled, no_callback(), None).unwrap();
+        let staged = mktemp::Temp::new_dir().unwrap();
+        let error =
+            generate_witness(pil_file, &[], &staged, no_callback(), None, None).unwrap_err();
+        assert_eq!(error.code, error_codes::INVALID_INPUT);
+        assert!(error.message.starts_with("Could not read"));
+        generate_fixed(pil_file, &[], &staged).unwrap();
+        generate_witness(pil_file, &[], &staged, no_callback(), None, None).unwrap();
+        for file in ["constants.bin", "commits.bin"] {
+            assert_eq!(
+                fs::read(compiled.join(file)).unwrap(),
+                fs::read(staged.join(file)).unwrap()
+            );
+        }
+        prove_pil(pil_file, &[], &staged, BackendType::Mock).unwrap();
+        let publics = verify_pil(pil_file, &[], &staged.join("proof.bin"), None).unwrap();
+        assert_eq!(publics.0, vec![("out".to_string(), 1597.into())]);
+
+        fs::write(staged.join("commits.bin"), [0; 12]).unwrap();
+        assert!(prove_pil(pil_file, &[], &staged, BackendType::Mock)
+            .unwrap_err()
+            .message
+            .contains("does not match the number of columns"));
+        fs::write(staged.join("commits.bin"), [0; 32]).unwrap();
+        assert!(prove_pil(pil_file, &[], &staged, BackendType::Mock)
+            .unwrap_err()
+            .message
+            .ends_with("contains 2 rows, but the degree is 16."));
+    }
+
+    #[test]
+    fn analysis_errors() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        let undeclared = dir.join("undeclared.pil");
+        fs::write(&undeclared, "namespace F(4);\npol commit x;\nx = y;\n").unwrap();
+        let expected = Error::new(
+            "E0001",
+            "undeclared.pil:3: Reference to undeclared polynomial F.y.",
+        );
+        assert_eq!(
+            generate_fixed(&undeclared, &[], &dir),
+            Err(expected.clone())
+        );
+        assert_eq!(
+            generate_witness(&undeclared, &[], &dir, no_callback(), None, None),
+            Err(expected.clone())
+        );
+        assert_eq!(
+            compile_pil(&undeclared, &[], &dir, no_callback(), None),
+            Err(expected)
+        );
+        let duplicate = dir.join("duplicate.pil");
+        fs::write(
+            &duplicate,
+            "namespace F(4);\npol commit x;\npol commit x;\n",
+        )
+        .unwrap();
+        assert_eq!(
+            generate_fixed(&duplicate, &[], &dir),
+            Err(Error::new(
+                error_codes::ANALYZER_ERROR,
+                "Polynomial F.x was declared twice."
+            ))
+        );
+        assert_eq!(
+            export_smt(&duplicate, &[], &dir).unwrap_err().code,
+            error_codes::ANALYZER_ERROR
+        );
+        assert_eq!(
+            verify_pil_batch(&duplicate, &[], &[]).unwrap_err().code,
+            error_codes::ANALYZER_ERROR
+        );
+        let undefined = dir.join("undefined.pil");
+        fs::write(
+            &undefined,
+            "namespace F(4);\npol constant C;\npol commit x;\nx = C;\n",
+        )
+        .unwrap();
+        assert_eq!(
+            compile_pil(&undefined, &[], &dir, no_callback(), None)
+                .unwrap_err()
+                .code,
+            error_codes::UNDEFINED_FIXED_COLUMNS
+        );
+        let stuck = dir.join("stuck.pil");
+        fs::write(&stuck, "namespace F(4);\npol commit x;\nx * x = 2;\n").unwrap();
+        assert_eq!(
+            compile_pil(&stuck, &[], &dir, no_callback(), None)
+                .unwrap_err()
+                .code,
+            error_codes::WITGEN_STUCK
+        );
+    }
+
+    #[test]
+    fn stages_without_fixed_columns() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        let pil_file = dir.join("no_fixed.pil");
+        fs::write(
+            &pil_file,
+            "namespace F(4);\npol commit x;\nx = 7;\npublic last = x(3);\n",
+        )
+        .unwrap();
+        generate_fixed(&pil_file, &[], &dir).unwrap();
+        generate_witness(&pil_file, &[], &dir, no_callback(), None, None).unwrap();
+        prove_pil(&pil_file, &[], &dir, BackendType::Mock).unwrap();
+        let proof_file = dir.join("proof.bin");
+        let publics = verify_pil(&pil_file, &[], &proof_file, None).unwrap();
+        assert_eq!(publics.0, vec![("last".to_string(), 7.into())]);
+        assert_eq!(
+            verify_pil_batch(&pil_file, &[], &[&proof_file]),
+            Ok(vec![Ok(publics)])
+        );
+    }
+}
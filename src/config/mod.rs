@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use toml::Value;
+
+use crate::backend::BackendType;
+use crate::field_fit::TargetField;
+use crate::number::{parse_number, AbstractNumberType, DegreeType};
+use crate::query_callbacks;
+
+/// Name of the project configuration file.
+pub const FILE_NAME: &str = "powdr.toml";
+
+/// Project settings read from `powdr.toml`, which serve as defaults for the
+/// command line flags. Paths are relative to the directory of the file.
+///
+/// ```toml
+/// entry = "main.asm"
+/// include_paths = ["lib"]
+/// field = "goldilocks"
+/// degree = 1024
+/// backend = "mock"
+/// output_directory = "build"
+/// inputs = [1, 2, "0x10"]
+/// ```
+///
+/// Instead of `inputs`, the inputs can be read from `inputs_file`,
+/// which contains numbers separated by commas or newlines.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// The .pil or .asm file the commands operate on.
+    pub entry: Option<PathBuf>,
+    /// Directories searched for included files that are not found
+    /// relative to the including file.
+    pub include_paths: Vec<PathBuf>,
+    /// The field of the backend, values are checked to fit on export.
+    pub field: Option<TargetField>,
+    /// The degree at which `check` generates fixed and witness columns.
+    pub degree: Option<DegreeType>,
+    /// The backend used to generate proofs.
+    pub backend: Option<BackendType>,
+    pub output_directory: Option<PathBuf>,
+    /// Free inputs of an assembly program or answers to the
+    /// queries `"input", <index>` in PIL.
+    pub inputs: Option<Vec<AbstractNumberType>>,
+}
+
+impl Config {
+    /// Searches `powdr.toml` in the given directory and its parents.
+    /// @returns the settings of the first file found, if any.
+    pub fn find(dir: &Path) -> Result<Option<Config>, String> {
+        for dir in dir.ancestors() {
+            let file = dir.join(FILE_NAME);
+            if file.is_file() {
+                return Config::load(&file).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn load(file: &Path) -> Result<Config, String> {
+        let contents =
+            fs::read_to_string(file).map_err(|e| format!("Could not read {file:?}: {e}"))?;
+        Config::parse(&contents, file.parent().unwrap_or(Path::new(".")))
+            .map_err(|e| format!("Invalid {}: {e}", file.display()))
+    }
+
+    /// Parses the contents of a configuration file located in `base_dir`.
+    pub fn parse(contents: &str, base_dir: &Path) -> Result<Config, String> {
+        let table = match contents.parse::<Value>().map_err(|e| e.to_string())? {
+            Value::Table(table) => table,
+            _ => return Err("Expected a table.".to_string()),
+        };
+        let path = |value: &Value| string(value).map(|s| base_dir.join(s));
+        let mut config = Config::default();
+        let mut inputs_file = None;
+        for (key, value) in &table {
+            match key.as_str() {
+                "entry" => config.entry = Some(path(value)?),
+                "include_paths" => {
+                    config.include_paths =
+                        array(value)?.iter().map(path).collect::<Result<_, _>>()?
+                }
+                "field" => config.field = Some(TargetField::from_str(string(value)?, true)?),
+                "degree" => {
+                    config.degree = Some(
+                        value
+                            .as_integer()
+                            .filter(|d| *d > 0)
+                            .ok_or("Expected a positive degree.")?
+                            as DegreeType,
+                    )
+                }
+                "backend" => config.backend = Some(BackendType::from_str(string(value)?, true)?),
+                "output_directory" => config.output_directory = Some(path(value)?),
+                "inputs" => {
+                    config.inputs =
+                        Some(array(value)?.iter().map(number).collect::<Result<_, _>>()?)
+                }
+                "inputs_file" => inputs_file = Some(path(value)?),
+                _ => return Err(format!("Unknown key {key}.")),
+            }
+        }
+        if let Some(file) = inputs_file {
+            if config.inputs.is_some() {
+                return Err("Only one of inputs and inputs_file can be given.".to_string());
+            }
+            config.inputs = Some(query_callbacks::read_inputs(&file)?);
+        }
+        Ok(config)
+    }
+}
+
+fn string(value: &Value) -> Result<&str, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("Expected a string instead of {value}."))
+}
+
+fn array(value: &Value) -> Result<&Vec<Value>, String> {
+    value
+        .as_array()
+        .ok_or_else(|| format!("Expected an array instead of {value}."))
+}
+
+/// Numbers can be given as integers or, if they do not fit 64 bits,
+/// as decimal or hexadecimal strings.
+fn number(value: &Value) -> Result<AbstractNumberType, String> {
+    match value {
+        Value::Integer(n) => Ok((*n).into()),
+        Value::String(s) => parse_number(s).ok_or(format!("Invalid number: {s}")),
+        _ => Err(format!("Expected a number instead of {value}.")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_all_keys() {
+        let config = Config::parse(
+            r#"
+            entry = "main.asm"
+            include_paths = ["lib", "/usr/share/powdr"]
+            field = "baby-bear"
+            degree = 1024
+            backend = "mock"
+            output_directory = "build"
+            inputs = [1, -2, "0x10"]
+            "#,
+            Path::new("project"),
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                entry: Some(PathBuf::from("project/main.asm")),
+                include_paths: vec![
+                    PathBuf::from("project/lib"),
+                    PathBuf::from("/usr/share/powdr")
+                ],
+                field: Some(TargetField::BabyBear),
+                degree: Some(1024),
+                backend: Some(BackendType::Mock),
+                output_directory: Some(PathBuf::from("project/build")),
+                inputs: Some(vec![1.into(), (-2).into(), 16.into()]),
+            }
+        );
+    }
+
+    #[test]
+    fn inputs_file() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        fs::write(dir.join("inputs.csv"), "7, 8\n9\n").unwrap();
+        fs::write(dir.join(FILE_NAME), "inputs_file = \"inputs.csv\"\n").unwrap();
+        let nested = dir.join("src");
+        fs::create_dir(&nested).unwrap();
+        let config = Config::find(&nested).unwrap().unwrap();
+        assert_eq!(config.inputs, Some(vec![7.into(), 8.into(), 9.into()]));
+    }
+
+    #[test]
+    fn include_paths() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        fs::create_dir(dir.join("lib")).unwrap();
+        fs::write(
+            dir.join("lib/byte.pil"),
+            "namespace Byte(4);\npol commit b;\n",
+        )
+        .unwrap();
+        fs::write(dir.join("lib/values.csv"), "1,2,3,4").unwrap();
+        fs::write(
+            dir.join("main.pil"),
+            "include \"byte.pil\";\ncol fixed V from \"values.csv\";\n",
+        )
+        .unwrap();
+        let config = Config::parse("include_paths = [\"lib\"]", &dir).unwrap();
+        let analyzed =
+            crate::analyzer::analyze(&dir.join("main.pil"), &config.include_paths).unwrap();
+        assert!(analyzed.definitions.contains_key("Byte.b"));
+        assert!(analyzed.definitions.contains_key("Byte.V"));
+    }
+
+    #[test]
+    fn errors() {
+        let parse = |contents| Config::parse(contents, Path::new(".")).unwrap_err();
+        assert_eq!(parse("entri = \"main.pil\""), "Unknown key entri.");
+        assert_eq!(parse("degree = 0"), "Expected a positive degree.");
+        assert_eq!(parse("inputs = [\"x\"]"), "Invalid number: x");
+        assert!(parse("field = \"bn254\"").contains("bn254"));
+    }
+}
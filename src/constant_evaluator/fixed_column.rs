@@ -0,0 +1,90 @@
+use crate::number::{is_zero, AbstractNumberType, DegreeType};
+
+/// Columns with at most one nonzero value every `SPARSE_FACTOR` rows
+/// are stored sparsely.
+const SPARSE_FACTOR: usize = 16;
+
+/// The values of a fixed column in a compact representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixedColumn {
+    /// The values of all rows or, for a periodic column, of the first
+    /// period; the value in row `i` is `values[i % values.len()]`.
+    Dense(Vec<AbstractNumberType>),
+    /// A column of the given degree that is zero except in the listed rows,
+    /// stored as (row, value) pairs sorted by row.
+    Sparse(DegreeType, Vec<(DegreeType, AbstractNumberType)>),
+}
+
+impl FixedColumn {
+    /// Chooses the most compact representation for the full values of a column.
+    pub fn compress(mut values: Vec<AbstractNumberType>) -> Self {
+        let len = values.len();
+        if let Some(period) = (1..len)
+            .filter(|p| len.is_multiple_of(*p))
+            .find(|&p| (p..len).all(|i| values[i] == values[i - p]))
+        {
+            values.truncate(period);
+            return FixedColumn::Dense(values);
+        }
+        let nonzero = values.iter().filter(|v| !is_zero(v)).count();
+        if len > 0 && nonzero * SPARSE_FACTOR <= len {
+            FixedColumn::Sparse(
+                len as DegreeType,
+                values
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, v)| !is_zero(v))
+                    .map(|(i, v)| (i as DegreeType, v))
+                    .collect(),
+            )
+        } else {
+            FixedColumn::Dense(values)
+        }
+    }
+
+    /// @returns the value in the given row.
+    pub fn get(&self, row: DegreeType) -> AbstractNumberType {
+        match self {
+            FixedColumn::Dense(values) => values[row as usize % values.len()].clone(),
+            FixedColumn::Sparse(degree, entries) => {
+                let row = row % degree;
+                entries
+                    .binary_search_by_key(&row, |(r, _)| *r)
+                    .map(|i| entries[i].1.clone())
+                    .unwrap_or_else(|_| 0.into())
+            }
+        }
+    }
+
+    /// @returns the first row that contains the given value.
+    pub fn position(&self, value: &AbstractNumberType) -> Option<DegreeType> {
+        match self {
+            FixedColumn::Dense(values) => values
+                .iter()
+                .position(|v| v == value)
+                .map(|i| i as DegreeType),
+            FixedColumn::Sparse(degree, entries) => {
+                if is_zero(value) {
+                    // The first row that is not listed.
+                    let first_gap = entries
+                        .iter()
+                        .enumerate()
+                        .find(|(i, (row, _))| *i as DegreeType != *row)
+                        .map(|(i, _)| i)
+                        .unwrap_or(entries.len()) as DegreeType;
+                    (first_gap < *degree).then_some(first_gap)
+                } else {
+                    entries
+                        .iter()
+                        .find(|(_, v)| v == value)
+                        .map(|(row, _)| *row)
+                }
+            }
+        }
+    }
+
+    /// @returns the full values of the column for the given degree.
+    pub fn to_values(&self, degree: DegreeType) -> Vec<AbstractNumberType> {
+        (0..degree).map(|row| self.get(row)).collect()
+    }
+}
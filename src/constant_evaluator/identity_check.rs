@@ -0,0 +1,31 @@
+use crate::analyzer::{Analyzed, IdentityKind};
+use crate::constraint_checker::ConstraintChecker;
+use crate::number::{AbstractNumberType, DegreeType};
+
+use super::FixedColumn;
+
+/// Checks all identities that only reference fixed columns and constants
+/// on all rows, so that bugs in the construction of tables are found
+/// before witness generation.
+/// Polynomial identities, plookups and permutations are checked,
+/// connect identities are ignored.
+/// Panics with a description of the first violation.
+pub fn check_identities(
+    analyzed: &Analyzed,
+    constants: &[(&str, FixedColumn)],
+    degree: DegreeType,
+) {
+    let checker = ConstraintChecker::from_columns(
+        analyzed,
+        constants,
+        &[] as &[(&str, Vec<AbstractNumberType>)],
+        degree,
+    );
+    for identity in analyzed.identities.iter().filter(|identity| {
+        identity.kind != IdentityKind::Connect && checker.references_available(identity)
+    }) {
+        if let Err(error) = checker.check(identity) {
+            panic!("The fixed columns violate an identity:\n{error}");
+        }
+    }
+}
@@ -1,44 +1,178 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+use rayon::prelude::*;
 
 use crate::analyzer::{
-    Analyzed, BinaryOperator, Expression, FunctionValueDefinition, UnaryOperator,
+    evaluator, Analyzed, BinaryOperator, Expression, FunctionValueDefinition, UnaryOperator,
 };
-use crate::number::{abstract_to_degree, AbstractNumberType, DegreeType};
+use crate::number::{abstract_to_degree, is_zero, AbstractNumberType, DegreeType};
+
+pub use self::fixed_column::FixedColumn;
+pub use self::identity_check::check_identities;
+
+mod fixed_column;
+mod identity_check;
 
 /// Generates the constant polynomial values for all constant polynomials
 /// that are defined (and not just declared).
+/// The columns are stored in a compact representation (dense, of which
+/// periodic columns only store one period, or sparse).
 /// @returns the values (in source order) and the degree of the polynomials.
-pub fn generate(analyzed: &Analyzed) -> (Vec<(&str, Vec<AbstractNumberType>)>, DegreeType) {
-    let mut degree = None;
-    let mut other_constants = HashMap::new();
-    for (poly, value) in analyzed.constant_polys_in_source_order() {
-        if let Some(value) = value {
-            if let Some(degree) = degree {
-                assert!(degree == poly.degree);
-            } else {
-                degree = Some(poly.degree);
-            }
-            let values = generate_values(analyzed, poly.degree, value, &other_constants);
-            other_constants.insert(&poly.absolute_name, values);
+pub fn generate(analyzed: &Analyzed) -> (Vec<(&str, FixedColumn)>, DegreeType) {
+    let degree = analyzed
+        .constant_polys_in_source_order()
+        .into_iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(poly, _)| poly.degree)
+        .reduce(|d1, d2| {
+            assert!(d1 == d2);
+            d1
+        })
+        .unwrap_or_default();
+    (generate_at_degree(analyzed, degree), degree)
+}
+
+/// Generates the full values of all defined constant polynomials without
+/// running the rest of the pipeline, so that external provers and test
+/// harnesses can reuse the fixed column generation.
+/// @returns the values (in source order, by absolute name) and the degree of the polynomials.
+pub fn generate_values_by_name(
+    analyzed: &Analyzed,
+) -> (Vec<(&str, Vec<AbstractNumberType>)>, DegreeType) {
+    let (constants, degree) = generate(analyzed);
+    let values = constants
+        .into_iter()
+        .map(|(name, column)| (name, column.to_values(degree)))
+        .collect();
+    (values, degree)
+}
+
+/// Generates the values of all defined constant polynomials as if
+/// they had the given degree, independent of their declared degree.
+/// This way, the same definitions can be instantiated at several degrees.
+/// @returns the values in source order.
+pub fn generate_at_degree(analyzed: &Analyzed, degree: DegreeType) -> Vec<(&str, FixedColumn)> {
+    let mut pending = analyzed
+        .constant_polys_in_source_order()
+        .into_iter()
+        .filter_map(|(poly, value)| value.as_ref().map(|value| (poly, value)))
+        .collect::<Vec<_>>();
+    let defined = pending
+        .iter()
+        .map(|(poly, _)| poly.absolute_name.as_str())
+        .collect::<HashSet<_>>();
+    for (poly, value) in &pending {
+        if let Some(name) = referenced_columns(value)
+            .into_iter()
+            .find(|name| !defined.contains(name))
+        {
+            panic!(
+                "Fixed column {} references {name}, which is not a defined fixed column.",
+                poly.absolute_name
+            );
         }
     }
+    let mut other_constants = HashMap::new();
+    while !pending.is_empty() {
+        // All columns that only reference already computed columns
+        // are independent of each other and computed in parallel.
+        let (ready, blocked): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(poly, value)| {
+            referenced_columns(value)
+                .iter()
+                .filter(|name| **name != poly.absolute_name)
+                .all(|name| other_constants.contains_key(name))
+        });
+        assert!(
+            !ready.is_empty(),
+            "Cyclic dependencies between fixed columns: {}",
+            blocked
+                .iter()
+                .map(|(poly, _)| poly.absolute_name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let computed = ready
+            .into_par_iter()
+            .map(|(poly, value)| {
+                let name = poly.absolute_name.as_str();
+                let values = generate_values(analyzed, name, degree, value, &other_constants);
+                (name, FixedColumn::compress(values))
+            })
+            .collect::<Vec<_>>();
+        other_constants.extend(computed);
+        pending = blocked;
+    }
     let mut values = Vec::new();
     for (poly, _) in analyzed.constant_polys_in_source_order() {
-        if let Some(v) = other_constants.get_mut(poly.absolute_name.as_str()) {
-            values.push((poly.absolute_name.as_str(), std::mem::take(v)));
+        if let Some(v) = other_constants.remove(poly.absolute_name.as_str()) {
+            values.push((poly.absolute_name.as_str(), v));
         };
     }
-    (values, degree.unwrap_or_default())
+    values
+}
+
+/// @returns the names of all (fixed) columns called in the definition.
+fn referenced_columns(body: &FunctionValueDefinition) -> HashSet<&str> {
+    fn collect<'a>(expr: &'a Expression, names: &mut HashSet<&'a str>) {
+        match expr {
+            Expression::FunctionCall(name, args) => {
+                names.insert(name.as_str());
+                args.iter().for_each(|a| collect(a, names));
+            }
+            Expression::Tuple(items) => items.iter().for_each(|i| collect(i, names)),
+            Expression::BinaryOperation(left, _, right) => {
+                collect(left, names);
+                collect(right, names);
+            }
+            Expression::UnaryOperation(_, e) => collect(e, names),
+            Expression::IfElse(condition, if_true, if_false) => {
+                collect(condition, names);
+                collect(if_true, names);
+                collect(if_false, names);
+            }
+            Expression::Constant(_)
+            | Expression::PolynomialReference(_)
+            | Expression::LocalVariableReference(_)
+            | Expression::PublicReference(_)
+            | Expression::Number(_)
+            | Expression::String(_) => {}
+        }
+    }
+    let mut names = HashSet::new();
+    match body {
+        FunctionValueDefinition::Mapping(e) | FunctionValueDefinition::Query(e) => {
+            collect(e, &mut names)
+        }
+        FunctionValueDefinition::Recurrence(e, initial) => {
+            collect(e, &mut names);
+            initial.iter().for_each(|e| collect(e, &mut names));
+        }
+        FunctionValueDefinition::Array(items) => items.iter().for_each(|e| collect(e, &mut names)),
+    }
+    names
+}
+
+/// Provides the values of other constant polynomials to the evaluator.
+trait OtherConstants: Sync {
+    fn value(&self, name: &str, row: DegreeType) -> AbstractNumberType;
+}
+
+impl OtherConstants for HashMap<&str, FixedColumn> {
+    fn value(&self, name: &str, row: DegreeType) -> AbstractNumberType {
+        self[name].get(row)
+    }
 }
 
 fn generate_values(
     analyzed: &Analyzed,
+    name: &str,
     degree: DegreeType,
     body: &FunctionValueDefinition,
-    other_constants: &HashMap<&str, Vec<AbstractNumberType>>,
+    other_constants: &HashMap<&str, FixedColumn>,
 ) -> Vec<AbstractNumberType> {
     match body {
         FunctionValueDefinition::Mapping(body) => (0..degree)
+            .into_par_iter()
             .map(|i| {
                 Evaluator {
                     analyzed,
@@ -56,18 +190,83 @@ fn generate_values(
             };
             let mut values: Vec<_> = values.iter().map(|v| evaluator.evaluate(v)).collect();
             // TODO we fill with zeros - should we warn? should we repeat?
-            if degree as usize > values.len() {
-                values.resize(degree as usize, 0.into())
-            }
+            // Values beyond the degree are dropped.
+            values.resize(degree as usize, 0.into());
             values
         }
+        FunctionValueDefinition::Recurrence(body, initial) => {
+            assert!(
+                initial.len() as DegreeType <= degree,
+                "Too many initial values for the recurrence of {name}."
+            );
+            generate_recurrence(analyzed, name, body, initial, degree, other_constants)
+        }
         FunctionValueDefinition::Query(_) => panic!("Query used for fixed column."),
     }
 }
 
+/// Computes the first `rows` rows of a column defined by a recurrence,
+/// row by row, such that the body can refer to earlier rows of the column.
+fn generate_recurrence(
+    analyzed: &Analyzed,
+    name: &str,
+    body: &Expression,
+    initial: &[Expression],
+    rows: DegreeType,
+    other_constants: &dyn OtherConstants,
+) -> Vec<AbstractNumberType> {
+    let evaluator = Evaluator {
+        analyzed,
+        variables: &[],
+        other_constants,
+    };
+    let mut values = initial
+        .iter()
+        .map(|v| evaluator.evaluate(v))
+        .collect::<Vec<_>>();
+    for row in values.len() as DegreeType..rows {
+        let value = Evaluator {
+            analyzed,
+            variables: &[row.into()],
+            other_constants: &WithComputedRows {
+                name,
+                values: &values,
+                other_constants,
+            },
+        }
+        .evaluate(body);
+        values.push(value);
+    }
+    values.truncate(rows as usize);
+    values
+}
+
+/// Provides the already computed rows of a column defined
+/// by a recurrence in addition to the other constants.
+struct WithComputedRows<'a> {
+    name: &'a str,
+    values: &'a [AbstractNumberType],
+    other_constants: &'a dyn OtherConstants,
+}
+
+impl<'a> OtherConstants for WithComputedRows<'a> {
+    fn value(&self, name: &str, row: DegreeType) -> AbstractNumberType {
+        if name == self.name {
+            self.values
+                .get(row as usize)
+                .unwrap_or_else(|| {
+                    panic!("Recurrence for {name} references row {row} before it is computed.")
+                })
+                .clone()
+        } else {
+            self.other_constants.value(name, row)
+        }
+    }
+}
+
 struct Evaluator<'a> {
     analyzed: &'a Analyzed,
-    other_constants: &'a HashMap<&'a str, Vec<AbstractNumberType>>,
+    other_constants: &'a dyn OtherConstants,
     variables: &'a [AbstractNumberType],
 }
 
@@ -88,8 +287,15 @@ impl<'a> Evaluator<'a> {
             Expression::FunctionCall(name, args) => {
                 let arg_values = args.iter().map(|a| self.evaluate(a)).collect::<Vec<_>>();
                 assert!(arg_values.len() == 1);
-                let values = &self.other_constants[name.as_str()];
-                values[abstract_to_degree(&arg_values[0]) as usize % values.len()].clone()
+                self.other_constants
+                    .value(name, abstract_to_degree(&arg_values[0]))
+            }
+            Expression::IfElse(condition, if_true, if_false) => {
+                if is_zero(&self.evaluate(condition)) {
+                    self.evaluate(if_false)
+                } else {
+                    self.evaluate(if_true)
+                }
             }
         }
     }
@@ -100,26 +306,7 @@ impl<'a> Evaluator<'a> {
         op: &BinaryOperator,
         right: &Expression,
     ) -> AbstractNumberType {
-        let left = self.evaluate(left);
-        let right = self.evaluate(right);
-        match op {
-            BinaryOperator::Add => left + right,
-            BinaryOperator::Sub => left - right,
-            BinaryOperator::Mul => left * right,
-            BinaryOperator::Div => {
-                if left == 0.into() {
-                    0.into()
-                } else {
-                    left / right
-                }
-            }
-            BinaryOperator::Pow => left.pow(abstract_to_degree(&right) as u32),
-            BinaryOperator::Mod => left % right,
-            BinaryOperator::BinaryAnd => left & right,
-            BinaryOperator::BinaryOr => left | right,
-            BinaryOperator::ShiftLeft => left << abstract_to_degree(&right),
-            BinaryOperator::ShiftRight => left >> abstract_to_degree(&right),
-        }
+        evaluator::evaluate_binary_operation(self.evaluate(left), op, self.evaluate(right))
     }
 
     fn evaluate_unary_operation(
@@ -127,11 +314,7 @@ impl<'a> Evaluator<'a> {
         op: &UnaryOperator,
         expr: &Expression,
     ) -> AbstractNumberType {
-        let v = self.evaluate(expr);
-        match op {
-            UnaryOperator::Plus => v,
-            UnaryOperator::Minus => -v,
-        }
+        evaluator::evaluate_unary_operation(op, self.evaluate(expr))
     }
 }
 
@@ -141,8 +324,8 @@ mod test {
 
     use super::*;
 
-    fn convert(input: Vec<i32>) -> Vec<AbstractNumberType> {
-        input.into_iter().map(|x| x.into()).collect()
+    fn convert(input: Vec<i32>) -> FixedColumn {
+        FixedColumn::Dense(input.into_iter().map(|x| x.into()).collect())
     }
 
     #[test]
@@ -152,7 +335,7 @@ mod test {
             namespace F(%N);
             pol constant LAST(i) { 1 - (i - (%N - 1)) / (i - (%N - 1)) };
         "#;
-        let analyzed = analyze_string(src);
+        let analyzed = analyze_string(src).unwrap();
         let (constants, degree) = generate(&analyzed);
         assert_eq!(degree, 8);
         assert_eq!(
@@ -168,7 +351,7 @@ mod test {
             namespace F(%N);
             pol constant EVEN(i) { 2 * (i - 1) };
         "#;
-        let analyzed = analyze_string(src);
+        let analyzed = analyze_string(src).unwrap();
         let (constants, degree) = generate(&analyzed);
         assert_eq!(degree, 8);
         assert_eq!(
@@ -185,7 +368,7 @@ mod test {
             macro minus_one(X) { X - 1 };
             pol constant EVEN(i) { 2 * minus_one(i) };
         "#;
-        let analyzed = analyze_string(src);
+        let analyzed = analyze_string(src).unwrap();
         let (constants, degree) = generate(&analyzed);
         assert_eq!(degree, 8);
         assert_eq!(
@@ -206,7 +389,7 @@ mod test {
             macro ite(C, T, F) { is_one(C) * T + is_zero(C) * F };
             pol constant TEN(i) { ite(is_equal(i, 10), 1, 0) };
         "#;
-        let analyzed = analyze_string(src);
+        let analyzed = analyze_string(src).unwrap();
         let (constants, degree) = generate(&analyzed);
         assert_eq!(degree, 12);
         assert_eq!(
@@ -228,7 +411,7 @@ mod test {
             col fixed half_nibble(i) { i & 0x7 };
             col fixed doubled_half_nibble(i) { half_nibble(i / 2) };
         "#;
-        let analyzed = analyze_string(src);
+        let analyzed = analyze_string(src).unwrap();
         let (constants, degree) = generate(&analyzed);
         assert_eq!(degree, 10);
         assert_eq!(constants.len(), 4);
@@ -238,10 +421,7 @@ mod test {
         );
         assert_eq!(
             constants[1],
-            (
-                "F.doub",
-                convert([1i32, 3, 5, 7, 9, 1, 3, 5, 7, 9].to_vec())
-            )
+            ("F.doub", convert([1i32, 3, 5, 7, 9].to_vec()))
         );
         assert_eq!(
             constants[2],
@@ -268,7 +448,7 @@ mod test {
             col fixed empty = [];
             col fixed ref_other = [%N-1, alt(1), 8];
         "#;
-        let analyzed = analyze_string(src);
+        let analyzed = analyze_string(src).unwrap();
         let (constants, degree) = generate(&analyzed);
         assert_eq!(degree, 10);
         assert_eq!(constants.len(), 3);
@@ -276,7 +456,7 @@ mod test {
             constants[0],
             ("F.alt", convert([0i32, 1, 0, 1, 0, 1, 0, 0, 0, 0].to_vec()))
         );
-        assert_eq!(constants[1], ("F.empty", convert([0i32; 10].to_vec())));
+        assert_eq!(constants[1], ("F.empty", convert([0i32].to_vec())));
         assert_eq!(
             constants[2],
             (
@@ -285,4 +465,338 @@ mod test {
             )
         );
     }
+
+    #[test]
+    pub fn test_periodic() {
+        let src = r#"
+            constant %N = 12;
+            namespace F(%N);
+            col fixed ONE(i) { 1 };
+            col fixed ALT(i) { i % 2 };
+            col fixed QUARTER(i) { (i / 3) % 2 };
+            col fixed NOT_DIVIDING(i) { i % 5 };
+            col fixed SHIFTED(i) { ALT(i + 1) };
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 12);
+        assert_eq!(
+            constants,
+            vec![
+                ("F.ONE", convert(vec![1])),
+                ("F.ALT", convert(vec![0, 1])),
+                ("F.QUARTER", convert(vec![0, 0, 0, 1, 1, 1])),
+                (
+                    "F.NOT_DIVIDING",
+                    convert(vec![0, 1, 2, 3, 4, 0, 1, 2, 3, 4, 0, 1])
+                ),
+                ("F.SHIFTED", convert(vec![1, 0])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_dependency_order() {
+        let src = r#"
+            constant %N = 4;
+            namespace F(%N);
+            col fixed later_plus_one(i) { seq(i) + 1 };
+            col fixed seq(i) { i };
+            col fixed independent(i) { 2 * i };
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 4);
+        assert_eq!(
+            constants,
+            vec![
+                ("F.later_plus_one", convert(vec![1, 2, 3, 4])),
+                ("F.seq", convert(vec![0, 1, 2, 3])),
+                ("F.independent", convert(vec![0, 2, 4, 6])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_composition() {
+        let src = r#"
+            constant %N = 8;
+            namespace F(%N);
+            pol constant G(i) { SQUARE(PERM(i)) };
+            pol constant H(i) { G(PERM(PERM(i))) + T.OFFSET(i) };
+            pol constant SQUARE(i) { i * i };
+            pol constant PERM(i) { (i * 3) % %N };
+            namespace T(%N);
+            pol constant OFFSET(i) { 100 };
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 8);
+        let perm = |i: i32| (i * 3) % 8;
+        assert_eq!(
+            constants[0],
+            ("F.G", convert((0..8).map(|i| perm(i) * perm(i)).collect()))
+        );
+        assert_eq!(
+            constants[1],
+            (
+                "F.H",
+                convert(
+                    (0..8)
+                        .map(|i| perm(perm(perm(i))) * perm(perm(perm(i))) + 100)
+                        .collect()
+                )
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "references F.MISSING, which is not a defined fixed column")]
+    pub fn test_composition_undefined() {
+        let src = r#"
+            constant %N = 4;
+            namespace F(%N);
+            pol constant G(i) { MISSING(i) + 1 };
+        "#;
+        generate(&analyze_string(src).unwrap());
+    }
+
+    #[test]
+    pub fn test_from_file() {
+        let analyzed =
+            crate::analyzer::analyze(std::path::Path::new("tests/fixed_from_file.pil"), &[])
+                .unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 8);
+        let from_csv = vec![
+            1.into(),
+            2.into(),
+            3.into(),
+            16.into(),
+            5.into(),
+            6.into(),
+            7.into(),
+            AbstractNumberType::from(0xffffffff00000000u64),
+        ];
+        let doubled = from_csv.iter().map(|v| v * 2).collect();
+        assert_eq!(
+            constants,
+            vec![
+                ("T.FROM_CSV", FixedColumn::Dense(from_csv)),
+                ("T.DOUBLED", FixedColumn::Dense(doubled))
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_recurrence() {
+        let src = r#"
+            constant %N = 6;
+            namespace F(%N);
+            col fixed ACC(i) { 3 * ACC(i - 1) + 1 } with [1];
+            col fixed FIB(i) { FIB(i - 1) + FIB(i - 2) } with [0, 1];
+            col fixed SHIFTED(i) { ACC(i + 1) };
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 6);
+        assert_eq!(
+            constants,
+            vec![
+                ("F.ACC", convert(vec![1, 4, 13, 40, 121, 364])),
+                ("F.FIB", convert(vec![0, 1, 1, 2, 3, 5])),
+                ("F.SHIFTED", convert(vec![4, 13, 40, 121, 364, 1])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_sparse() {
+        let src = r#"
+            constant %N = 32;
+            namespace F(%N);
+            macro is_zero(X) { 1 - X / X };
+            col fixed AT_FIVE(i) { 7 * is_zero(i - 5) };
+            col fixed ALL_BUT_ONE(i) { 1 - is_zero(i) };
+            col fixed CALLS_SPARSE(i) { AT_FIVE(i + 1) };
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 32);
+        assert_eq!(
+            constants[0],
+            ("F.AT_FIVE", FixedColumn::Sparse(32, vec![(5, 7.into())]))
+        );
+        assert!(matches!(constants[1].1, FixedColumn::Dense(_)));
+        assert_eq!(
+            constants[2],
+            (
+                "F.CALLS_SPARSE",
+                FixedColumn::Sparse(32, vec![(4, 7.into())])
+            )
+        );
+        let at_five = &constants[0].1;
+        assert_eq!(at_five.get(5), 7.into());
+        assert_eq!(at_five.get(37), 7.into());
+        assert_eq!(at_five.get(6), 0.into());
+        assert_eq!(at_five.position(&7.into()), Some(5));
+        assert_eq!(at_five.position(&0.into()), Some(0));
+        assert_eq!(
+            FixedColumn::Sparse(3, vec![(0, 1.into()), (1, 1.into())]).position(&0.into()),
+            Some(2)
+        );
+    }
+
+    #[test]
+    pub fn test_random() {
+        let src = r#"
+            constant %N = 16;
+            namespace F(%N);
+            pol constant R1 random(7);
+            pol constant R2 random(7);
+            pol constant R3 random(8);
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 16);
+        let values = constants
+            .iter()
+            .map(|(_, c)| c.to_values(degree))
+            .collect::<Vec<_>>();
+        assert_eq!(values[0], values[1]);
+        assert_ne!(values[0], values[2]);
+        assert!(values[0]
+            .iter()
+            .all(|v| *v >= 0.into() && *v < crate::number::GOLDILOCKS_MOD.into()));
+        // Deterministic across runs.
+        assert_eq!(values[0][0], 7191089600892374487u64.into());
+    }
+
+    #[test]
+    pub fn test_comparisons_and_conditionals() {
+        let src = r#"
+            constant %N = 8;
+            constant %HALF = %N / 2 >= 4 && !(%N == 7);
+            namespace F(%N);
+            pol constant FIRST_HALF(i) { i < %N / 2 };
+            pol constant LOGIC(i) { (i % 2 == 0 || i == 3) && i != 6 };
+            pol constant BITS(i) { (i ^ 5) & 3 };
+            pol constant COND(i) { if i < 2 { 10 } else if i >= 6 { 20 + i } else { %HALF } };
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 8);
+        let values = constants
+            .iter()
+            .map(|(name, c)| (*name, c.to_values(degree)))
+            .collect::<Vec<_>>();
+        let expected = |v: Vec<i32>| v.into_iter().map(|x| x.into()).collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                ("F.FIRST_HALF", expected(vec![1, 1, 1, 1, 0, 0, 0, 0])),
+                ("F.LOGIC", expected(vec![1, 0, 1, 1, 1, 0, 0, 0])),
+                ("F.BITS", expected(vec![1, 0, 3, 2, 1, 0, 3, 2])),
+                ("F.COND", expected(vec![10, 10, 1, 1, 1, 1, 26, 27])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_division_by_zero() {
+        let src = r#"
+            constant %N = 4;
+            constant %Q = 7 / 0;
+            constant %R = 7 % 0;
+            namespace F(%N);
+            pol constant DIV(i) { i / 0 + %Q };
+            pol constant MOD(i) { i % 0 + %R };
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        assert_eq!(degree, 4);
+        let values = constants
+            .iter()
+            .map(|(name, c)| (*name, c.to_values(degree)))
+            .collect::<Vec<_>>();
+        let expected = |v: Vec<i32>| v.into_iter().map(|x| x.into()).collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![
+                ("F.DIV", expected(vec![0, 0, 0, 0])),
+                ("F.MOD", expected(vec![7, 8, 9, 10])),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_multiple_degrees() {
+        let src = r#"
+            constant %N = 8;
+            namespace F(%N);
+            pol constant STEP(i) { i };
+            pol constant ODD(i) { STEP(i) % 2 };
+            pol constant FIRST_TWO = [1, 1];
+            pol constant FIB(i) { FIB(i - 2) + FIB(i - 1) } with [1, 1];
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let small = generate_at_degree(&analyzed, 4);
+        assert_eq!(
+            small,
+            vec![
+                ("F.STEP", convert(vec![0, 1, 2, 3])),
+                ("F.ODD", convert(vec![0, 1])),
+                ("F.FIRST_TWO", convert(vec![1, 1, 0, 0])),
+                ("F.FIB", convert(vec![1, 1, 2, 3])),
+            ]
+        );
+        let large = generate_at_degree(&analyzed, 16);
+        assert_eq!(
+            large[0].1.to_values(16),
+            convert((0..16).collect()).to_values(16)
+        );
+        assert_eq!(large[1].1, convert(vec![0, 1]));
+        assert_eq!(
+            large[2].1.to_values(16)[..3],
+            convert(vec![1, 1, 0]).to_values(3)
+        );
+        assert_eq!(large[3].1.get(15), 987.into());
+    }
+
+    #[test]
+    pub fn test_check_identities() {
+        let src = r#"
+            constant %N = 8;
+            namespace F(%N);
+            pol constant BYTE(i) { i };
+            pol constant REV(i) { %N - 1 - i };
+            pol constant DOUBLE(i) { 2 * i };
+            pol constant FIRST = [1, 0];
+            pol commit x;
+            DOUBLE = BYTE + BYTE;
+            FIRST * (BYTE' - 1) = 0;
+            REV in BYTE;
+            FIRST { BYTE } in { DOUBLE };
+            REV is BYTE;
+            x = BYTE;
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        check_identities(&analyzed, &constants, degree);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not satisfied in row 4: F.BYTE = 4, F.SMALL = 0")]
+    pub fn test_check_identities_violated() {
+        let src = r#"
+            constant %N = 8;
+            namespace F(%N);
+            pol constant BYTE(i) { i };
+            pol constant SMALL(i) { i % 4 };
+            BYTE = SMALL;
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (constants, degree) = generate(&analyzed);
+        check_identities(&analyzed, &constants, degree);
+    }
 }
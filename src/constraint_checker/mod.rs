@@ -0,0 +1,552 @@
+use std::collections::{BTreeSet, HashMap};
+
+use rayon::prelude::*;
+
+use crate::analyzer::{
+    evaluator, Analyzed, Expression, FunctionValueDefinition, Identity, IdentityKind,
+    PolynomialType, SelectedExpressions,
+};
+use crate::constant_evaluator::FixedColumn;
+use crate::number::{format_number, is_zero, to_field_element, AbstractNumberType, DegreeType};
+
+/// Checks all identities, plookups and permutations against the full
+/// fixed and witness columns, without any cryptography.
+/// Identities that cannot be checked (connect identities and identities
+/// that reference array elements or columns that are not available)
+/// are reported as errors as well.
+/// @returns an error describing the first violated row (including the values
+/// of the involved columns) of each violated identity.
+pub fn check(
+    analyzed: &Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    witness: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+) -> Result<(), String> {
+    let checker = ConstraintChecker::from_columns(analyzed, fixed, witness, degree);
+    let errors = analyzed
+        .identities
+        .iter()
+        .filter_map(|identity| checker.check(identity).err())
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+/// @returns the witness columns followed by the fixed columns.
+pub fn column_values<'a, N: AsRef<str>>(
+    fixed: &'a [(&'a str, FixedColumn)],
+    witness: &'a [(N, Vec<AbstractNumberType>)],
+) -> Vec<(&'a str, &'a dyn ColumnValues)> {
+    witness
+        .iter()
+        .map(|(name, c)| (name.as_ref(), c as &dyn ColumnValues))
+        .chain(
+            fixed
+                .iter()
+                .map(|(name, c)| (*name, c as &dyn ColumnValues)),
+        )
+        .collect()
+}
+
+/// Values of a column, independent of its representation.
+pub trait ColumnValues: Sync {
+    fn value(&self, row: DegreeType) -> AbstractNumberType;
+}
+
+impl ColumnValues for FixedColumn {
+    fn value(&self, row: DegreeType) -> AbstractNumberType {
+        self.get(row)
+    }
+}
+
+impl ColumnValues for Vec<AbstractNumberType> {
+    fn value(&self, row: DegreeType) -> AbstractNumberType {
+        self[row as usize].clone()
+    }
+}
+
+pub struct ConstraintChecker<'a> {
+    analyzed: &'a Analyzed,
+    columns: HashMap<&'a str, &'a dyn ColumnValues>,
+    degree: DegreeType,
+}
+
+impl<'a> ConstraintChecker<'a> {
+    pub fn new(
+        analyzed: &'a Analyzed,
+        columns: HashMap<&'a str, &'a dyn ColumnValues>,
+        degree: DegreeType,
+    ) -> Self {
+        ConstraintChecker {
+            analyzed,
+            columns,
+            degree,
+        }
+    }
+
+    /// Creates a checker for the given fixed and witness columns.
+    pub fn from_columns<N: AsRef<str>>(
+        analyzed: &'a Analyzed,
+        fixed: &'a [(&'a str, FixedColumn)],
+        witness: &'a [(N, Vec<AbstractNumberType>)],
+        degree: DegreeType,
+    ) -> Self {
+        Self::new(
+            analyzed,
+            column_values(fixed, witness).into_iter().collect(),
+            degree,
+        )
+    }
+
+    /// @returns true if the identity only references columns
+    /// (directly or through intermediate polynomials or public values)
+    /// that are available.
+    pub fn references_available(&self, identity: &Identity) -> bool {
+        selected_parts(identity).all(|e| self.expression_references_available(e))
+    }
+
+    /// @returns true if the expression only references columns
+    /// (directly or through intermediate polynomials or public values)
+    /// that are available.
+    pub fn expression_references_available(&self, expr: &Expression) -> bool {
+        self.unavailable_reference(expr).is_none()
+    }
+
+    /// @returns a description of the first reference in the expression
+    /// that cannot be evaluated.
+    fn unavailable_reference(&self, expr: &Expression) -> Option<String> {
+        match expr {
+            Expression::Constant(name) => {
+                (!self.analyzed.constants.contains_key(name)).then(|| format!("constant {name}"))
+            }
+            Expression::Number(_) => None,
+            Expression::PolynomialReference(poly) => match self.intermediate(&poly.name) {
+                Some(definition) => self.unavailable_reference(definition),
+                None if poly.index.is_some() => Some(format!("array element {expr}")),
+                None if !self.columns.contains_key(poly.name.as_str()) => {
+                    Some(format!("column {}", poly.name))
+                }
+                None => None,
+            },
+            Expression::PublicReference(name) => match self.public_reference(name) {
+                Some(reference) => self.unavailable_reference(&reference),
+                None => Some(format!("public value {name}")),
+            },
+            Expression::BinaryOperation(left, _, right) => self
+                .unavailable_reference(left)
+                .or_else(|| self.unavailable_reference(right)),
+            Expression::UnaryOperation(_, e) => self.unavailable_reference(e),
+            Expression::IfElse(c, t, f) => [c, t, f]
+                .into_iter()
+                .find_map(|e| self.unavailable_reference(e)),
+            Expression::LocalVariableReference(_)
+            | Expression::String(_)
+            | Expression::Tuple(_)
+            | Expression::FunctionCall(_, _) => Some(format!("{expr}")),
+        }
+    }
+
+    /// Checks the identity on all rows.
+    /// @returns an error describing the first violation.
+    pub fn check(&self, identity: &Identity) -> Result<(), String> {
+        let description = format!(
+            "{identity} (from {}:{})",
+            identity.source.file, identity.source.line
+        );
+        if let Some(reference) =
+            selected_parts(identity).find_map(|e| self.unavailable_reference(e))
+        {
+            return Err(format!(
+                "Identity {description} cannot be checked, {reference} is not available."
+            ));
+        }
+        match identity.kind {
+            IdentityKind::Polynomial => {
+                let expr = identity.left.selector.as_ref().unwrap();
+                match (0..self.degree)
+                    .into_par_iter()
+                    .find_first(|row| !is_zero(&self.evaluate(expr, *row)))
+                {
+                    Some(row) => Err(format!(
+                        "Identity {description} is not satisfied in row {row}: {}",
+                        self.format_cells(identity, row)
+                    )),
+                    None => Ok(()),
+                }
+            }
+            IdentityKind::Plookup => {
+                let right = self.selected_rows(&identity.right);
+                let right = right.iter().map(|(_, v)| v).collect::<BTreeSet<_>>();
+                match self
+                    .selected_rows(&identity.left)
+                    .into_iter()
+                    .find(|(_, values)| !right.contains(values))
+                {
+                    Some((row, values)) => Err(format!(
+                        "Plookup {description} is not satisfied in row {row}, values ({}) not found: {}",
+                        format_values(&values),
+                        self.format_cells(identity, row)
+                    )),
+                    None => Ok(()),
+                }
+            }
+            IdentityKind::Permutation => {
+                let mut counts: HashMap<_, i64> = HashMap::new();
+                for (_, values) in self.selected_rows(&identity.right) {
+                    *counts.entry(values).or_default() += 1;
+                }
+                for (row, values) in self.selected_rows(&identity.left) {
+                    let count = counts.entry(values.clone()).or_default();
+                    *count -= 1;
+                    if *count < 0 {
+                        return Err(format!(
+                            "Permutation {description} is not satisfied in row {row}, values ({}) occur more often on the left side: {}",
+                            format_values(&values),
+                            self.format_cells(identity, row)
+                        ));
+                    }
+                }
+                match counts.into_iter().find(|(_, count)| *count != 0) {
+                    Some((values, _)) => Err(format!(
+                        "Permutation {description} is not satisfied, values ({}) occur more often on the right side.",
+                        format_values(&values)
+                    )),
+                    None => Ok(()),
+                }
+            }
+            IdentityKind::Connect => {
+                Err(format!("Connect identity {description} cannot be checked."))
+            }
+        }
+    }
+
+    /// @returns all rows in which the identity is violated: the rows in which a
+    /// polynomial identity is nonzero, the rows whose values are not found on
+    /// the right side of a plookup and the rows whose values occur more often
+    /// on the left side of a permutation than on the right side.
+    pub fn violating_rows(&self, identity: &Identity) -> Vec<DegreeType> {
+        match identity.kind {
+            IdentityKind::Polynomial => {
+                let expr = identity.left.selector.as_ref().unwrap();
+                (0..self.degree)
+                    .into_par_iter()
+                    .filter(|row| !is_zero(&self.evaluate(expr, *row)))
+                    .collect()
+            }
+            IdentityKind::Plookup => {
+                let right = self.selected_rows(&identity.right);
+                let right = right.iter().map(|(_, v)| v).collect::<BTreeSet<_>>();
+                self.selected_rows(&identity.left)
+                    .into_iter()
+                    .filter(|(_, values)| !right.contains(values))
+                    .map(|(row, _)| row)
+                    .collect()
+            }
+            IdentityKind::Permutation => {
+                let mut counts: HashMap<_, i64> = HashMap::new();
+                for (_, values) in self.selected_rows(&identity.right) {
+                    *counts.entry(values).or_default() += 1;
+                }
+                self.selected_rows(&identity.left)
+                    .into_iter()
+                    .filter(|(_, values)| {
+                        let count = counts.entry(values.clone()).or_default();
+                        *count -= 1;
+                        *count < 0
+                    })
+                    .map(|(row, _)| row)
+                    .collect()
+            }
+            IdentityKind::Connect => vec![],
+        }
+    }
+
+    /// @returns the values of the expressions in all rows where the selector is nonzero.
+    fn selected_rows(
+        &self,
+        selected: &SelectedExpressions,
+    ) -> Vec<(DegreeType, Vec<AbstractNumberType>)> {
+        (0..self.degree)
+            .into_par_iter()
+            .filter(|row| {
+                selected
+                    .selector
+                    .as_ref()
+                    .map(|s| !is_zero(&self.evaluate(s, *row)))
+                    .unwrap_or(true)
+            })
+            .map(|row| {
+                let values = selected
+                    .expressions
+                    .iter()
+                    .map(|e| self.evaluate(e, row))
+                    .collect();
+                (row, values)
+            })
+            .collect()
+    }
+
+    /// Evaluates the expression in the given row, reduced to a field element
+    /// like the values of witness files (see `witness_io`).
+    pub fn evaluate(&self, expr: &Expression, row: DegreeType) -> AbstractNumberType {
+        to_field_element(&self.evaluate_unreduced(expr, row))
+    }
+
+    fn evaluate_unreduced(&self, expr: &Expression, row: DegreeType) -> AbstractNumberType {
+        match expr {
+            Expression::Constant(name) => self.analyzed.constants[name].clone(),
+            Expression::Number(n) => n.clone(),
+            Expression::PolynomialReference(poly) => {
+                let row = if poly.next { row + 1 } else { row };
+                match self.intermediate(&poly.name) {
+                    Some(definition) => self.evaluate_unreduced(definition, row),
+                    None => self.columns[poly.name.as_str()].value(row % self.degree),
+                }
+            }
+            Expression::PublicReference(name) => {
+                let public = &self.analyzed.public_declarations[name];
+                self.evaluate_unreduced(
+                    &Expression::PolynomialReference(public.polynomial.clone()),
+                    public.index,
+                )
+            }
+            Expression::BinaryOperation(left, op, right) => evaluator::evaluate_binary_operation(
+                self.evaluate_unreduced(left, row),
+                op,
+                self.evaluate_unreduced(right, row),
+            ),
+            Expression::UnaryOperation(op, e) => {
+                evaluator::evaluate_unary_operation(op, self.evaluate_unreduced(e, row))
+            }
+            Expression::IfElse(condition, if_true, if_false) => {
+                if is_zero(&self.evaluate(condition, row)) {
+                    self.evaluate_unreduced(if_false, row)
+                } else {
+                    self.evaluate_unreduced(if_true, row)
+                }
+            }
+            _ => panic!("Cannot evaluate {expr}."),
+        }
+    }
+
+    /// @returns a reference to the cell that holds the public value of the given name.
+    fn public_reference(&self, name: &str) -> Option<Expression> {
+        self.analyzed
+            .public_declarations
+            .get(name)
+            .map(|public| Expression::PolynomialReference(public.polynomial.clone()))
+    }
+
+    /// @returns the definition of the intermediate polynomial of the given name.
+    fn intermediate(&self, name: &str) -> Option<&'a Expression> {
+        match self.analyzed.definitions.get(name) {
+            Some((poly, Some(FunctionValueDefinition::Mapping(definition))))
+                if poly.poly_type == PolynomialType::Intermediate =>
+            {
+                Some(definition)
+            }
+            _ => None,
+        }
+    }
+
+    /// @returns the columns referenced by the expression (directly or through
+    /// intermediate polynomials), together with whether the next row is referenced.
+    pub fn referenced_cells(&self, expr: &'a Expression) -> BTreeSet<(&'a str, bool)> {
+        let mut cells = BTreeSet::new();
+        self.collect_cells(expr, false, &mut cells);
+        cells
+    }
+
+    /// Formats the values of all columns referenced by the identity in the given row.
+    fn format_cells(&self, identity: &'a Identity, row: DegreeType) -> String {
+        selected_parts(identity)
+            .flat_map(|expr| self.referenced_cells(expr))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|(name, next)| {
+                let value = self.columns[name].value((row + next as DegreeType) % self.degree);
+                format!(
+                    "{name}{} = {}",
+                    if next { "'" } else { "" },
+                    format_number(&value)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn collect_cells(
+        &self,
+        expr: &'a Expression,
+        next: bool,
+        cells: &mut BTreeSet<(&'a str, bool)>,
+    ) {
+        match expr {
+            Expression::PolynomialReference(poly) => match self.intermediate(&poly.name) {
+                // TODO references to the next row of an intermediate
+                // polynomial that itself uses the next row are not shown.
+                Some(definition) => self.collect_cells(definition, next || poly.next, cells),
+                None => {
+                    cells.insert((poly.name.as_str(), next || poly.next));
+                }
+            },
+            Expression::BinaryOperation(left, _, right) => {
+                self.collect_cells(left, next, cells);
+                self.collect_cells(right, next, cells);
+            }
+            Expression::UnaryOperation(_, e) => self.collect_cells(e, next, cells),
+            Expression::IfElse(c, t, f) => {
+                for e in [c, t, f] {
+                    self.collect_cells(e, next, cells);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// @returns all selectors and expressions of both sides of the identity.
+fn selected_parts(identity: &Identity) -> impl Iterator<Item = &Expression> {
+    [&identity.left, &identity.right]
+        .into_iter()
+        .flat_map(|s| s.selector.iter().chain(s.expressions.iter()))
+}
+
+fn format_values(values: &[AbstractNumberType]) -> String {
+    values
+        .iter()
+        .map(format_number)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::constant_evaluator;
+    use crate::witness_io::Witness;
+
+    use super::*;
+
+    fn check_with_witness(src: &str, witness: Vec<(&str, Vec<i32>)>) -> Result<(), String> {
+        let analyzed = analyze_string(src).unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        let witness = witness
+            .into_iter()
+            .map(|(name, values)| (name, values.into_iter().map(|v| v.into()).collect()))
+            .collect::<Vec<_>>();
+        check(&analyzed, &fixed, &witness, degree)
+    }
+
+    const SRC: &str = r#"
+        constant %N = 4;
+        namespace F(%N);
+        pol constant FIRST = [1, 0, 0, 0];
+        pol constant BYTE(i) { i };
+        pol commit x, y;
+        pol double = 2 * x;
+        FIRST * x = 0;
+        (1 - FIRST') * (x' - x - 1) = 0;
+        y = double;
+        y in { 2 * BYTE };
+        x is BYTE;
+    "#;
+
+    #[test]
+    fn valid_witness() {
+        assert_eq!(
+            check_with_witness(
+                SRC,
+                vec![("F.x", vec![0, 1, 2, 3]), ("F.y", vec![0, 2, 4, 6])]
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn invalid_witness() {
+        let errors = check_with_witness(
+            SRC,
+            vec![("F.x", vec![0, 1, 3, 3]), ("F.y", vec![0, 2, 6, 7])],
+        )
+        .unwrap_err();
+        let errors = errors.lines().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].contains("is not satisfied in row 1: F.FIRST' = 0, F.x = 1, F.x' = 3"));
+        assert!(errors[1].contains("is not satisfied in row 3: F.x = 3, F.y = 7"));
+        assert!(errors[2].contains("is not satisfied in row 3, values (7) not found"));
+        assert!(errors[3].contains("row 3, values (3) occur more often on the left side"));
+    }
+
+    #[test]
+    fn witness_from_csv() {
+        let analyzed = analyze_string(
+            "namespace F(2); pol constant M = [-1, 5]; pol commit x; x in M; x * (x + 1) * (x - 5) = 0;",
+        )
+        .unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        let witness = Witness::from_csv("row,F.x\n0,-1\n1,0x5\n").unwrap();
+        let witness = witness
+            .columns
+            .iter()
+            .map(|(name, values)| (name.as_str(), values.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(check(&analyzed, &fixed, &witness, degree), Ok(()));
+    }
+
+    #[test]
+    fn violating_rows() {
+        let analyzed = analyze_string(SRC).unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        let witness = [
+            ("F.x", vec![0.into(), 1.into(), 1.into(), 1.into()]),
+            ("F.y", vec![0.into(), 2.into(), 4.into(), 9.into()]),
+        ];
+        let checker = ConstraintChecker::from_columns(&analyzed, &fixed, &witness, degree);
+        let violations = analyzed
+            .identities
+            .iter()
+            .map(|identity| checker.violating_rows(identity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            violations,
+            vec![vec![], vec![1, 2], vec![2, 3], vec![3], vec![2, 3]]
+        );
+    }
+
+    #[test]
+    fn public_references() {
+        let src = r#"
+            namespace F(2);
+            pol constant FIRST = [1, 0];
+            pol commit x, out;
+            x = 3 + 0 * :out;
+            out = :out;
+            public out = out(1);
+        "#;
+        assert_eq!(
+            check_with_witness(src, vec![("F.x", vec![3, 3]), ("F.out", vec![5, 5])]),
+            Ok(())
+        );
+        let errors =
+            check_with_witness(src, vec![("F.x", vec![9, 9]), ("F.out", vec![5, 6])]).unwrap_err();
+        let errors = errors.lines().collect::<Vec<_>>();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("is not satisfied in row 0: F.x = 9"));
+        assert!(errors[1].contains("is not satisfied in row 0: F.out = 5"));
+    }
+
+    #[test]
+    fn unavailable_references() {
+        let src = r#"
+            namespace F(2);
+            pol commit x, y;
+            x = y;
+        "#;
+        let errors = check_with_witness(src, vec![("F.x", vec![1, 1])]).unwrap_err();
+        assert!(errors.ends_with("cannot be checked, column F.y is not available."));
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::analyzer::{
+    Analyzed, Expression, FunctionValueDefinition, IdentityKind, PolynomialType,
+    SelectedExpressions, StatementIdentifier,
+};
+
+/// Exports the dependencies between columns as a graph in the DOT language
+/// of Graphviz. Every namespace is a cluster containing its columns (boxes
+/// for witness columns, ellipses for fixed columns and dashed boxes for
+/// intermediate polynomials) and its identities (points for polynomial
+/// identities, with edges to all referenced columns, and `in`/`is`/`connect`
+/// nodes for plookups, permutations and connections, with edges from the
+/// columns on the left to the columns on the right). Intermediate polynomials
+/// have dashed edges to the columns they are defined by.
+/// An identity belongs to the namespace of the first column it references.
+/// If a namespace is given, only the identities that reference one of its
+/// columns are shown, together with the columns they reference.
+pub fn export(analyzed: &Analyzed, namespace: Option<&str>) -> String {
+    let in_namespace = |name: &str| namespace.is_none_or(|ns| namespace_of(name) == ns);
+    // Namespace -> declarations of identity nodes.
+    let mut identity_nodes: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    let mut edges = vec![];
+    let mut columns = BTreeSet::new();
+
+    for (index, identity) in analyzed.identities.iter().enumerate() {
+        let left = referenced_columns(&identity.left);
+        let right = referenced_columns(&identity.right);
+        let Some(first) = left.iter().chain(&right).next() else {
+            continue;
+        };
+        if !left.iter().chain(&right).any(|c| in_namespace(c)) {
+            continue;
+        }
+        let node = format!("identity_{index}");
+        let (shape, label) = match identity.kind {
+            IdentityKind::Polynomial => ("point", ""),
+            IdentityKind::Plookup => ("plaintext", "in"),
+            IdentityKind::Permutation => ("plaintext", "is"),
+            IdentityKind::Connect => ("plaintext", "connect"),
+        };
+        identity_nodes
+            .entry(namespace_of(first))
+            .or_default()
+            .push(format!(
+                "\"{node}\" [label=\"{label}\", shape={shape}, tooltip=\"{}\"];",
+                escape(&format!(
+                    "{identity} (from {}:{})",
+                    identity.source.file, identity.source.line
+                ))
+            ));
+        if identity.kind == IdentityKind::Polynomial {
+            for column in &left {
+                edges.push(format!("\"{node}\" -> \"{column}\" [dir=none];"));
+            }
+        } else {
+            for column in &left {
+                edges.push(format!("\"{column}\" -> \"{node}\";"));
+            }
+            for column in &right {
+                edges.push(format!("\"{node}\" -> \"{column}\";"));
+            }
+        }
+        columns.extend(left.into_iter().chain(right));
+    }
+    for statement in &analyzed.source_order {
+        let StatementIdentifier::Definition(name) = statement else {
+            continue;
+        };
+        if in_namespace(name) {
+            columns.insert(name);
+        }
+    }
+
+    // Intermediate polynomials can add further columns.
+    let mut to_visit = columns.iter().cloned().collect::<Vec<_>>();
+    while let Some(name) = to_visit.pop() {
+        if let Some((poly, Some(FunctionValueDefinition::Mapping(expr)))) =
+            analyzed.definitions.get(name.as_str())
+        {
+            if poly.poly_type == PolynomialType::Intermediate {
+                for referenced in referenced_in_expression(expr) {
+                    edges.push(format!("\"{name}\" -> \"{referenced}\" [style=dashed];"));
+                    if columns.insert(referenced) {
+                        to_visit.push(referenced);
+                    }
+                }
+            }
+        }
+    }
+
+    // Namespace -> declarations of column nodes.
+    let mut column_nodes: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for name in &columns {
+        let attributes = match analyzed.definitions.get(name.as_str()) {
+            Some((poly, _)) => match poly.poly_type {
+                PolynomialType::Committed => "shape=box",
+                PolynomialType::Constant => "shape=ellipse",
+                PolynomialType::Intermediate => "shape=box, style=dashed",
+            },
+            None => "shape=box, color=red",
+        };
+        let short_name = name.rsplit_once('.').map(|(_, n)| n).unwrap_or(name);
+        column_nodes
+            .entry(namespace_of(name))
+            .or_default()
+            .push(format!(
+                "\"{name}\" [label=\"{short_name}\", {attributes}];"
+            ));
+    }
+
+    let mut out = String::new();
+    writeln!(out, "digraph pil {{").unwrap();
+    writeln!(out, "  node [fontsize=10];").unwrap();
+    for (namespace, nodes) in column_nodes {
+        writeln!(out, "  subgraph \"cluster_{namespace}\" {{").unwrap();
+        writeln!(out, "    label=\"{namespace}\";").unwrap();
+        let identities = identity_nodes.remove(namespace).unwrap_or_default();
+        for node in nodes.into_iter().chain(identities) {
+            writeln!(out, "    {node}").unwrap();
+        }
+        writeln!(out, "  }}").unwrap();
+    }
+    edges.sort();
+    edges.dedup();
+    for edge in edges {
+        writeln!(out, "  {edge}").unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+fn namespace_of(name: &str) -> &str {
+    name.rsplit_once('.').map(|(ns, _)| ns).unwrap_or("Global")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// @returns the names of the columns referenced in the selector and expressions.
+fn referenced_columns(selected: &SelectedExpressions) -> BTreeSet<&String> {
+    selected
+        .selector
+        .iter()
+        .chain(&selected.expressions)
+        .flat_map(referenced_in_expression)
+        .collect()
+}
+
+/// @returns the names of the columns referenced or called in the expression.
+fn referenced_in_expression(expr: &Expression) -> BTreeSet<&String> {
+    fn collect<'a>(expr: &'a Expression, names: &mut BTreeSet<&'a String>) {
+        match expr {
+            Expression::PolynomialReference(poly) => {
+                names.insert(&poly.name);
+            }
+            Expression::FunctionCall(name, args) => {
+                names.insert(name);
+                args.iter().for_each(|a| collect(a, names));
+            }
+            Expression::Tuple(items) => items.iter().for_each(|i| collect(i, names)),
+            Expression::BinaryOperation(left, _, right) => {
+                collect(left, names);
+                collect(right, names);
+            }
+            Expression::UnaryOperation(_, e) => collect(e, names),
+            Expression::IfElse(condition, if_true, if_false) => {
+                collect(condition, names);
+                collect(if_true, names);
+                collect(if_false, names);
+            }
+            Expression::Constant(_)
+            | Expression::LocalVariableReference(_)
+            | Expression::PublicReference(_)
+            | Expression::Number(_)
+            | Expression::String(_) => {}
+        }
+    }
+    let mut names = BTreeSet::new();
+    collect(expr, &mut names);
+    names
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+
+    use super::*;
+
+    const SRC: &str = r#"
+        namespace Main(4);
+        pol constant FIRST = [1, 0, 0, 0];
+        pol commit x, y;
+        pol next = x + 1;
+        (1 - FIRST) * (x' - next) = 0;
+        y in Byte.BYTE;
+        namespace Byte(4);
+        pol constant BYTE(i) { i };
+        pol commit unused;
+    "#;
+
+    #[test]
+    fn full_graph() {
+        assert_eq!(
+            export(&analyze_string(SRC).unwrap(), None),
+            r#"digraph pil {
+  node [fontsize=10];
+  subgraph "cluster_Byte" {
+    label="Byte";
+    "Byte.BYTE" [label="BYTE", shape=ellipse];
+    "Byte.unused" [label="unused", shape=box];
+  }
+  subgraph "cluster_Main" {
+    label="Main";
+    "Main.FIRST" [label="FIRST", shape=ellipse];
+    "Main.next" [label="next", shape=box, style=dashed];
+    "Main.x" [label="x", shape=box];
+    "Main.y" [label="y", shape=box];
+    "identity_0" [label="", shape=point, tooltip="((1 - Main.FIRST) * (Main.x' - Main.next)) = 0; (from input:6)"];
+    "identity_1" [label="in", shape=plaintext, tooltip="{ Main.y } in { Byte.BYTE }; (from input:7)"];
+  }
+  "Main.next" -> "Main.x" [style=dashed];
+  "Main.y" -> "identity_1";
+  "identity_0" -> "Main.FIRST" [dir=none];
+  "identity_0" -> "Main.next" [dir=none];
+  "identity_0" -> "Main.x" [dir=none];
+  "identity_1" -> "Byte.BYTE";
+}
+"#
+        );
+    }
+
+    #[test]
+    fn restricted_to_namespace() {
+        let dot = export(&analyze_string(SRC).unwrap(), Some("Byte"));
+        assert!(dot.contains("\"Main.y\" -> \"identity_1\";"));
+        assert!(dot.contains("\"Byte.unused\""));
+        assert!(!dot.contains("identity_0"));
+        assert!(!dot.contains("Main.x"));
+    }
+}
@@ -0,0 +1,132 @@
+use std::any::Any;
+use std::fmt::{self, Display};
+
+use json::{object, JsonValue};
+
+// Stable codes of the failures reported by the compiler. Each range of
+// codes has its own process exit status, so that scripts can branch on the
+// kind of failure:
+// - E00xx (exit status 3): errors in the source, see `linter::lint`, and
+//   missing constraints,
+// - E01xx (exit status 4): fixed or witness column generation failed,
+// - E02xx (exit status 5): the backend could not prove or verify,
+// - E03xx (exit status 6): missing or invalid input files.
+// Other failures (E04xx: the terminal or the standard streams failed) exit
+// with status 1, invalid command lines with status 2.
+
+/// The source could not be parsed.
+pub const SYNTAX_ERROR: &str = "E0002";
+/// The analyzer rejected the source, e.g. because of a duplicate declaration.
+pub const ANALYZER_ERROR: &str = "E0005";
+/// A second witness with the same public values satisfies the constraints,
+/// which points to a missing constraint.
+pub const SECOND_WITNESS: &str = "E0006";
+/// Witness generation could not derive all values of a row
+/// or an identity does not hold.
+pub const WITGEN_STUCK: &str = "E0100";
+/// Not all declared fixed columns are defined.
+pub const UNDEFINED_FIXED_COLUMNS: &str = "E0101";
+/// The backend could not generate a proof.
+pub const BACKEND_FAILURE: &str = "E0200";
+/// A proof or its public values were rejected.
+pub const VERIFICATION_FAILURE: &str = "E0201";
+/// An input file could not be read or has invalid contents.
+pub const INVALID_INPUT: &str = "E0300";
+/// The terminal or the connection to the client on the standard streams failed.
+pub const IO_FAILURE: &str = "E0400";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Error {
+        Error {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn exit_status(&self) -> i32 {
+        exit_status(self.code)
+    }
+
+    /// @returns the error as a json object with the keys `code`,
+    /// `message`, `exit_status` and `rendered` (the error in text form).
+    pub fn to_json(&self) -> JsonValue {
+        object! {
+            code: self.code,
+            message: self.message.as_str(),
+            exit_status: self.exit_status(),
+            rendered: self.to_string(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error[{}]: {}", self.code, self.message)
+    }
+}
+
+/// @returns the message of a panic, if it has one.
+pub fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|m| m.to_string()))
+}
+
+/// @returns the process exit status for failures with the given code.
+pub fn exit_status(code: &str) -> i32 {
+    match code.get(..3) {
+        Some("E00") => 3,
+        Some("E01") => 4,
+        Some("E02") => 5,
+        Some("E03") => 6,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn exit_statuses() {
+        assert_eq!(exit_status("E0001"), 3);
+        assert_eq!(exit_status(WITGEN_STUCK), 4);
+        assert_eq!(exit_status(VERIFICATION_FAILURE), 5);
+        assert_eq!(exit_status(INVALID_INPUT), 6);
+        assert_eq!(exit_status(IO_FAILURE), 1);
+        assert_eq!(exit_status("W0001"), 1);
+    }
+
+    #[test]
+    fn panic_messages() {
+        let payload = std::panic::catch_unwind(|| panic!("Static message.")).unwrap_err();
+        assert_eq!(
+            panic_message(&*payload),
+            Some("Static message.".to_string())
+        );
+        let payload = std::panic::catch_unwind(|| panic!("{} message.", "Formatted")).unwrap_err();
+        assert_eq!(
+            panic_message(&*payload),
+            Some("Formatted message.".to_string())
+        );
+        let payload = std::panic::catch_unwind(|| std::panic::panic_any(7)).unwrap_err();
+        assert_eq!(panic_message(&*payload), None);
+    }
+
+    #[test]
+    fn output() {
+        let error = Error::new(BACKEND_FAILURE, "Out of memory.");
+        assert_eq!(error.to_string(), "error[E0200]: Out of memory.");
+        assert_eq!(
+            error.to_json().dump(),
+            r#"{"code":"E0200","message":"Out of memory.","exit_status":5,"rendered":"error[E0200]: Out of memory."}"#
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::analyzer::{Analyzed, Expression, Identity, IdentityKind, SelectedExpressions};
+use crate::commit_evaluator::{self, Provenance};
+use crate::constant_evaluator::FixedColumn;
+use crate::constraint_checker::ConstraintChecker;
+use crate::number::{format_number, to_signed, AbstractNumberType, DegreeType};
+
+/// Explains the value of a cell of a complete witness: it runs witness
+/// generation up to the row to find the identity, query or machine that
+/// determined the value, and lists every identity that references the cell
+/// (in the row itself or through a reference to the next row in the row
+/// before) with the values of the row substituted and whether it holds.
+/// For lookups and permutations, only the side referencing the cell is
+/// substituted.
+/// @returns the explanation as text.
+pub fn explain(
+    analyzed: &Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    witness: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+    query_callback: Option<impl FnMut(&str) -> Option<AbstractNumberType>>,
+    column: &str,
+    row: DegreeType,
+) -> Result<String, String> {
+    let Some((_, values)) = witness.iter().find(|(name, _)| *name == column) else {
+        return Err(format!("Witness column {column} not found."));
+    };
+    if row >= degree {
+        return Err(format!(
+            "Row {row} is out of range, the degree is {degree}."
+        ));
+    }
+    let value = &values[row as usize];
+    let mut out = String::new();
+    writeln!(out, "{column} in row {row} = {}", format_number(value)).unwrap();
+
+    let generated = panic::catch_unwind(AssertUnwindSafe(|| {
+        commit_evaluator::generate_until_row(analyzed, degree, fixed, query_callback, row)
+    }));
+    match generated {
+        Err(_) => writeln!(
+            out,
+            "Witness generation fails before it completes row {row}."
+        ),
+        Ok(cells) => {
+            let (_, generated, provenance) = cells
+                .into_iter()
+                .find(|(name, _, _)| *name == column)
+                .unwrap();
+            match provenance {
+                Some(Provenance::Identity(identity)) => {
+                    writeln!(out, "Determined by {}", describe(identity))
+                }
+                Some(Provenance::Query) => writeln!(out, "Determined by the query of {column}."),
+                Some(Provenance::Hint) => writeln!(out, "Taken from the witness hints."),
+                Some(Provenance::Machine(machine)) => {
+                    writeln!(out, "Set by the {machine} machine after the last row.")
+                }
+                None => writeln!(
+                    out,
+                    "Not determined by witness generation, which sets it to 0."
+                ),
+            }
+            .unwrap();
+            match generated {
+                Some(generated) if generated != *value => writeln!(
+                    out,
+                    "Witness generation computes {} instead.",
+                    format_number(&generated)
+                ),
+                _ => Ok(()),
+            }
+        }
+    }
+    .unwrap();
+
+    let checker = ConstraintChecker::from_columns(analyzed, fixed, witness, degree);
+    writeln!(out, "\nIdentities referencing {column} in row {row}:").unwrap();
+    for identity in &analyzed.identities {
+        let references = |side: &SelectedExpressions| {
+            side.selector
+                .iter()
+                .chain(&side.expressions)
+                .flat_map(|e| checker.referenced_cells(e))
+                .filter(|(name, _)| *name == column)
+                .map(|(_, next)| next)
+                .collect::<BTreeSet<_>>()
+        };
+        let (left, right) = (references(&identity.left), references(&identity.right));
+        // The rows in which the identity is evaluated to reference the cell.
+        let rows = left
+            .iter()
+            .chain(&right)
+            .map(|next| (row + degree - *next as DegreeType) % degree)
+            .collect::<BTreeSet<_>>();
+        if rows.is_empty() || !checker.references_available(identity) {
+            continue;
+        }
+        let violating_rows = checker.violating_rows(identity);
+        for evaluation_row in rows {
+            let status = if identity.kind == IdentityKind::Connect {
+                "not checked"
+            } else if violating_rows.contains(&evaluation_row) {
+                "violated"
+            } else {
+                "satisfied"
+            };
+            let substitute_side = |side: &SelectedExpressions, references: &BTreeSet<bool>| {
+                if references.is_empty() && identity.kind != IdentityKind::Polynomial {
+                    side.clone()
+                } else {
+                    substitute_selected(&checker, side, evaluation_row)
+                }
+            };
+            let substituted = Identity {
+                left: substitute_side(&identity.left, &left),
+                right: substitute_side(&identity.right, &right),
+                ..identity.clone()
+            };
+            writeln!(
+                out,
+                "  row {evaluation_row}, {status}: {}\n    {substituted}",
+                describe(identity)
+            )
+            .unwrap();
+        }
+    }
+    Ok(out)
+}
+
+fn describe(identity: &Identity) -> String {
+    format!(
+        "{identity} (from {}:{})",
+        identity.source.file, identity.source.line
+    )
+}
+
+fn substitute_selected(
+    checker: &ConstraintChecker,
+    selected: &SelectedExpressions,
+    row: DegreeType,
+) -> SelectedExpressions {
+    SelectedExpressions {
+        selector: selected
+            .selector
+            .as_ref()
+            .map(|s| substitute(checker, s, row)),
+        expressions: selected
+            .expressions
+            .iter()
+            .map(|e| substitute(checker, e, row))
+            .collect(),
+    }
+}
+
+/// Replaces all references to columns and intermediate polynomials
+/// by their values in the given row.
+fn substitute(checker: &ConstraintChecker, expr: &Expression, row: DegreeType) -> Expression {
+    match expr {
+        Expression::PolynomialReference(_) => {
+            Expression::Number(to_signed(&checker.evaluate(expr, row)))
+        }
+        Expression::BinaryOperation(left, op, right) => Expression::BinaryOperation(
+            Box::new(substitute(checker, left, row)),
+            *op,
+            Box::new(substitute(checker, right, row)),
+        ),
+        Expression::UnaryOperation(op, e) => {
+            Expression::UnaryOperation(*op, Box::new(substitute(checker, e, row)))
+        }
+        Expression::IfElse(c, t, f) => Expression::IfElse(
+            Box::new(substitute(checker, c, row)),
+            Box::new(substitute(checker, t, row)),
+            Box::new(substitute(checker, f, row)),
+        ),
+        _ => expr.clone(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::constant_evaluator;
+
+    use super::*;
+
+    const SRC: &str = r#"
+        namespace F(4);
+        pol constant FIRST = [1, 0, 0, 0];
+        pol constant BYTE(i) { i };
+        pol commit x, y;
+        FIRST * x = 0;
+        (1 - FIRST') * (x' - x - 1) = 0;
+        y = 2 * x;
+        x in BYTE;
+    "#;
+
+    fn explain_cell(witness: &[(&str, Vec<AbstractNumberType>)], row: DegreeType) -> String {
+        let analyzed = analyze_string(SRC).unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        explain(
+            &analyzed,
+            &fixed,
+            witness,
+            degree,
+            None::<fn(&str) -> Option<AbstractNumberType>>,
+            "F.x",
+            row,
+        )
+        .unwrap()
+    }
+
+    fn column(values: &[i32]) -> Vec<AbstractNumberType> {
+        values.iter().map(|v| (*v).into()).collect()
+    }
+
+    #[test]
+    fn generated_witness() {
+        let witness = [
+            ("F.x", column(&[0, 1, 2, 3])),
+            ("F.y", column(&[0, 2, 4, 6])),
+        ];
+        assert_eq!(
+            explain_cell(&witness, 2),
+            "F.x in row 2 = 2\n\
+             Determined by ((1 - F.FIRST') * ((F.x' - F.x) - 1)) = 0; (from input:7)\n\
+             \n\
+             Identities referencing F.x in row 2:\n  \
+               row 2, satisfied: (F.FIRST * F.x) = 0; (from input:6)\n    \
+                 (0 * 2) = 0;\n  \
+               row 1, satisfied: ((1 - F.FIRST') * ((F.x' - F.x) - 1)) = 0; (from input:7)\n    \
+                 ((1 - 0) * ((2 - 1) - 1)) = 0;\n  \
+               row 2, satisfied: ((1 - F.FIRST') * ((F.x' - F.x) - 1)) = 0; (from input:7)\n    \
+                 ((1 - 0) * ((3 - 2) - 1)) = 0;\n  \
+               row 2, satisfied: F.y = (2 * F.x); (from input:8)\n    \
+                 4 = (2 * 2);\n  \
+               row 2, satisfied: { F.x } in { F.BYTE }; (from input:9)\n    \
+                 { 2 } in { F.BYTE };\n"
+        );
+    }
+
+    #[test]
+    fn wrong_value() {
+        let witness = [
+            ("F.x", column(&[0, 1, 5, 3])),
+            ("F.y", column(&[0, 2, 4, 6])),
+        ];
+        let explanation = explain_cell(&witness, 2);
+        assert!(explanation.contains("Witness generation computes 2 instead."));
+        assert!(explanation.contains("row 2, violated: F.y = (2 * F.x);"));
+        assert!(explanation.contains("{ 5 } in { F.BYTE };"));
+    }
+}
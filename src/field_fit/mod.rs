@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::analyzer::{Analyzed, Expression, FunctionValueDefinition, PolynomialType};
+use crate::constant_evaluator::FixedColumn;
+use crate::number::{to_signed, AbstractNumberType, DegreeType, GOLDILOCKS_MOD};
+
+/// A field a backend might work over, smaller than or equal to the
+/// field all values are computed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum TargetField {
+    Goldilocks,
+    /// 2^31 - 2^27 + 1
+    BabyBear,
+    /// 2^31 - 1
+    Mersenne31,
+}
+
+impl TargetField {
+    pub fn modulus(&self) -> u64 {
+        match self {
+            TargetField::Goldilocks => GOLDILOCKS_MOD,
+            TargetField::BabyBear => (1 << 31) - (1 << 27) + 1,
+            TargetField::Mersenne31 => (1 << 31) - 1,
+        }
+    }
+
+    /// @returns true if the value (in the Goldilocks field) has the same meaning
+    /// in the target field, i.e. its absolute value is smaller than the
+    /// modulus of the target field. Negative numbers stay negative.
+    pub fn fits(&self, value: &AbstractNumberType) -> bool {
+        let value = to_signed(value);
+        let value = if value < 0.into() { -value } else { value };
+        value < self.modulus().into()
+    }
+}
+
+/// Finds all values that would be silently wrapped when exporting to a
+/// backend over the target field: numbers and constants used in identities
+/// and intermediate polynomials, and values of fixed columns.
+/// @returns one message per offending location.
+pub fn check(
+    analyzed: &Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    degree: DegreeType,
+    field: TargetField,
+) -> Vec<String> {
+    let mut errors = vec![];
+    let mut check_expression = |location: &str, expr: &Expression| {
+        for_each_number(expr, &analyzed.constants, &mut |number, constant| {
+            if !field.fits(number) {
+                errors.push(match constant {
+                    Some(name) => format!(
+                        "{location}: The constant {name} = {number} does not fit into {field:?}."
+                    ),
+                    None => format!("{location}: The number {number} does not fit into {field:?}."),
+                });
+            }
+        });
+    };
+    for (poly, definition) in analyzed.definitions.values() {
+        if let (PolynomialType::Intermediate, Some(FunctionValueDefinition::Mapping(expr))) =
+            (poly.poly_type, definition)
+        {
+            check_expression(
+                &format!(
+                    "Intermediate polynomial {} (from {}:{})",
+                    poly.absolute_name, poly.source.file, poly.source.line
+                ),
+                expr,
+            );
+        }
+    }
+    for identity in &analyzed.identities {
+        let location = format!(
+            "Identity {identity} (from {}:{})",
+            identity.source.file, identity.source.line
+        );
+        for selected in [&identity.left, &identity.right] {
+            for expr in selected.selector.iter().chain(&selected.expressions) {
+                check_expression(&location, expr);
+            }
+        }
+    }
+    // Intermediate polynomials are stored in a hash map.
+    errors.sort();
+
+    for (name, column) in fixed {
+        let mut offending = (0..degree)
+            .map(|row| (row, column.get(row)))
+            .filter(|(_, value)| !field.fits(value));
+        if let Some((row, value)) = offending.next() {
+            let count = offending.count() + 1;
+            errors.push(format!(
+                "Fixed column {name}: {count} value(s) do not fit into {field:?}, the first is {value} in row {row}."
+            ));
+        }
+    }
+    errors
+}
+
+/// Calls `f` on every number in the expression, together with the name
+/// of the constant it comes from, if any.
+fn for_each_number(
+    expr: &Expression,
+    constants: &HashMap<String, AbstractNumberType>,
+    f: &mut impl FnMut(&AbstractNumberType, Option<&str>),
+) {
+    match expr {
+        Expression::Number(n) => f(n, None),
+        Expression::Constant(name) => f(&constants[name], Some(name)),
+        Expression::Tuple(items) | Expression::FunctionCall(_, items) => {
+            items.iter().for_each(|e| for_each_number(e, constants, f))
+        }
+        Expression::BinaryOperation(l, _, r) => {
+            for_each_number(l, constants, f);
+            for_each_number(r, constants, f);
+        }
+        Expression::UnaryOperation(_, e) => for_each_number(e, constants, f),
+        Expression::IfElse(c, t, e) => {
+            for_each_number(c, constants, f);
+            for_each_number(t, constants, f);
+            for_each_number(e, constants, f);
+        }
+        Expression::PolynomialReference(_)
+        | Expression::LocalVariableReference(_)
+        | Expression::PublicReference(_)
+        | Expression::String(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::constant_evaluator;
+
+    use super::*;
+
+    #[test]
+    fn fits() {
+        assert!(TargetField::BabyBear.fits(&2013265920.into()));
+        assert!(!TargetField::BabyBear.fits(&2013265921.into()));
+        assert!(TargetField::Mersenne31.fits(&(-2147483646).into()));
+        assert!(!TargetField::Mersenne31.fits(&(-2147483647).into()));
+        assert!(TargetField::Goldilocks.fits(&(GOLDILOCKS_MOD - 1).into()));
+    }
+
+    #[test]
+    fn offending_values() {
+        let src = r#"
+            constant %big = 2**40;
+            namespace F(4);
+            pol constant ADDR(i) { i * 2**32 };
+            pol constant SMALL(i) { i };
+            pol commit x;
+            pol y = x * 2**31;
+            x' = x + %big;
+            x * (x - 1) = -1 + 1;
+        "#;
+        let analyzed = analyze_string(src).unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        assert_eq!(
+            check(&analyzed, &fixed, degree, TargetField::Mersenne31),
+            vec![
+                "Identity F.x' = (F.x + %big); (from input:8): The constant %big = 1099511627776 does not fit into Mersenne31.".to_string(),
+                "Intermediate polynomial F.y (from input:7): The number 2147483648 does not fit into Mersenne31.".to_string(),
+                "Fixed column F.ADDR: 3 value(s) do not fit into Mersenne31, the first is 4294967296 in row 1.".to_string(),
+            ]
+        );
+        assert!(check(&analyzed, &fixed, degree, TargetField::Goldilocks).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt::Write;
+use std::panic::{self, AssertUnwindSafe};
+
+use arbitrary::{Result, Unstructured};
+
+use crate::analyzer::{self, Analyzed};
+use crate::commit_evaluator;
+use crate::constant_evaluator::{self, FixedColumn};
+use crate::constraint_checker;
+use crate::number::{AbstractNumberType, DegreeType};
+
+/// Generates a small random PIL file from the given entropy source.
+/// The constraint system always has a solution that the witness generator
+/// can find row by row: every witness column is either an affine function
+/// of previously declared columns in the same row, a copy of a fixed column
+/// that is also checked by a plookup, or a recurrence over the rows started
+/// by a constraint on the first row.
+pub fn constraint_system(u: &mut Unstructured) -> Result<String> {
+    let degree: DegreeType = 1 << u.int_in_range(2..=4)?;
+    let mut out = String::new();
+    writeln!(out, "namespace Fuzz({degree});").unwrap();
+    let first = (0..degree).map(|i| if i == 0 { "1" } else { "0" });
+    writeln!(out, "pol constant FIRST = [{}];", join(first)).unwrap();
+    let last = (0..degree).map(|i| if i == degree - 1 { "1" } else { "0" });
+    writeln!(out, "pol constant LAST = [{}];", join(last)).unwrap();
+
+    let mut fixed = vec![];
+    for i in 0..u.int_in_range(1..=3)? {
+        let name = format!("F{i}");
+        if u.arbitrary()? {
+            let factor: u8 = u.arbitrary()?;
+            let offset: u8 = u.arbitrary()?;
+            writeln!(out, "pol constant {name}(i) {{ i * {factor} + {offset} }};").unwrap();
+        } else {
+            let values = (0..degree)
+                .map(|_| u.arbitrary::<u8>().map(|v| v.to_string()))
+                .collect::<Result<Vec<_>>>()?;
+            writeln!(out, "pol constant {name} = [{}];", join(values)).unwrap();
+        }
+        fixed.push(name);
+    }
+
+    let mut columns = fixed.clone();
+    for i in 0..u.int_in_range(1..=5)? {
+        let name = format!("w{i}");
+        writeln!(out, "pol commit {name};").unwrap();
+        match u.int_in_range(0..=3)? {
+            0 => {
+                let (a, b) = (coefficient(u)?, coefficient(u)?);
+                let (x, y) = (u.choose(&columns)?, u.choose(&columns)?);
+                let c: u8 = u.arbitrary()?;
+                writeln!(out, "{name} = {a} * {x} + {b} * {y} + {c};").unwrap();
+            }
+            1 => {
+                let (f, x) = (u.choose(&fixed)?, u.choose(&columns)?);
+                let c = coefficient(u)?;
+                writeln!(out, "{name} = {f} * {x} + {c};").unwrap();
+            }
+            2 => {
+                let f = u.choose(&fixed)?;
+                writeln!(out, "{name} = {f};").unwrap();
+                writeln!(out, "{{ {name} }} in {{ {f} }};").unwrap();
+            }
+            _ => {
+                let initial: u8 = u.arbitrary()?;
+                let step = u.choose(&columns)?;
+                writeln!(out, "FIRST * ({name} - {initial}) = 0;").unwrap();
+                writeln!(out, "(1 - LAST) * ({name}' - {name} - {step}) = 0;").unwrap();
+            }
+        }
+        columns.push(name);
+    }
+    Ok(out)
+}
+
+/// Runs witness generation twice on the given PIL source and checks that it
+/// does not panic, that both runs produce the same witness and that the
+/// witness satisfies all identities.
+/// @returns a description of the first violated invariant.
+pub fn check_solver_invariants(source: &str) -> std::result::Result<(), String> {
+    let analyzed = panic::catch_unwind(|| analyzer::analyze_string(source))
+        .map_err(|_| "The analyzer panicked.".to_string())?
+        .map_err(|e| format!("The analyzer rejected the generated source: {e}"))?;
+    let (fixed, degree) =
+        panic::catch_unwind(AssertUnwindSafe(|| constant_evaluator::generate(&analyzed)))
+            .map_err(|_| "Fixed column generation panicked.".to_string())?;
+    let witness = generate_witness(&analyzed, &fixed, degree)?;
+    if generate_witness(&analyzed, &fixed, degree)? != witness {
+        return Err("Witness generation is not deterministic.".to_string());
+    }
+    constraint_checker::check(&analyzed, &fixed, &witness, degree)
+        .map_err(|e| format!("The generated witness violates the constraints:\n{e}"))
+}
+
+fn generate_witness<'a>(
+    analyzed: &'a Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    degree: DegreeType,
+) -> std::result::Result<Vec<(&'a str, Vec<AbstractNumberType>)>, String> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        commit_evaluator::generate(
+            analyzed,
+            degree,
+            fixed,
+            None::<fn(&str) -> Option<AbstractNumberType>>,
+        )
+    }))
+    .map_err(|_| "Witness generation panicked.".to_string())
+}
+
+fn coefficient(u: &mut Unstructured) -> Result<String> {
+    Ok(match u.int_in_range(-3..=3)? {
+        c if c < 0 => format!("({c})"),
+        c => c.to_string(),
+    })
+}
+
+fn join(items: impl IntoIterator<Item = impl ToString>) -> String {
+    items
+        .into_iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Deterministic pseudo-random bytes, so that failures are reproducible.
+    fn entropy(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn random_systems_are_solved() {
+        for seed in 0..100 {
+            let data = entropy(seed, 256);
+            let source = constraint_system(&mut Unstructured::new(&data)).unwrap();
+            if let Err(e) = check_solver_invariants(&source) {
+                panic!("Seed {seed}: {e}\n{source}");
+            }
+        }
+    }
+
+    #[test]
+    fn detects_violations() {
+        let source = r#"
+            namespace Fuzz(4);
+            pol constant FIRST = [1, 0, 0, 0];
+            pol commit x;
+            FIRST * (x - 1) = 0;
+            x * (x - 1) = 0;
+        "#;
+        assert!(check_solver_invariants(source).is_err());
+    }
+}
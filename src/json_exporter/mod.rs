@@ -267,13 +267,7 @@ impl<'a> Exporter<'a> {
                         );
                         ("pow", deg_left + deg_right)
                     }
-                    BinaryOperator::Mod
-                    | BinaryOperator::BinaryAnd
-                    | BinaryOperator::BinaryOr
-                    | BinaryOperator::ShiftLeft
-                    | BinaryOperator::ShiftRight => {
-                        panic!("Operator {op:?} not supported on polynomials.")
-                    }
+                    _ => panic!("Operator {op:?} not supported on polynomials."),
                 };
                 (
                     degree,
@@ -298,6 +292,9 @@ impl<'a> Exporter<'a> {
                         },
                         deps,
                     ),
+                    UnaryOperator::LogicalNot => {
+                        panic!("Operator {op:?} not supported on polynomials.")
+                    }
                 }
             }
             Expression::FunctionCall(_, _) => {
@@ -305,6 +302,7 @@ impl<'a> Exporter<'a> {
             }
             Expression::String(_) => panic!("Strings not allowed here."),
             Expression::Tuple(_) => panic!("Tuples not allowed here"),
+            Expression::IfElse(_, _, _) => panic!("Conditionals not allowed here."),
         }
     }
 
@@ -348,7 +346,7 @@ mod test {
         let temp_dir = mktemp::Temp::new_dir().unwrap();
         let output_file = temp_dir.join("out.json");
 
-        let analyzed = analyzer::analyze(Path::new(file));
+        let analyzed = analyzer::analyze(Path::new(file), &[]).unwrap();
         let json_out = export(&analyzed);
 
         let pilcom = std::env::var("PILCOM").expect(
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+
+use json::JsonValue;
+
+use crate::analyzer::{
+    Analyzed, BinaryOperator, Expression, FunctionValueDefinition, Identity, IdentityKind,
+    Polynomial, PolynomialReference, PolynomialType, PublicDeclaration, SelectedExpressions,
+    SourceRef, StatementIdentifier, UnaryOperator,
+};
+use crate::number::parse_number;
+
+/// Imports the json format of pilcom (as produced by pilcom or by the json exporter).
+/// Constant definitions are not part of the format, so all polynomials are only declared.
+/// Since the format does not contain the source order, the order is
+/// reconstructed from the polynomial and expression IDs.
+pub fn import(json: &JsonValue) -> Result<Analyzed, String> {
+    Importer::new(json)?.import()
+}
+
+struct Importer<'a> {
+    json: &'a JsonValue,
+    /// Name, type, first ID and length (for arrays) of all non-intermediate polynomials.
+    declarations: Vec<(&'a str, PolynomialType, u64, Option<u64>)>,
+    /// Names of the intermediate polynomials by expression ID.
+    intermediates: HashMap<u64, &'a str>,
+    /// Names of the public declarations by ID.
+    publics: HashMap<u64, &'a str>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(json: &'a JsonValue) -> Result<Self, String> {
+        let mut declarations = Vec::new();
+        let mut intermediates = HashMap::new();
+        for (name, reference) in json["references"].entries() {
+            let id = u64_field(reference, "id")?;
+            match polynomial_type(str_field(reference, "type")?)? {
+                PolynomialType::Intermediate => {
+                    intermediates.insert(id, name);
+                }
+                poly_type => {
+                    declarations.push((name, poly_type, id, reference["len"].as_u64()));
+                }
+            }
+        }
+        let publics = json["publics"]
+            .members()
+            .map(|public| Ok((u64_field(public, "id")?, str_field(public, "name")?)))
+            .collect::<Result<_, String>>()?;
+        Ok(Importer {
+            json,
+            declarations,
+            intermediates,
+            publics,
+        })
+    }
+
+    fn import(&self) -> Result<Analyzed, String> {
+        let mut definitions = HashMap::new();
+        let mut public_declarations = HashMap::new();
+        let mut identities = Vec::new();
+        // Items without expressions come first, the others are
+        // sorted by their first expression ID.
+        let mut source_order = Vec::new();
+        let mut ordered_by_expression = Vec::new();
+
+        let mut declarations = self.declarations.clone();
+        declarations.sort_by_key(|(_, poly_type, id, _)| (type_order(*poly_type), *id));
+        for (name, poly_type, id, length) in declarations {
+            let poly = self.polynomial(name, poly_type, id, length)?;
+            definitions.insert(name.to_string(), (poly, None));
+            source_order.push(StatementIdentifier::Definition(name.to_string()));
+        }
+
+        let mut intermediates = self.intermediates.iter().collect::<Vec<_>>();
+        intermediates.sort();
+        for (poly_id, (expression_id, name)) in intermediates.into_iter().enumerate() {
+            let poly = self.polynomial(name, PolynomialType::Intermediate, poly_id as u64, None)?;
+            let value = self.expression_by_id(*expression_id)?;
+            definitions.insert(
+                name.to_string(),
+                (poly, Some(FunctionValueDefinition::Mapping(value))),
+            );
+            ordered_by_expression.push((
+                *expression_id,
+                StatementIdentifier::Definition(name.to_string()),
+            ));
+        }
+
+        for public in self.json["publics"].members() {
+            let name = str_field(public, "name")?;
+            let poly_type = match str_field(public, "polType")? {
+                "cmP" => "cm",
+                "constP" => "const",
+                "imP" => "exp",
+                t => return Err(format!("Invalid polynomial type {t}.")),
+            };
+            public_declarations.insert(
+                name.to_string(),
+                PublicDeclaration {
+                    id: u64_field(public, "id")?,
+                    source: self.source(&JsonValue::Null),
+                    name: name.to_string(),
+                    polynomial: self.polynomial_reference(
+                        poly_type,
+                        u64_field(public, "polId")?,
+                        false,
+                    )?,
+                    index: u64_field(public, "idx")?,
+                },
+            );
+            source_order.push(StatementIdentifier::PublicDeclaration(name.to_string()));
+        }
+
+        for (kind, key) in [
+            (IdentityKind::Polynomial, "polIdentities"),
+            (IdentityKind::Plookup, "plookupIdentities"),
+            (IdentityKind::Permutation, "permutationIdentities"),
+            (IdentityKind::Connect, "connectionIdentities"),
+        ] {
+            for (id, identity) in self.json[key].members().enumerate() {
+                let (expression_ids, left, right) = match kind {
+                    IdentityKind::Polynomial => (
+                        vec![&identity["e"]],
+                        self.selected_expressions(&identity["e"], &JsonValue::Null)?,
+                        SelectedExpressions::default(),
+                    ),
+                    IdentityKind::Plookup | IdentityKind::Permutation => (
+                        [&identity["selF"], &identity["selT"]]
+                            .into_iter()
+                            .chain(identity["f"].members())
+                            .chain(identity["t"].members())
+                            .collect(),
+                        self.selected_expressions(&identity["selF"], &identity["f"])?,
+                        self.selected_expressions(&identity["selT"], &identity["t"])?,
+                    ),
+                    IdentityKind::Connect => (
+                        identity["pols"]
+                            .members()
+                            .chain(identity["connections"].members())
+                            .collect(),
+                        self.selected_expressions(&JsonValue::Null, &identity["pols"])?,
+                        self.selected_expressions(&JsonValue::Null, &identity["connections"])?,
+                    ),
+                };
+                let first_expression = expression_ids
+                    .into_iter()
+                    .filter_map(|e| e.as_u64())
+                    .min()
+                    .unwrap_or_default();
+                ordered_by_expression.push((
+                    first_expression,
+                    StatementIdentifier::Identity(identities.len()),
+                ));
+                identities.push(Identity {
+                    id: id as u64,
+                    kind,
+                    source: self.source(identity),
+                    left,
+                    right,
+                });
+            }
+        }
+
+        ordered_by_expression.sort_by_key(|(expression_id, _)| *expression_id);
+        source_order.extend(ordered_by_expression.into_iter().map(|(_, item)| item));
+
+        Ok(Analyzed {
+            constants: HashMap::new(),
+            definitions,
+            public_declarations,
+            identities,
+            source_order,
+        })
+    }
+
+    fn polynomial(
+        &self,
+        name: &str,
+        poly_type: PolynomialType,
+        id: u64,
+        length: Option<u64>,
+    ) -> Result<Polynomial, String> {
+        Ok(Polynomial {
+            id,
+            source: self.source(&JsonValue::Null),
+            absolute_name: name.to_string(),
+            poly_type,
+            degree: u64_field(&self.json["references"][name], "polDeg")?,
+            length,
+        })
+    }
+
+    fn source(&self, item: &JsonValue) -> SourceRef {
+        SourceRef {
+            file: item["fileName"].as_str().unwrap_or_default().to_string(),
+            line: item["line"].as_usize().unwrap_or_default(),
+        }
+    }
+
+    /// Converts the selector and the expressions, which are given as expression IDs.
+    fn selected_expressions(
+        &self,
+        selector: &JsonValue,
+        expressions: &JsonValue,
+    ) -> Result<SelectedExpressions, String> {
+        let by_id = |id: &JsonValue| {
+            id.as_u64()
+                .ok_or_else(|| format!("Expected an expression ID but found {id}."))
+                .and_then(|id| self.expression_by_id(id))
+        };
+        Ok(SelectedExpressions {
+            selector: (!selector.is_null()).then(|| by_id(selector)).transpose()?,
+            expressions: expressions.members().map(by_id).collect::<Result<_, _>>()?,
+        })
+    }
+
+    fn expression_by_id(&self, id: u64) -> Result<Expression, String> {
+        let expressions = &self.json["expressions"];
+        if id >= expressions.len() as u64 {
+            return Err(format!("Reference to undefined expression {id}."));
+        }
+        self.expression(&expressions[id as usize])
+    }
+
+    fn expression(&self, expr: &JsonValue) -> Result<Expression, String> {
+        let op = str_field(expr, "op")?;
+        let values = |count: usize| {
+            let values = expr["values"]
+                .members()
+                .map(|v| self.expression(v).map(Box::new))
+                .collect::<Result<Vec<_>, _>>()?;
+            if values.len() != count {
+                return Err(format!(
+                    "Expected {count} operands of {op} but found {}.",
+                    values.len()
+                ));
+            }
+            Ok(values)
+        };
+        Ok(match op {
+            "add" | "sub" | "mul" | "pow" => {
+                let mut values = values(2)?;
+                let right = values.pop().unwrap();
+                let left = values.pop().unwrap();
+                let op = match op {
+                    "add" => BinaryOperator::Add,
+                    "sub" => BinaryOperator::Sub,
+                    "mul" => BinaryOperator::Mul,
+                    _ => BinaryOperator::Pow,
+                };
+                Expression::BinaryOperation(left, op, right)
+            }
+            "neg" => Expression::UnaryOperation(UnaryOperator::Minus, values(1)?.pop().unwrap()),
+            "number" => {
+                let value = str_field(expr, "value")?;
+                Expression::Number(parse_number(value).ok_or(format!("Invalid number {value}."))?)
+            }
+            "public" => {
+                let id = u64_field(expr, "id")?;
+                let name = self
+                    .publics
+                    .get(&id)
+                    .ok_or(format!("Reference to undeclared public {id}."))?;
+                Expression::PublicReference(name.to_string())
+            }
+            "cm" | "const" | "exp" => Expression::PolynomialReference(self.polynomial_reference(
+                op,
+                u64_field(expr, "id")?,
+                expr["next"].as_bool().unwrap_or_default(),
+            )?),
+            _ => return Err(format!("Unsupported operation {op}.")),
+        })
+    }
+
+    /// Converts a reference to a polynomial by type and ID (including the array offset).
+    fn polynomial_reference(
+        &self,
+        op: &str,
+        id: u64,
+        next: bool,
+    ) -> Result<PolynomialReference, String> {
+        if op == "exp" {
+            let name = self
+                .intermediates
+                .get(&id)
+                .ok_or(format!("Reference to undeclared polynomial {op} {id}."))?;
+            return Ok(PolynomialReference {
+                name: name.to_string(),
+                index: None,
+                next,
+            });
+        }
+        let poly_type = if op == "cm" {
+            PolynomialType::Committed
+        } else {
+            PolynomialType::Constant
+        };
+        let (name, first_id, length) = self
+            .declarations
+            .iter()
+            .filter(|(_, t, _, _)| *t == poly_type)
+            .map(|(name, _, first_id, length)| (name, *first_id, *length))
+            .find(|(_, first_id, length)| {
+                (*first_id..*first_id + length.unwrap_or(1)).contains(&id)
+            })
+            .ok_or(format!("Reference to undeclared polynomial {op} {id}."))?;
+        Ok(PolynomialReference {
+            name: name.to_string(),
+            index: length.map(|_| id - first_id),
+            next,
+        })
+    }
+}
+
+fn u64_field(item: &JsonValue, key: &str) -> Result<u64, String> {
+    item[key]
+        .as_u64()
+        .ok_or_else(|| format!("Expected a number in {key} but found {}.", item[key]))
+}
+
+fn str_field<'a>(item: &'a JsonValue, key: &str) -> Result<&'a str, String> {
+    item[key]
+        .as_str()
+        .ok_or_else(|| format!("Expected a string in {key} but found {}.", item[key]))
+}
+
+fn polynomial_type(t: &str) -> Result<PolynomialType, String> {
+    match t {
+        "cmP" => Ok(PolynomialType::Committed),
+        "constP" => Ok(PolynomialType::Constant),
+        "imP" => Ok(PolynomialType::Intermediate),
+        _ => Err(format!("Invalid polynomial type {t}.")),
+    }
+}
+
+fn type_order(t: PolynomialType) -> u8 {
+    match t {
+        PolynomialType::Constant => 0,
+        PolynomialType::Committed => 1,
+        PolynomialType::Intermediate => 2,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::{analyzer, json_exporter};
+
+    use super::*;
+
+    fn round_trip(file: &str) {
+        let exported = json_exporter::export(&analyzer::analyze(Path::new(file), &[]).unwrap());
+        let imported = import(&exported).unwrap();
+        assert_eq!(json_exporter::export(&imported), exported);
+    }
+
+    #[test]
+    fn invalid_json() {
+        let mut exported = json_exporter::export(
+            &analyzer::analyze(Path::new("tests/fibonacci.pil"), &[]).unwrap(),
+        );
+        exported["polIdentities"][0]["e"] = 1000.into();
+        assert_eq!(
+            import(&exported).err().unwrap(),
+            "Reference to undefined expression 1000."
+        );
+        exported["references"]["Fibonacci.x"]["type"] = "x".into();
+        assert_eq!(
+            import(&exported).err().unwrap(),
+            "Invalid polynomial type x."
+        );
+        exported["references"]["Fibonacci.x"].remove("type");
+        assert_eq!(
+            import(&exported).err().unwrap(),
+            "Expected a string in type but found null."
+        );
+    }
+
+    #[test]
+    fn import_fibonacci() {
+        round_trip("tests/fibonacci.pil");
+    }
+
+    #[test]
+    fn import_witness_lookup() {
+        round_trip("tests/witness_lookup.pil");
+    }
+
+    #[test]
+    fn import_polygon_hermez() {
+        for file in [
+            "arith", "binary", "global", "keccakf", "main", "mem", "storage",
+        ] {
+            round_trip(&format!("tests/polygon-hermez/{file}.pil"));
+        }
+    }
+}
@@ -0,0 +1,799 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use json::{object, JsonValue};
+use url::Url;
+
+use crate::analyzer::{self, Analyzed, PolynomialType, SourceRef};
+use crate::linter::{self, Diagnostic, Severity};
+use crate::{error_codes, parser, utils};
+
+/// Error codes of json-rpc and the language server protocol.
+const PARSE_ERROR: i32 = -32700;
+const INVALID_REQUEST: i32 = -32600;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const REQUEST_FAILED: i32 = -32803;
+
+/// Runs a language server for PIL files on stdin and stdout until the
+/// client shuts it down. Included files are searched for in the include
+/// paths like in `analyzer::analyze`.
+pub fn run(include_paths: &[PathBuf]) -> Result<(), String> {
+    let mut input = io::stdin().lock();
+    let mut output = io::stdout().lock();
+    let mut server = Server::new(include_paths);
+    let mut shut_down = false;
+    while let Some(content) = read_message(&mut input)? {
+        let responses = match json::parse(&content) {
+            Ok(message) => {
+                let id = &message["id"];
+                match (message["method"].as_str(), id.is_null()) {
+                    (Some("exit"), _) => {
+                        return match shut_down {
+                            true => Ok(()),
+                            false => Err("The client exited without a shutdown request.".into()),
+                        }
+                    }
+                    (Some(_), false) if shut_down => vec![response(
+                        id,
+                        Err((INVALID_REQUEST, "The server is shut down.".to_string())),
+                    )],
+                    (Some("initialize"), false) => vec![response(
+                        id,
+                        Ok(object! {
+                            capabilities: capabilities(),
+                            serverInfo: object! { name: "powdr" },
+                        }),
+                    )],
+                    (Some("shutdown"), false) => {
+                        shut_down = true;
+                        vec![response(id, Ok(JsonValue::Null))]
+                    }
+                    (Some(_), false) => vec![server.handle_request(&message)],
+                    (Some(_), true) => server.handle_notification(&message),
+                    // Responses to requests we never send.
+                    (None, _) => vec![],
+                }
+            }
+            Err(e) => vec![response(
+                &JsonValue::Null,
+                Err((PARSE_ERROR, format!("Invalid message: {e}"))),
+            )],
+        };
+        for response in responses {
+            write_message(&mut output, &response).map_err(|e| e.to_string())?;
+        }
+    }
+    Err("The client closed the connection without an exit notification.".into())
+}
+
+fn capabilities() -> JsonValue {
+    object! {
+        // Full synchronization, the client always sends the whole document.
+        textDocumentSync: 1,
+        definitionProvider: true,
+        hoverProvider: true,
+        renameProvider: true,
+    }
+}
+
+/// Reads a message of the base protocol: header fields, an empty line
+/// and the content, which is as long as the Content-Length header says.
+/// @returns the content or None at the end of the input.
+fn read_message(input: &mut impl BufRead) -> Result<Option<String>, String> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                let value = value.trim();
+                length = Some(
+                    value
+                        .parse::<usize>()
+                        .map_err(|e| format!("Invalid Content-Length {value}: {e}"))?,
+                );
+            }
+        }
+    }
+    let mut content = vec![0; length.ok_or("Message without Content-Length.")?];
+    input.read_exact(&mut content).map_err(|e| e.to_string())?;
+    String::from_utf8(content)
+        .map(Some)
+        .map_err(|e| format!("Message is not valid UTF-8: {e}"))
+}
+
+fn write_message(output: &mut impl Write, message: &JsonValue) -> io::Result<()> {
+    let content = message.dump();
+    write!(output, "Content-Length: {}\r\n\r\n{content}", content.len())?;
+    output.flush()
+}
+
+fn response(id: &JsonValue, result: Result<JsonValue, (i32, String)>) -> JsonValue {
+    match result {
+        Ok(result) => object! { jsonrpc: "2.0", id: id.clone(), result: result },
+        Err((code, message)) => object! {
+            jsonrpc: "2.0",
+            id: id.clone(),
+            error: object! { code: code, message: message },
+        },
+    }
+}
+
+fn notification(method: &str, params: JsonValue) -> JsonValue {
+    object! { jsonrpc: "2.0", method: method, params: params }
+}
+
+/// A position in a document, lines and characters are counted from zero.
+#[derive(Clone, Copy)]
+struct Position {
+    line: usize,
+    character: usize,
+}
+
+impl From<Position> for JsonValue {
+    fn from(position: Position) -> JsonValue {
+        object! { line: position.line, character: position.character }
+    }
+}
+
+/// The parameters of requests about a position in a document.
+struct DocumentPosition {
+    path: PathBuf,
+    position: Position,
+}
+
+impl DocumentPosition {
+    fn from_params(params: &JsonValue) -> Result<DocumentPosition, String> {
+        let position = &params["position"];
+        let field = |name: &str| {
+            position[name]
+                .as_usize()
+                .ok_or_else(|| format!("Missing or invalid position.{name}."))
+        };
+        Ok(DocumentPosition {
+            path: document_path(params)?,
+            position: Position {
+                line: field("line")?,
+                character: field("character")?,
+            },
+        })
+    }
+}
+
+/// The open documents of a language server session.
+/// Every open document is analyzed on its own, files it includes
+/// are read from the editor if they are open and from disk otherwise.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<PathBuf, String>,
+    include_paths: Vec<PathBuf>,
+}
+
+impl Server {
+    pub fn new(include_paths: &[PathBuf]) -> Server {
+        Server {
+            include_paths: include_paths.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    /// @returns the response to a request.
+    pub fn handle_request(&self, request: &JsonValue) -> JsonValue {
+        let params = &request["params"];
+        let method = request["method"].as_str().unwrap_or_default();
+        let result = match method {
+            "textDocument/hover" => {
+                DocumentPosition::from_params(params).map(|p| Ok(self.hover(&p).into()))
+            }
+            "textDocument/definition" => {
+                DocumentPosition::from_params(params).map(|p| Ok(self.definition(&p).into()))
+            }
+            "textDocument/rename" => DocumentPosition::from_params(params).and_then(|p| {
+                let new_name = params["newName"].as_str().ok_or("Missing newName.")?;
+                Ok(self
+                    .rename(&p, new_name)
+                    .map_err(|message| (REQUEST_FAILED, message)))
+            }),
+            _ => Ok(Err((
+                METHOD_NOT_FOUND,
+                format!("Unsupported request {method}."),
+            ))),
+        };
+        let result = match result {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(message)) => Err(message),
+            Err(message) => Err((INVALID_PARAMS, message)),
+        };
+        response(&request["id"], result)
+    }
+
+    /// Updates the open documents.
+    /// @returns the diagnostics to publish.
+    pub fn handle_notification(&mut self, notification: &JsonValue) -> Vec<JsonValue> {
+        let params = &notification["params"];
+        let Ok(path) = document_path(params) else {
+            return vec![];
+        };
+        match notification["method"].as_str().unwrap_or_default() {
+            "textDocument/didOpen" => {
+                let Some(text) = params["textDocument"]["text"].as_str() else {
+                    return vec![];
+                };
+                self.documents.insert(path.clone(), text.to_string());
+            }
+            "textDocument/didChange" => {
+                // We only support full synchronization.
+                let Some(text) = params["contentChanges"]
+                    .members()
+                    .last()
+                    .and_then(|change| change["text"].as_str())
+                else {
+                    return vec![];
+                };
+                self.documents.insert(path.clone(), text.to_string());
+            }
+            "textDocument/didSave" => {}
+            "textDocument/didClose" => {
+                self.documents.remove(&path);
+                return vec![publish(&path, vec![])];
+            }
+            _ => return vec![],
+        }
+        self.diagnostics(&path)
+            .into_iter()
+            .map(|(file, diagnostics)| publish(&file, diagnostics))
+            .collect()
+    }
+
+    /// @returns the diagnostics of analyzing the document, for each file it consists of.
+    fn diagnostics(&self, path: &Path) -> Vec<(PathBuf, Vec<JsonValue>)> {
+        let (diagnostics, sources) = match self.analyze(path) {
+            Ok((analyzed, sources)) => (linter::lint(&analyzed), sources),
+            Err(diagnostic) => (vec![diagnostic], vec![path.to_path_buf()]),
+        };
+        sources
+            .iter()
+            .map(|file| {
+                let text = self.text(file).unwrap_or_default();
+                let diagnostics = diagnostics
+                    .iter()
+                    .filter(|d| {
+                        source_path(&sources, &d.source.file).map_or(path, PathBuf::as_path) == file
+                    })
+                    .map(|d| to_lsp_diagnostic(d, &text))
+                    .collect();
+                (file.clone(), diagnostics)
+            })
+            .collect()
+    }
+
+    fn hover(&self, params: &DocumentPosition) -> Option<JsonValue> {
+        let (path, symbol) = self.symbol_at(params)?;
+        let (analyzed, _) = self.analyze_including(&path).ok()?;
+        let name = symbol.resolve(&analyzed)?;
+        let description = match analyzed.definitions.get(&name) {
+            Some((poly, _)) => {
+                let kind = match poly.poly_type {
+                    PolynomialType::Committed => "witness column",
+                    PolynomialType::Constant => "fixed column",
+                    PolynomialType::Intermediate => "intermediate polynomial",
+                };
+                let length = poly.length.map(|l| format!("[{l}]")).unwrap_or_default();
+                format!("{kind} `{name}{length}`, degree {}", poly.degree)
+            }
+            None => format!("constant `{name} = {}`", analyzed.constants[&name]),
+        };
+        let text = self.text(&path)?;
+        Some(object! {
+            contents: object! { kind: "markdown", value: description },
+            range: to_range(&text, &symbol.range),
+        })
+    }
+
+    /// @returns the location where the polynomial under the cursor is declared.
+    fn definition(&self, params: &DocumentPosition) -> Option<JsonValue> {
+        let (path, symbol) = self.symbol_at(params)?;
+        let (analyzed, sources) = self.analyze_including(&path).ok()?;
+        let name = symbol.resolve(&analyzed)?;
+        let source = &analyzed.definitions.get(&name)?.0.source;
+        let file = source_path(&sources, &source.file)?;
+        let text = self.text(file)?;
+        let line_starts = utils::compute_line_starts(&text);
+        let range = symbols(&text)
+            .into_iter()
+            .find(|s| {
+                utils::offset_to_line(s.range.start, &line_starts) == source.line
+                    && s.resolve(&analyzed).as_ref() == Some(&name)
+            })
+            .map(|s| s.range)
+            .unwrap_or_else(|| {
+                let start = line_starts
+                    .get(source.line - 1)
+                    .copied()
+                    .unwrap_or_default();
+                start..start
+            });
+        Some(object! {
+            uri: to_uri(file),
+            range: to_range(&text, &range),
+        })
+    }
+
+    /// Renames the polynomial or constant under the cursor in the document
+    /// and all files it includes.
+    fn rename(&self, params: &DocumentPosition, new_name: &str) -> Result<JsonValue, String> {
+        let (path, symbol) = self
+            .symbol_at(params)
+            .ok_or("No polynomial or constant at the cursor.")?;
+        let (analyzed, sources) = self
+            .analyze_including(&path)
+            .map_err(|_| "The file has errors.".to_string())?;
+        let name = symbol
+            .resolve(&analyzed)
+            .ok_or("No polynomial or constant at the cursor.")?;
+        let is_constant = name.starts_with('%');
+        let valid = match new_name.strip_prefix('%') {
+            Some(n) => is_constant && is_identifier(n),
+            None => !is_constant && is_identifier(new_name),
+        };
+        if !valid {
+            return Err(format!("{new_name} is not a valid name for {name}."));
+        }
+        let new_absolute_name = match name.rsplit_once('.') {
+            Some((namespace, _)) => format!("{namespace}.{new_name}"),
+            None => new_name.to_string(),
+        };
+        if analyzed.definitions.contains_key(&new_absolute_name)
+            || analyzed.constants.contains_key(&new_absolute_name)
+        {
+            return Err(format!("{new_absolute_name} already exists."));
+        }
+        let mut changes = JsonValue::new_object();
+        for (uri, edits) in sources.iter().filter_map(|file| {
+            let text = self.text(file)?;
+            let edits = symbols(&text)
+                .into_iter()
+                .filter(|s| s.resolve(&analyzed).as_ref() == Some(&name))
+                .map(|s| {
+                    object! {
+                        range: to_range(&text, &(s.name_start..s.range.end)),
+                        newText: new_name,
+                    }
+                })
+                .collect::<Vec<_>>();
+            (!edits.is_empty()).then(|| (to_uri(file), edits))
+        }) {
+            changes[uri.as_str()] = edits.into();
+        }
+        Ok(object! { changes: changes })
+    }
+
+    /// @returns the contents of the file in the editor or on disk.
+    fn text(&self, path: &Path) -> Option<String> {
+        match self.documents.get(path) {
+            Some(text) => Some(text.clone()),
+            None => fs::read_to_string(path).ok(),
+        }
+    }
+
+    fn symbol_at(&self, params: &DocumentPosition) -> Option<(PathBuf, Symbol)> {
+        let path = params.path.clone();
+        let text = self.text(&path)?;
+        let offset = to_offset(&text, params.position);
+        let symbol = symbols(&text)
+            .into_iter()
+            .find(|s| s.range.start <= offset && offset <= s.range.end)?;
+        Some((path, symbol))
+    }
+
+    /// Analyzes an open document that includes the given file, so that
+    /// names defined in other files can be resolved, or the file itself.
+    fn analyze_including(&self, path: &Path) -> Result<(Analyzed, Vec<PathBuf>), Diagnostic> {
+        let mut documents = self.documents.keys().collect::<Vec<_>>();
+        documents.sort();
+        documents
+            .into_iter()
+            .filter(|document| document.as_path() != path)
+            .filter_map(|document| self.analyze(document).ok())
+            .find(|(_, sources)| sources.iter().any(|s| s == path))
+            .map_or_else(|| self.analyze(path), Ok)
+    }
+
+    /// @returns the analyzed document and the files it consists of or
+    /// the first error found.
+    fn analyze(&self, path: &Path) -> Result<(Analyzed, Vec<PathBuf>), Diagnostic> {
+        let text = self.text(path).unwrap_or_default();
+        let file = path.to_string_lossy();
+        parser::parse(Some(&file), &text).map_err(|err| Diagnostic::from(&err))?;
+        analyzer::analyze_string_with_sources(path, &self.include_paths, &text).map_err(|message| {
+            Diagnostic {
+                code: error_codes::ANALYZER_ERROR,
+                severity: Severity::Error,
+                source: SourceRef {
+                    file: file.to_string(),
+                    line: 1,
+                },
+                message,
+            }
+        })
+    }
+}
+
+/// A (possibly namespaced) name of a polynomial or a constant in the source.
+struct Symbol {
+    range: Range<usize>,
+    /// Start of the name without the namespace.
+    name_start: usize,
+    text: String,
+    /// The namespace the symbol occurs in.
+    namespace: String,
+}
+
+impl Symbol {
+    /// @returns the absolute name of the polynomial or constant
+    /// the symbol refers to, if it exists.
+    fn resolve(&self, analyzed: &Analyzed) -> Option<String> {
+        if self.text.starts_with('%') {
+            return analyzed
+                .constants
+                .contains_key(&self.text)
+                .then(|| self.text.clone());
+        }
+        let name = if self.text.contains('.') {
+            self.text.clone()
+        } else {
+            format!("{}.{}", self.namespace, self.text)
+        };
+        analyzed.definitions.contains_key(&name).then_some(name)
+    }
+}
+
+/// @returns all identifiers in the source outside of comments and strings,
+/// together with the namespace they occur in.
+fn symbols(text: &str) -> Vec<Symbol> {
+    let bytes = text.as_bytes();
+    let end_of_identifier = |mut i: usize| {
+        while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || b"_$".contains(&bytes[i])) {
+            i += 1;
+        }
+        i
+    };
+    let is_identifier_start =
+        |i: usize| i < bytes.len() && (bytes[i].is_ascii_alphabetic() || bytes[i] == b'_');
+    let mut namespace = "Global".to_string();
+    let mut expect_namespace = false;
+    let mut result = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &text[i..];
+        if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            i += comment.find("*/").map(|n| n + 4).unwrap_or(rest.len());
+        } else if bytes[i] == b'"' {
+            i += rest[1..].find('"').map(|n| n + 2).unwrap_or(rest.len());
+        } else if bytes[i].is_ascii_digit() {
+            i = end_of_identifier(i);
+        } else if is_identifier_start(i) || (bytes[i] == b'%' && is_identifier_start(i + 1)) {
+            let start = i;
+            i = end_of_identifier(i + 1);
+            let mut name_start = start;
+            if bytes[start] != b'%' && bytes.get(i) == Some(&b'.') && is_identifier_start(i + 1) {
+                name_start = i + 1;
+                i = end_of_identifier(i + 1);
+            }
+            let word = &text[start..i];
+            if expect_namespace {
+                namespace = word.to_string();
+                expect_namespace = false;
+            } else if word == "namespace" {
+                expect_namespace = true;
+            } else {
+                result.push(Symbol {
+                    range: start..i,
+                    name_start,
+                    text: word.to_string(),
+                    namespace: namespace.clone(),
+                });
+            }
+        } else {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    result
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// @returns the file among the sources that a source reference refers to.
+/// The analyzer only stores the file name, the parser the full path.
+fn source_path<'a>(sources: &'a [PathBuf], file: &str) -> Option<&'a PathBuf> {
+    sources
+        .iter()
+        .find(|s| s.as_path() == Path::new(file))
+        .or_else(|| {
+            sources
+                .iter()
+                .find(|s| s.file_name() == Path::new(file).file_name())
+        })
+}
+
+fn to_lsp_diagnostic(diagnostic: &Diagnostic, text: &str) -> JsonValue {
+    let line_starts = utils::compute_line_starts(text);
+    let line = diagnostic.source.line.max(1) - 1;
+    let start = line_starts.get(line).copied().unwrap_or(text.len());
+    let end = line_starts
+        .get(line + 1)
+        .map(|next| text[..*next].trim_end_matches(['\n', '\r']).len())
+        .unwrap_or(text.len())
+        .max(start);
+    object! {
+        range: to_range(text, &(start..end)),
+        severity: match diagnostic.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        },
+        code: diagnostic.code,
+        source: "powdr",
+        message: diagnostic.message.as_str(),
+    }
+}
+
+fn publish(path: &Path, diagnostics: Vec<JsonValue>) -> JsonValue {
+    notification(
+        "textDocument/publishDiagnostics",
+        object! { uri: to_uri(path), diagnostics: diagnostics },
+    )
+}
+
+/// @returns the path of the document the parameters of a request or notification refer to.
+fn document_path(params: &JsonValue) -> Result<PathBuf, String> {
+    let uri = params["textDocument"]["uri"]
+        .as_str()
+        .ok_or("Missing textDocument.uri.")?;
+    let uri = Url::parse(uri).map_err(|e| format!("Invalid uri {uri}: {e}"))?;
+    Ok(uri
+        .to_file_path()
+        .unwrap_or_else(|_| PathBuf::from(uri.path())))
+}
+
+fn to_uri(path: &Path) -> String {
+    Url::from_file_path(path)
+        .map(String::from)
+        .unwrap_or_else(|_| format!("file://{}", path.to_string_lossy()))
+}
+
+/// Converts a position to a byte offset, assuming that lines only contain ASCII characters.
+fn to_offset(text: &str, position: Position) -> usize {
+    let line_starts = utils::compute_line_starts(text);
+    line_starts
+        .get(position.line)
+        .map(|start| start + position.character)
+        .unwrap_or(text.len())
+        .min(text.len())
+}
+
+fn to_range(text: &str, range: &Range<usize>) -> JsonValue {
+    let line_starts = utils::compute_line_starts(text);
+    let to_position = |offset: usize| {
+        let line = utils::offset_to_line(offset, &line_starts) - 1;
+        Position {
+            line,
+            character: offset - line_starts[line],
+        }
+    };
+    object! {
+        start: to_position(range.start),
+        end: to_position(range.end),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use json::array;
+
+    use super::*;
+
+    const MAIN: &str = r#"include "columns.pil";
+namespace Main(4);
+pol constant FIRST = [1, 0, 0, 0];
+pol commit y;
+// x is defined in columns.pil
+y = Main.x * %N;
+"#;
+
+    const COLUMNS: &str = r#"constant %N = 4;
+namespace Main(%N);
+pol commit x;
+(1 - FIRST') * (x' - x) = 0;
+"#;
+
+    fn setup() -> (mktemp::Temp, PathBuf, PathBuf, Server) {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        let main = dir.join("main.pil");
+        let columns = dir.join("columns.pil");
+        fs::write(&main, MAIN).unwrap();
+        fs::write(&columns, COLUMNS).unwrap();
+        let (main, columns) = (
+            main.canonicalize().unwrap(),
+            columns.canonicalize().unwrap(),
+        );
+        let mut server = Server::default();
+        server.handle_notification(&notification(
+            "textDocument/didOpen",
+            object! {
+                textDocument: object! {
+                    uri: to_uri(&main),
+                    languageId: "pil",
+                    version: 0,
+                    text: MAIN,
+                },
+            },
+        ));
+        (dir, main, columns, server)
+    }
+
+    fn position(path: &Path, line: usize, character: usize) -> DocumentPosition {
+        DocumentPosition {
+            path: path.to_path_buf(),
+            position: Position { line, character },
+        }
+    }
+
+    fn range(line: usize, start: usize, end_line: usize, end: usize) -> JsonValue {
+        object! {
+            start: object! { line: line, character: start },
+            end: object! { line: end_line, character: end },
+        }
+    }
+
+    fn change(server: &mut Server, path: &Path, text: &str) -> Vec<JsonValue> {
+        server.handle_notification(&notification(
+            "textDocument/didChange",
+            object! {
+                textDocument: object! { uri: to_uri(path), version: 1 },
+                contentChanges: array![object! { text: text }],
+            },
+        ))
+    }
+
+    #[test]
+    fn symbols_after_non_ascii_characters() {
+        let text = "x = ä + Main.y;";
+        let found = symbols(text)
+            .into_iter()
+            .map(|s| (s.text, s.range))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![("x".to_string(), 0..1), ("Main.y".to_string(), 9..15)]
+        );
+    }
+
+    #[test]
+    fn read_and_write_messages() {
+        let mut output = vec![];
+        write_message(&mut output, &notification("exit", JsonValue::Null)).unwrap();
+        write_message(&mut output, &object! { text: "ä" }).unwrap();
+        let mut input = io::Cursor::new(output);
+        assert_eq!(
+            read_message(&mut input)
+                .unwrap()
+                .map(|m| json::parse(&m).unwrap()),
+            Some(object! { jsonrpc: "2.0", method: "exit", params: JsonValue::Null })
+        );
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some(r#"{"text":"ä"}"#.to_string())
+        );
+        assert_eq!(read_message(&mut input), Ok(None));
+        let mut input = io::Cursor::new("Content-Type: x\r\n\r\n{}");
+        assert!(read_message(&mut input).is_err());
+    }
+
+    #[test]
+    fn hover_and_definition() {
+        let (_dir, main, columns, server) = setup();
+        let hover = server.hover(&position(&main, 5, 6)).unwrap();
+        assert_eq!(
+            hover["contents"],
+            object! { kind: "markdown", value: "witness column `Main.x`, degree 4" }
+        );
+        assert_eq!(hover["range"], range(5, 4, 5, 10));
+        let hover = server.hover(&position(&main, 5, 14)).unwrap();
+        assert_eq!(hover["contents"]["value"], "constant `%N = 4`");
+        assert!(server.hover(&position(&main, 4, 4)).is_none());
+        assert_eq!(
+            server.definition(&position(&main, 5, 9)),
+            Some(object! { uri: to_uri(&columns), range: range(2, 11, 2, 12) })
+        );
+    }
+
+    #[test]
+    fn requests() {
+        let (_dir, main, _, server) = setup();
+        let request = |method: &str, params: JsonValue| {
+            server
+                .handle_request(&object! { jsonrpc: "2.0", id: 7, method: method, params: params })
+        };
+        let params = object! {
+            textDocument: object! { uri: to_uri(&main) },
+            position: object! { line: 5, character: 14 },
+        };
+        let response = request("textDocument/hover", params.clone());
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["contents"]["value"], "constant `%N = 4`");
+        let mut rename = params.clone();
+        rename["newName"] = "M".into();
+        let response = request("textDocument/rename", rename);
+        assert_eq!(response["error"]["code"], REQUEST_FAILED);
+        let response = request("textDocument/rename", params);
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        let response = request("textDocument/formatting", JsonValue::Null);
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn rename_across_includes() {
+        let (_dir, main, columns, server) = setup();
+        let edit = server.rename(&position(&columns, 2, 11), "z").unwrap();
+        let changes = &edit["changes"];
+        let edit =
+            |line, start, end| object! { range: range(line, start, line, end), newText: "z" };
+        assert_eq!(changes[to_uri(&main).as_str()], array![edit(5, 9, 10)]);
+        assert_eq!(
+            changes[to_uri(&columns).as_str()],
+            array![edit(2, 11, 12), edit(3, 16, 17), edit(3, 21, 22)]
+        );
+        assert_eq!(
+            server.rename(&position(&main, 5, 0), "x"),
+            Err("Main.x already exists.".to_string())
+        );
+        assert!(server.rename(&position(&main, 5, 0), "%y").is_err());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let (_dir, main, columns, mut server) = setup();
+        let published = change(&mut server, &main, &MAIN.replace("Main.x", "Main.w"))
+            .into_iter()
+            .map(|n| {
+                assert_eq!(n["method"], "textDocument/publishDiagnostics");
+                let params = &n["params"];
+                (
+                    params["uri"].to_string(),
+                    params["diagnostics"].members().cloned().collect::<Vec<_>>(),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        assert_eq!(published.len(), 2);
+        let columns_diagnostics = &published[&to_uri(&columns)];
+        assert_eq!(columns_diagnostics.len(), 0);
+        let main_diagnostics = &published[&to_uri(&main)];
+        assert_eq!(main_diagnostics.len(), 1);
+        assert_eq!(
+            main_diagnostics[0]["message"],
+            "Reference to undeclared polynomial Main.w."
+        );
+        assert_eq!(main_diagnostics[0]["range"], range(5, 0, 5, 16));
+
+        let published = change(&mut server, &main, "pol commit");
+        assert_eq!(published[0]["params"]["diagnostics"][0]["code"], "E0002");
+    }
+}
@@ -1,9 +1,34 @@
 pub mod analyzer;
 pub mod asm_compiler;
+pub mod backend;
+pub mod bench;
+pub mod bundle;
+pub mod column_file;
+pub mod column_stats;
 pub mod commit_evaluator;
 pub mod compiler;
+pub mod config;
 pub mod constant_evaluator;
+pub mod constraint_checker;
+pub mod dot_exporter;
+pub mod error_codes;
+pub mod explain;
+pub mod field_fit;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
 pub mod json_exporter;
+pub mod json_importer;
+pub mod language_server;
+pub mod linter;
 pub mod number;
 pub mod parser;
+pub mod query_callbacks;
+pub mod repl;
+pub mod satisfiability;
+pub mod smt_exporter;
+pub mod snapshot;
+pub mod solver_telemetry;
+pub mod trace_viewer;
 pub mod utils;
+pub mod witness_hints;
+pub mod witness_io;
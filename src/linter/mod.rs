@@ -0,0 +1,350 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{self, Display};
+
+use json::{object, JsonValue};
+
+use crate::analyzer::{
+    Analyzed, Expression, FunctionValueDefinition, PolynomialType, SourceRef, StatementIdentifier,
+};
+use crate::error_codes;
+use crate::number::DegreeType;
+use crate::parser::ParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// Stable identifier of the kind of problem, see `lint`.
+    pub code: &'static str,
+    pub severity: Severity,
+    pub source: SourceRef,
+    pub message: String,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+impl Diagnostic {
+    /// @returns the diagnostic as a json object with the keys `code`,
+    /// `severity`, `message`, `span` (an object with the keys `file` and
+    /// `line`) and `rendered` (the diagnostic as printed in text form).
+    pub fn to_json(&self) -> JsonValue {
+        object! {
+            code: self.code,
+            severity: self.severity.as_str(),
+            message: self.message.as_str(),
+            span: object! {
+                file: self.source.file.as_str(),
+                line: self.source.line,
+            },
+            rendered: self.to_string(),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.source.file,
+            self.source.line,
+            self.severity.as_str(),
+            self.message
+        )
+    }
+}
+
+impl From<&ParseError<'_>> for Diagnostic {
+    fn from(err: &ParseError<'_>) -> Self {
+        let source = SourceRef {
+            file: err.file_name().to_string(),
+            line: err.line(),
+        };
+        error(
+            error_codes::SYNTAX_ERROR,
+            &source,
+            err.message().to_string(),
+        )
+    }
+}
+
+/// Finds problems in an analyzed PIL file that the analyzer does not report:
+/// - E0001: references to undeclared polynomials and public values,
+/// - E0003: public values taken in a row beyond the degree of their polynomial,
+/// - E0004: identities that refer to polynomials of different degrees,
+/// - W0001: fixed columns that are declared but not defined,
+/// - W0002: witness columns that do not occur in any identity.
+///
+/// Syntax errors (E0002) are converted from parse errors, other errors
+/// reported by the analyzer have the code E0005.
+///
+/// @returns the diagnostics in source order, the unused witness columns last.
+pub fn lint(analyzed: &Analyzed) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut constrained = HashSet::new();
+    for statement in &analyzed.source_order {
+        match statement {
+            StatementIdentifier::Definition(name) => {
+                let (poly, definition) = &analyzed.definitions[name];
+                let expressions: Vec<&Expression> = match definition {
+                    Some(FunctionValueDefinition::Mapping(e))
+                    | Some(FunctionValueDefinition::Query(e)) => vec![e],
+                    Some(FunctionValueDefinition::Array(items)) => items.iter().collect(),
+                    Some(FunctionValueDefinition::Recurrence(e, initial)) => {
+                        std::iter::once(e).chain(initial).collect()
+                    }
+                    None => vec![],
+                };
+                for expr in expressions {
+                    check_references(analyzed, &poly.source, expr, &mut diagnostics);
+                }
+                if poly.poly_type == PolynomialType::Constant && definition.is_none() {
+                    diagnostics.push(warning(
+                        "W0001",
+                        &poly.source,
+                        format!("Fixed column {name} is declared but not defined."),
+                    ));
+                }
+            }
+            StatementIdentifier::PublicDeclaration(name) => {
+                let public = &analyzed.public_declarations[name];
+                let poly_name = &public.polynomial.name;
+                match analyzed.definitions.get(poly_name) {
+                    None => diagnostics.push(error(
+                        "E0001",
+                        &public.source,
+                        format!("Public value {name} refers to undeclared polynomial {poly_name}."),
+                    )),
+                    Some((poly, _)) if public.index >= poly.degree => diagnostics.push(error(
+                        "E0003",
+                        &public.source,
+                        format!(
+                            "Public value {name} is taken in row {}, but {poly_name} only has {} rows.",
+                            public.index, poly.degree
+                        ),
+                    )),
+                    Some(_) => {
+                        constrained.insert(poly_name.as_str());
+                    }
+                }
+            }
+            StatementIdentifier::Identity(index) => {
+                let identity = &analyzed.identities[*index];
+                let expressions = [&identity.left, &identity.right]
+                    .into_iter()
+                    .flat_map(|selected| selected.selector.iter().chain(&selected.expressions))
+                    .collect::<Vec<_>>();
+                let mut degrees = BTreeSet::new();
+                for expr in expressions {
+                    check_references(analyzed, &identity.source, expr, &mut diagnostics);
+                    for name in referenced_polynomials(analyzed, expr) {
+                        constrained.insert(name);
+                        degrees.insert(analyzed.definitions[name].0.degree);
+                    }
+                }
+                if degrees.len() > 1 {
+                    diagnostics.push(error(
+                        "E0004",
+                        &identity.source,
+                        format!(
+                            "Identity {identity} refers to polynomials of different degrees: {}.",
+                            degrees
+                                .iter()
+                                .map(DegreeType::to_string)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+    for (poly, _) in analyzed.committed_polys_in_source_order() {
+        if !constrained.contains(poly.absolute_name.as_str()) {
+            diagnostics.push(warning(
+                "W0002",
+                &poly.source,
+                format!(
+                    "Witness column {} is not used in any identity.",
+                    poly.absolute_name
+                ),
+            ));
+        }
+    }
+    diagnostics
+}
+
+fn error(code: &'static str, source: &SourceRef, message: String) -> Diagnostic {
+    Diagnostic {
+        code,
+        severity: Severity::Error,
+        source: source.clone(),
+        message,
+    }
+}
+
+fn warning(code: &'static str, source: &SourceRef, message: String) -> Diagnostic {
+    Diagnostic {
+        code,
+        severity: Severity::Warning,
+        source: source.clone(),
+        message,
+    }
+}
+
+/// Reports references to undeclared polynomials and public values in the expression.
+fn check_references(
+    analyzed: &Analyzed,
+    source: &SourceRef,
+    expr: &Expression,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for_each_subexpression(expr, &mut |e| match e {
+        Expression::PolynomialReference(poly) if !analyzed.definitions.contains_key(&poly.name) => {
+            diagnostics.push(error(
+                "E0001",
+                source,
+                format!("Reference to undeclared polynomial {}.", poly.name),
+            ))
+        }
+        Expression::FunctionCall(name, _) if !analyzed.definitions.contains_key(name) => {
+            diagnostics.push(error(
+                "E0001",
+                source,
+                format!("Call to undeclared polynomial {name}."),
+            ))
+        }
+        Expression::PublicReference(name) if !analyzed.public_declarations.contains_key(name) => {
+            diagnostics.push(error(
+                "E0001",
+                source,
+                format!("Reference to undeclared public value {name}."),
+            ))
+        }
+        _ => {}
+    });
+}
+
+/// @returns the names of the declared polynomials referenced in the expression,
+/// including the ones referenced through intermediate polynomials.
+fn referenced_polynomials<'a>(analyzed: &'a Analyzed, expr: &Expression) -> BTreeSet<&'a str> {
+    let mut result = BTreeSet::new();
+    let mut to_visit = vec![];
+    for_each_subexpression(expr, &mut |e| {
+        if let Expression::PolynomialReference(poly) = e {
+            to_visit.extend(analyzed.definitions.get_key_value(&poly.name));
+        }
+    });
+    while let Some((name, (poly, definition))) = to_visit.pop() {
+        if !result.insert(name.as_str()) {
+            continue;
+        }
+        if let (PolynomialType::Intermediate, Some(FunctionValueDefinition::Mapping(e))) =
+            (poly.poly_type, definition)
+        {
+            for_each_subexpression(e, &mut |e| {
+                if let Expression::PolynomialReference(poly) = e {
+                    to_visit.extend(analyzed.definitions.get_key_value(&poly.name));
+                }
+            });
+        }
+    }
+    result
+}
+
+fn for_each_subexpression(expr: &Expression, f: &mut impl FnMut(&Expression)) {
+    f(expr);
+    match expr {
+        Expression::Tuple(items) | Expression::FunctionCall(_, items) => {
+            items.iter().for_each(|e| for_each_subexpression(e, f))
+        }
+        Expression::BinaryOperation(l, _, r) => {
+            for_each_subexpression(l, f);
+            for_each_subexpression(r, f);
+        }
+        Expression::UnaryOperation(_, e) => for_each_subexpression(e, f),
+        Expression::IfElse(c, t, e) => {
+            for_each_subexpression(c, f);
+            for_each_subexpression(t, f);
+            for_each_subexpression(e, f);
+        }
+        Expression::Constant(_)
+        | Expression::PolynomialReference(_)
+        | Expression::LocalVariableReference(_)
+        | Expression::PublicReference(_)
+        | Expression::Number(_)
+        | Expression::String(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+
+    use super::*;
+
+    fn lint_string(src: &str) -> Vec<String> {
+        lint(&analyze_string(src).unwrap())
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn clean() {
+        let src = r#"
+            namespace F(4);
+            pol constant FIRST = [1, 0, 0, 0];
+            pol commit x;
+            pol next = x + 1;
+            (1 - FIRST') * (x' - next) = 0;
+            public last = x(3);
+        "#;
+        assert!(lint_string(src).is_empty());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let src = r#"
+            namespace F(4);
+            pol constant FIRST;
+            pol commit x, y, unused;
+            pol inter = y + 1;
+            FIRST * (x' - z) = 0;
+            public out = x(4);
+            namespace G(8);
+            pol commit a;
+            a = F.inter;
+        "#;
+        assert_eq!(
+            lint_string(src),
+            vec![
+                "input:3: warning: Fixed column F.FIRST is declared but not defined.",
+                "input:6: error: Reference to undeclared polynomial F.z.",
+                "input:7: error: Public value out is taken in row 4, but F.x only has 4 rows.",
+                "input:10: error: Identity G.a = F.inter; refers to polynomials of different degrees: 4, 8.",
+                "input:4: warning: Witness column F.unused is not used in any identity.",
+            ]
+        );
+    }
+
+    #[test]
+    fn json() {
+        let diagnostics = lint(&analyze_string("namespace F(4);\nF.y = 1;").unwrap());
+        assert_eq!(
+            diagnostics[0].to_json().dump(),
+            r#"{"code":"E0001","severity":"error","message":"Reference to undeclared polynomial F.y.","span":{"file":"input","line":2},"rendered":"input:2: error: Reference to undeclared polynomial F.y."}"#
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use num_bigint::Sign;
+use num_bigint::{BigUint, Sign};
 
 /// The abstract type of numbers to be computed with.
 /// They have arbitrary precision, but need to be converted
@@ -8,11 +8,33 @@ pub type AbstractNumberType = num_bigint::BigInt;
 pub type DegreeType = u64;
 
 pub fn abstract_to_degree(input: &AbstractNumberType) -> DegreeType {
-    match input.to_biguint().unwrap().to_u64_digits()[..] {
-        [] => 0,
-        [d] => d,
-        _ => panic!(),
+    to_u64(input).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Converts the number to u64, fails if it is negative or too large.
+pub fn to_u64(input: &AbstractNumberType) -> Result<u64, String> {
+    match input.to_biguint().map(|x| x.to_u64_digits()).as_deref() {
+        Some([]) => Ok(0),
+        Some([d]) => Ok(*d),
+        _ => Err(format!("Number {input} does not fit into u64.")),
+    }
+}
+
+/// Converts the number to u32, fails if it is negative or too large.
+pub fn to_u32(input: &AbstractNumberType) -> Result<u32, String> {
+    to_u64(input)?
+        .try_into()
+        .map_err(|_| format!("Number {input} does not fit into u32."))
+}
+
+/// Converts a field element to its signed value (see `to_signed`) as i64,
+/// fails if the number is not a field element in canonical form.
+pub fn to_i64(input: &AbstractNumberType) -> Result<i64, String> {
+    if input.sign() == Sign::Minus || *input >= GOLDILOCKS_MOD.into() {
+        return Err(format!("Number {input} is not a canonical field element."));
     }
+    // The signed value is at most half the modulus in absolute value.
+    Ok(i64::try_from(&to_signed(input)).unwrap())
 }
 
 pub fn is_zero(x: &AbstractNumberType) -> bool {
@@ -20,11 +42,204 @@ pub fn is_zero(x: &AbstractNumberType) -> bool {
 }
 
 pub const GOLDILOCKS_MOD: u64 = 0xffffffff00000001u64;
+/// The largest `k` such that `2**k` divides `GOLDILOCKS_MOD - 1`.
+pub const GOLDILOCKS_TWO_ADICITY: u32 = 32;
+/// A generator of the multiplicative group modulo `GOLDILOCKS_MOD`.
+pub const GOLDILOCKS_GENERATOR: u64 = 7;
+
+/// @returns a primitive `2**log_n`-th root of unity modulo `GOLDILOCKS_MOD`.
+pub fn goldilocks_root_of_unity(log_n: u32) -> u64 {
+    assert!(
+        log_n <= GOLDILOCKS_TWO_ADICITY,
+        "There is no root of unity of order 2**{log_n}."
+    );
+    goldilocks_pow(GOLDILOCKS_GENERATOR, (GOLDILOCKS_MOD - 1) >> log_n)
+}
+
+/// 2^64 mod GOLDILOCKS_MOD
+const GOLDILOCKS_EPSILON: u64 = 0xffffffff;
+
+/// Reduces a 128-bit number modulo `GOLDILOCKS_MOD`, using the special form
+/// of the modulus (2^64 - 2^32 + 1) instead of a generic division.
+pub fn goldilocks_reduce(x: u128) -> u64 {
+    let low = x as u64;
+    let high = (x >> 64) as u64;
+    let high_high = high >> 32;
+    let high_low = high & GOLDILOCKS_EPSILON;
+    // x = low + high_low * 2^64 + high_high * 2^96
+    // and 2^64 = 2^32 - 1, 2^96 = -1 modulo GOLDILOCKS_MOD.
+    let (mut t0, borrow) = low.overflowing_sub(high_high);
+    if borrow {
+        t0 -= GOLDILOCKS_EPSILON;
+    }
+    let t1 = high_low * GOLDILOCKS_EPSILON;
+    let (mut t2, carry) = t0.overflowing_add(t1);
+    if carry {
+        t2 += GOLDILOCKS_EPSILON;
+    }
+    if t2 >= GOLDILOCKS_MOD {
+        t2 - GOLDILOCKS_MOD
+    } else {
+        t2
+    }
+}
+
+/// Multiplies two numbers modulo `GOLDILOCKS_MOD`.
+pub fn goldilocks_mul(a: u64, b: u64) -> u64 {
+    goldilocks_reduce(a as u128 * b as u128)
+}
 
+/// Computes `x**e` modulo `GOLDILOCKS_MOD`.
+pub fn goldilocks_pow(mut x: u64, mut e: u64) -> u64 {
+    let mut r = 1;
+    while e > 0 {
+        if e & 1 == 1 {
+            r = goldilocks_mul(r, x);
+        }
+        x = goldilocks_mul(x, x);
+        e >>= 1;
+    }
+    r
+}
+
+/// Computes the multiplicative inverse modulo `GOLDILOCKS_MOD`.
+pub fn goldilocks_inv(x: u64) -> u64 {
+    assert!(!x.is_multiple_of(GOLDILOCKS_MOD), "Division by zero.");
+    goldilocks_pow(x, GOLDILOCKS_MOD - 2)
+}
+
+/// Field elements are interpreted as signed numbers: Values above half the
+/// modulus represent the negative numbers, i.e. `p - x` is `-x`.
+/// @returns the signed representative of the number, in the range (-p/2, p/2].
+pub fn to_signed(x: &AbstractNumberType) -> AbstractNumberType {
+    let x = to_field_element(x);
+    if x > (GOLDILOCKS_MOD / 2).into() {
+        x - GOLDILOCKS_MOD
+    } else {
+        x
+    }
+}
+
+/// Formats a number as (signed) decimal, see `to_signed`.
 pub fn format_number(x: &AbstractNumberType) -> String {
-    if *x > (GOLDILOCKS_MOD / 2).into() {
-        format!("{}", GOLDILOCKS_MOD - x)
+    format!("{}", to_signed(x))
+}
+
+/// Parses a decimal or hexadecimal (prefixed by `0x`) number,
+/// optionally preceded by a minus sign. Underscores are ignored.
+pub fn parse_number(input: &str) -> Option<AbstractNumberType> {
+    let input = input.replace('_', "");
+    let (sign, digits) = match input.strip_prefix('-') {
+        Some(digits) => (Sign::Minus, digits),
+        None => (Sign::Plus, input.as_str()),
+    };
+    let magnitude = match digits.strip_prefix("0x") {
+        Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+        None => BigUint::parse_bytes(digits.as_bytes(), 10),
+    }?;
+    Some(AbstractNumberType::from_biguint(sign, magnitude))
+}
+
+/// Encodes a number as a field element in 8 little-endian bytes.
+pub fn to_le_bytes(x: &AbstractNumberType) -> [u8; 8] {
+    abstract_to_degree(&to_field_element(x)).to_le_bytes()
+}
+
+/// Decodes a number from 8 little-endian bytes.
+pub fn from_le_bytes(bytes: &[u8]) -> AbstractNumberType {
+    u64::from_le_bytes(bytes.try_into().expect("Expected 8 bytes.")).into()
+}
+
+/// @returns the number reduced into the range [0, GOLDILOCKS_MOD).
+pub fn to_field_element(x: &AbstractNumberType) -> AbstractNumberType {
+    let x = x % GOLDILOCKS_MOD;
+    if x.sign() == Sign::Minus {
+        x + GOLDILOCKS_MOD
     } else {
-        format!("{x}")
+        x
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn goldilocks_arith() {
+        let p = GOLDILOCKS_MOD as u128;
+        for (a, b) in [
+            (0, 0),
+            (1, GOLDILOCKS_MOD - 1),
+            (GOLDILOCKS_MOD - 1, GOLDILOCKS_MOD - 1),
+            (0xffffffff, 0x100000000),
+            (0x123456789abcdef0, 0xfedcba9876543210 % GOLDILOCKS_MOD),
+        ] {
+            assert_eq!(goldilocks_mul(a, b) as u128, (a as u128 * b as u128) % p);
+        }
+        assert_eq!(goldilocks_reduce(u128::MAX) as u128, u128::MAX % p);
+        assert_eq!(goldilocks_pow(7, 0), 1);
+        assert_eq!(goldilocks_pow(7, 2), 49);
+        let inverse_of_four = 13835058052060938241u64;
+        assert_eq!(goldilocks_inv(4), inverse_of_four);
+        assert_eq!(goldilocks_mul(4, inverse_of_four), 1);
+    }
+
+    #[test]
+    pub fn roots_of_unity() {
+        assert_eq!(goldilocks_root_of_unity(0), 1);
+        assert_eq!(goldilocks_root_of_unity(1), GOLDILOCKS_MOD - 1);
+        assert_eq!(goldilocks_root_of_unity(32), 1753635133440165772);
+        for log_n in [2, 10, GOLDILOCKS_TWO_ADICITY] {
+            let root = goldilocks_root_of_unity(log_n);
+            assert_eq!(goldilocks_pow(root, 1 << log_n), 1);
+            assert_ne!(goldilocks_pow(root, 1 << (log_n - 1)), 1);
+        }
+        // The generator is not a quadratic residue.
+        assert_eq!(
+            goldilocks_pow(GOLDILOCKS_GENERATOR, (GOLDILOCKS_MOD - 1) / 2),
+            GOLDILOCKS_MOD - 1
+        );
+    }
+
+    #[test]
+    pub fn checked_conversions() {
+        assert_eq!(to_u64(&u64::MAX.into()), Ok(u64::MAX));
+        assert!(to_u64(&(AbstractNumberType::from(u64::MAX) + 1)).is_err());
+        assert!(to_u64(&(-1).into()).is_err());
+        assert_eq!(to_u32(&7.into()), Ok(7));
+        assert!(to_u32(&(1u64 << 32).into()).is_err());
+        assert_eq!(to_i64(&(GOLDILOCKS_MOD - 3).into()), Ok(-3));
+        assert_eq!(to_i64(&3.into()), Ok(3));
+        assert!(to_i64(&(-3).into()).is_err());
+        assert!(to_i64(&GOLDILOCKS_MOD.into()).is_err());
+    }
+
+    #[test]
+    pub fn signed() {
+        let half = GOLDILOCKS_MOD / 2;
+        assert_eq!(to_signed(&half.into()), half.into());
+        assert_eq!(
+            to_signed(&(half + 1).into()),
+            AbstractNumberType::from(half + 1) - GOLDILOCKS_MOD
+        );
+        assert_eq!(to_signed(&(-5).into()), (-5).into());
+        assert_eq!(to_signed(&GOLDILOCKS_MOD.into()), 0.into());
+        assert_eq!(format_number(&(-5).into()), "-5");
+        assert_eq!(format_number(&(GOLDILOCKS_MOD - 5).into()), "-5");
+    }
+
+    #[test]
+    pub fn serialization() {
+        let minus_one = AbstractNumberType::from(-1);
+        assert_eq!(format_number(&(GOLDILOCKS_MOD - 1).into()), "-1");
+        assert_eq!(format_number(&7.into()), "7");
+        assert_eq!(parse_number("0x1_00"), Some(256.into()));
+        assert_eq!(parse_number("1_000"), Some(1000.into()));
+        assert_eq!(parse_number("0xg"), None);
+        assert_eq!(parse_number("-0x10"), Some((-16).into()));
+        assert_eq!(parse_number("-7"), Some((-7).into()));
+        assert_eq!(parse_number("--7"), None);
+        assert_eq!(to_le_bytes(&minus_one), (GOLDILOCKS_MOD - 1).to_le_bytes());
+        assert_eq!(from_le_bytes(&to_le_bytes(&1234.into())), 1234.into());
     }
 }
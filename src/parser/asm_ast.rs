@@ -16,6 +16,21 @@ pub enum ASMStatement {
     Assignment(usize, Vec<String>, Option<String>, Box<Expression>),
     Instruction(usize, String, Vec<Expression>),
     Label(usize, String),
+    /// A labeled block of code that can only be entered by jumping to
+    /// its label, only containing assignments, instructions and labels.
+    Function(usize, String, Vec<ASMStatement>),
+    /// Code that is inserted wherever the macro is used like an instruction,
+    /// with the parameters replaced by the arguments.
+    MacroDefinition(usize, String, Vec<String>, Vec<ASMStatement>),
+    /// Code that is repeated for each value of the variable
+    /// from the first (inclusive) to the second (exclusive) constant.
+    Loop(
+        usize,
+        String,
+        Box<Expression>,
+        Box<Expression>,
+        Vec<ASMStatement>,
+    ),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
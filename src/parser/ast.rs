@@ -48,6 +48,8 @@ pub enum Expression {
     UnaryOperation(UnaryOperator, Box<Expression>),
     FunctionCall(String, Vec<Expression>),
     FreeInput(Box<Expression>),
+    /// Condition, value if the condition is nonzero, value otherwise.
+    IfElse(Box<Expression>, Box<Expression>, Box<Expression>),
 }
 
 #[derive(Debug, PartialEq, Eq, Default, Clone)]
@@ -68,6 +70,7 @@ pub struct PolynomialReference {
 pub enum UnaryOperator {
     Plus,
     Minus,
+    LogicalNot,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -79,13 +82,23 @@ pub enum BinaryOperator {
     Mod,
     Pow,
     BinaryAnd,
+    BinaryXor,
     BinaryOr,
     ShiftLeft,
     ShiftRight,
+    LogicalOr,
+    LogicalAnd,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
 }
 
 /// The definition of a function (excluding its name):
-/// Either a param-value mapping or an array of values.
+/// Either a param-value mapping, an array of values, a query,
+/// a file to load the values from or a recurrence.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum FunctionDefinition {
     /// Parameter-value-mapping.
@@ -94,4 +107,11 @@ pub enum FunctionDefinition {
     Array(Vec<Expression>),
     /// Prover query.
     Query(Vec<String>, Expression),
+    /// Values loaded from a file (path relative to the current file).
+    File(String),
+    /// Parameter-value-mapping that can reference earlier rows of the
+    /// function itself, with explicit values for the first rows.
+    Recurrence(Vec<String>, Expression, Vec<Expression>),
+    /// Pseudo-random values generated deterministically from a seed.
+    Random(Expression),
 }
@@ -100,6 +100,14 @@ impl Display for FunctionDefinition {
             FunctionDefinition::Query(params, value) => {
                 write!(f, "({}) query {value}", params.join(", "),)
             }
+            FunctionDefinition::File(path) => write!(f, " from {}", quote(path)),
+            FunctionDefinition::Random(seed) => write!(f, " random({seed})"),
+            FunctionDefinition::Recurrence(params, body, initial) => write!(
+                f,
+                "({}) {{ {body} }} with [{}]",
+                params.join(", "),
+                format_expressions(initial)
+            ),
         }
     }
 }
@@ -139,6 +147,9 @@ impl Display for Expression {
             Expression::UnaryOperation(op, exp) => write!(f, "{op}{exp}"),
             Expression::FunctionCall(fun, args) => write!(f, "{fun}({})", format_expressions(args)),
             Expression::FreeInput(input) => write!(f, "${{ {input} }}"),
+            Expression::IfElse(condition, if_true, if_false) => {
+                write!(f, "if {condition} {{ {if_true} }} else {{ {if_false} }}")
+            }
         }
     }
 }
@@ -189,9 +200,18 @@ impl Display for BinaryOperator {
                 BinaryOperator::Mod => "%",
                 BinaryOperator::Pow => "**",
                 BinaryOperator::BinaryAnd => "&",
+                BinaryOperator::BinaryXor => "^",
                 BinaryOperator::BinaryOr => "|",
                 BinaryOperator::ShiftLeft => "<<",
                 BinaryOperator::ShiftRight => ">>",
+                BinaryOperator::LogicalOr => "||",
+                BinaryOperator::LogicalAnd => "&&",
+                BinaryOperator::Less => "<",
+                BinaryOperator::LessEqual => "<=",
+                BinaryOperator::Equal => "==",
+                BinaryOperator::NotEqual => "!=",
+                BinaryOperator::GreaterEqual => ">=",
+                BinaryOperator::Greater => ">",
             }
         )
     }
@@ -205,6 +225,7 @@ impl Display for UnaryOperator {
             match self {
                 UnaryOperator::Minus => "-",
                 UnaryOperator::Plus => "+",
+                UnaryOperator::LogicalNot => "!",
             }
         )
     }
@@ -248,6 +269,34 @@ public out = y(%last_row);"#;
         assert_eq!(input.trim(), printed.trim());
     }
 
+    #[test]
+    fn reparse_fixed_from_file() {
+        let input = r#"pol constant BYTE from "byte.csv";"#;
+        let printed = format!("{}", parser::parse(Some("input"), input).unwrap());
+        assert_eq!(input.trim(), printed.trim());
+    }
+
+    #[test]
+    fn reparse_random() {
+        let input = r#"pol constant R random(42);"#;
+        let printed = format!("{}", parser::parse(Some("input"), input).unwrap());
+        assert_eq!(input.trim(), printed.trim());
+    }
+
+    #[test]
+    fn reparse_conditionals() {
+        let input = r#"pol constant F(i) { if ((i < 3) && !(i == 1)) { (i ^ 2) } else { if (i != 5) { 1 } else { 0 } } };"#;
+        let printed = format!("{}", parser::parse(Some("input"), input).unwrap());
+        assert_eq!(input.trim(), printed.trim());
+    }
+
+    #[test]
+    fn reparse_recurrence() {
+        let input = r#"pol constant F(i) { ((3 * F((i - 1))) + 1) } with [1, 4];"#;
+        let printed = format!("{}", parser::parse(Some("input"), input).unwrap());
+        assert_eq!(input.trim(), printed.trim());
+    }
+
     #[test]
     fn reparse_strings_and_tuples() {
         let input = r#"constant %N = ("abc", 3);"#;
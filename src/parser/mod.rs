@@ -1,9 +1,13 @@
+use std::fmt::{self, Display};
+
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use codespan_reporting::files::SimpleFiles;
 use codespan_reporting::term;
 use codespan_reporting::term::termcolor::{ColorChoice, StandardStream};
 use lalrpop_util::*;
 
+use crate::utils;
+
 pub mod asm_ast;
 pub mod ast;
 pub mod display;
@@ -24,6 +28,19 @@ pub struct ParseError<'a> {
 }
 
 impl<'a> ParseError<'a> {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// @returns the (one-based) line the error starts in.
+    pub fn line(&self) -> usize {
+        utils::offset_to_line(self.start, &utils::compute_line_starts(self.contents))
+    }
+
     pub fn output_to_stderr(&self) {
         let config = term::Config::default();
         let mut files = SimpleFiles::new();
@@ -36,6 +53,12 @@ impl<'a> ParseError<'a> {
     }
 }
 
+impl Display for ParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.file_name, self.line(), self.message)
+    }
+}
+
 pub fn parse<'a>(file_name: Option<&str>, input: &'a str) -> Result<ast::PILFile, ParseError<'a>> {
     powdr::PILFileParser::new()
         .parse(input)
@@ -230,5 +253,6 @@ mod test {
     #[test]
     fn parse_example_asm_files() {
         parse_asm_file("tests/simple_sum.asm");
+        parse_asm_file("tests/functions.asm");
     }
 }
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::compiler::{asm_input_index, pil_input_index};
+use crate::number::{parse_number, AbstractNumberType};
+
+/// Where the answers to the queries of witness generation come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum QuerySource {
+    /// The list of inputs given on the command line, in a file or in powdr.toml.
+    #[default]
+    Inputs,
+    /// Asks for each input on stderr and reads the answer from stdin.
+    Stdin,
+    /// Reads the input with index i from the environment variable POWDR_INPUT_<i>.
+    Env,
+}
+
+pub type QueryCallback = Box<dyn FnMut(&str) -> Option<AbstractNumberType>>;
+
+/// Prefix of the environment variables read for `QuerySource::Env`.
+pub const ENV_PREFIX: &str = "POWDR_INPUT_";
+
+/// @returns a callback that answers the queries for free inputs of an
+/// assembly program (if `is_asm` is set) or the queries `"input", <index>`
+/// in PIL from the given source. `inputs` is only used for `QuerySource::Inputs`.
+pub fn callback(
+    source: QuerySource,
+    inputs: Vec<AbstractNumberType>,
+    is_asm: bool,
+) -> QueryCallback {
+    match source {
+        QuerySource::Inputs => Box::new(from_inputs(inputs, is_asm)),
+        QuerySource::Stdin => Box::new(interactive(io::stdin().lock(), io::stderr(), is_asm)),
+        QuerySource::Env => Box::new(from_environment(is_asm)),
+    }
+}
+
+/// Reads inputs separated by commas or newlines from a file.
+pub fn read_inputs(file: &Path) -> Result<Vec<AbstractNumberType>, String> {
+    let contents = fs::read_to_string(file).map_err(|e| format!("Could not read {file:?}: {e}"))?;
+    contents
+        .split([',', '\n'])
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .map(|v| parse_number(v).ok_or(format!("Invalid input in {file:?}: {v}")))
+        .collect()
+}
+
+fn input_index(query: &str, is_asm: bool) -> Option<usize> {
+    if is_asm {
+        asm_input_index(query)
+    } else {
+        pil_input_index(query)
+    }
+}
+
+pub fn from_inputs(
+    inputs: Vec<AbstractNumberType>,
+    is_asm: bool,
+) -> impl FnMut(&str) -> Option<AbstractNumberType> {
+    move |query| inputs.get(input_index(query, is_asm)?).cloned()
+}
+
+/// Writes a prompt for each input to `output` and reads the answer as a line
+/// from `input`, where an empty line or the end of the input leaves the
+/// input unanswered. In PIL, other queries are prompted verbatim.
+/// Each input is only asked for once.
+pub fn interactive(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    is_asm: bool,
+) -> impl FnMut(&str) -> Option<AbstractNumberType> {
+    let mut answers = HashMap::<String, Option<AbstractNumberType>>::new();
+    move |query| {
+        let prompt = match input_index(query, is_asm) {
+            Some(index) => format!("Input {index}"),
+            None if is_asm => return None,
+            None => format!("Query {query}"),
+        };
+        if let Some(answer) = answers.get(&prompt) {
+            return answer.clone();
+        }
+        let answer = loop {
+            write!(output, "{prompt}: ").unwrap();
+            output.flush().unwrap();
+            let mut line = String::new();
+            input.read_line(&mut line).unwrap();
+            let line = line.trim();
+            if line.is_empty() {
+                break None;
+            }
+            match parse_number(line) {
+                Some(value) => break Some(value),
+                None => writeln!(output, "Invalid number: {line}").unwrap(),
+            }
+        };
+        answers.insert(prompt, answer.clone());
+        answer
+    }
+}
+
+/// Answers the input with index i from the environment variable
+/// POWDR_INPUT_<i>, leaving it unanswered if the variable is not set.
+pub fn from_environment(is_asm: bool) -> impl FnMut(&str) -> Option<AbstractNumberType> {
+    move |query| {
+        let name = format!("{ENV_PREFIX}{}", input_index(query, is_asm)?);
+        let value = env::var(&name).ok()?;
+        Some(
+            parse_number(value.trim())
+                .unwrap_or_else(|| panic!("Invalid number in {name}: {value}")),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inputs() {
+        let mut callback = from_inputs(vec![7.into(), 8.into()], false);
+        assert_eq!(callback("\"input\", 1"), Some(8.into()));
+        assert_eq!(callback("\"input\", 2"), None);
+        assert_eq!(callback("\"hint\", 1"), None);
+
+        let mut callback = from_inputs(vec![7.into(), 8.into()], true);
+        assert_eq!(
+            callback("3, 5, 4, \"input\", 1, 5, \"input\", 0"),
+            Some(7.into())
+        );
+        assert_eq!(callback("3, 6, 4, \"input\", 1, 5, \"input\", 0"), None);
+    }
+
+    #[test]
+    fn stdin() {
+        let mut output = vec![];
+        let mut callback = interactive(&b"x\n12\n\n"[..], &mut output, false);
+        assert_eq!(callback("\"input\", 0"), Some(12.into()));
+        // Answers are only asked for once.
+        assert_eq!(callback("\"input\", 0"), Some(12.into()));
+        assert_eq!(callback("\"hint\", 2"), None);
+        assert_eq!(callback("\"input\", 1"), None);
+        drop(callback);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Input 0: Invalid number: x\nInput 0: Query \"hint\", 2: Input 1: "
+        );
+    }
+
+    #[test]
+    fn environment() {
+        env::set_var("POWDR_INPUT_4", "0x10");
+        let mut callback = from_environment(false);
+        assert_eq!(callback("\"input\", 4"), Some(16.into()));
+        assert_eq!(callback("\"input\", 5"), None);
+    }
+
+    #[test]
+    fn inputs_file() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        let file = dir.join("inputs.csv");
+        fs::write(&file, "7, 8\n9\n").unwrap();
+        assert_eq!(read_inputs(&file), Ok(vec![7.into(), 8.into(), 9.into()]));
+        fs::write(&file, "7, x").unwrap();
+        assert!(read_inputs(&file).unwrap_err().contains("Invalid input"));
+    }
+}
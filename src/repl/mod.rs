@@ -0,0 +1,170 @@
+use std::io::{self, BufRead, Write};
+
+use crate::analyzer::{self, Analyzed};
+use crate::constant_evaluator::FixedColumn;
+use crate::constraint_checker::ConstraintChecker;
+use crate::number::{to_signed, AbstractNumberType, DegreeType};
+
+const HELP: &str = "\
+<expression>      evaluate a PIL expression at the current row
+:row <n>          go to row n
+:next / :prev     go to the next / previous row
+:namespace <ns>   resolve unqualified names in namespace ns
+:help             show this help
+:quit             exit";
+
+/// Evaluates PIL expressions on fixed and witness columns at a chosen row.
+pub struct Repl<'a> {
+    analyzed: &'a Analyzed,
+    checker: ConstraintChecker<'a>,
+    degree: DegreeType,
+    namespace: String,
+    row: DegreeType,
+}
+
+impl<'a> Repl<'a> {
+    pub fn new(
+        analyzed: &'a Analyzed,
+        fixed: &'a [(&'a str, FixedColumn)],
+        witness: &'a [(&'a str, Vec<AbstractNumberType>)],
+        degree: DegreeType,
+    ) -> Self {
+        // Start in the namespace of the first witness column.
+        let namespace = witness
+            .first()
+            .and_then(|(name, _)| name.rsplit_once('.'))
+            .map(|(namespace, _)| namespace.to_string())
+            .unwrap_or_else(|| "Global".to_string());
+        Repl {
+            analyzed,
+            checker: ConstraintChecker::from_columns(analyzed, fixed, witness, degree),
+            degree,
+            namespace,
+            row: 0,
+        }
+    }
+
+    pub fn prompt(&self) -> String {
+        format!("{}@{}> ", self.namespace, self.row)
+    }
+
+    /// Executes a command or evaluates an expression.
+    /// @returns the text to print, or None if the REPL should exit.
+    pub fn execute(&mut self, line: &str) -> Option<Result<String, String>> {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        Some(match command {
+            "" => Ok(String::new()),
+            ":quit" | ":q" => return None,
+            ":help" => Ok(HELP.to_string()),
+            ":row" => match argument.parse::<DegreeType>() {
+                Ok(row) if row < self.degree => {
+                    self.row = row;
+                    Ok(String::new())
+                }
+                _ => Err(format!("Expected a row between 0 and {}.", self.degree - 1)),
+            },
+            ":next" => {
+                self.row = (self.row + 1) % self.degree;
+                Ok(String::new())
+            }
+            ":prev" => {
+                self.row = (self.row + self.degree - 1) % self.degree;
+                Ok(String::new())
+            }
+            ":namespace" | ":ns" if !argument.is_empty() => {
+                self.namespace = argument.to_string();
+                Ok(String::new())
+            }
+            _ if command.starts_with(':') => Err(format!("Unknown command {line}, see :help.")),
+            _ => self.evaluate(line),
+        })
+    }
+
+    /// @returns the value of the expression at the current row,
+    /// followed by its signed form if that is negative.
+    fn evaluate(&self, input: &str) -> Result<String, String> {
+        let expr = analyzer::analyze_expression(self.analyzed, &self.namespace, input)?;
+        if !self.checker.expression_references_available(&expr) {
+            return Err(format!(
+                "{expr} references unknown columns or constants or cannot be evaluated row by row."
+            ));
+        }
+        let value = self.checker.evaluate(&expr, self.row);
+        let signed = to_signed(&value);
+        Ok(if signed < 0.into() {
+            format!("{value} ({signed})")
+        } else {
+            format!("{value}")
+        })
+    }
+}
+
+/// Reads commands and expressions from stdin until it is closed or `:quit` is entered.
+pub fn run(repl: &mut Repl) {
+    println!("Type :help for a list of commands.");
+    let stdin = io::stdin();
+    loop {
+        print!("{}", repl.prompt());
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            return;
+        }
+        match repl.execute(&line) {
+            None => return,
+            Some(Ok(output)) if output.is_empty() => {}
+            Some(Ok(output)) => println!("{output}"),
+            Some(Err(error)) => eprintln!("{error}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::constant_evaluator;
+
+    use super::*;
+
+    #[test]
+    fn evaluate() {
+        let analyzed = analyze_string(
+            r#"
+            constant %N = 4;
+            namespace F(%N);
+            pol constant FIRST = [1, 0, 0, 0];
+            pol commit x;
+            pol next = x + 1;
+            (1 - FIRST') * (x' - next) = 0;
+        "#,
+        )
+        .unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        let witness = vec![("F.x", vec![5.into(), 6.into(), 7.into(), 9.into()])];
+        let mut repl = Repl::new(&analyzed, &fixed, &witness, degree);
+        let mut execute = |line: &str| repl.execute(line).unwrap();
+        assert_eq!(execute("x"), Ok("5".to_string()));
+        assert_eq!(execute("next' * %N"), Ok("28".to_string()));
+        assert_eq!(execute(":row 2"), Ok(String::new()));
+        assert_eq!(execute("(1 - FIRST') * (x' - next)"), Ok("1".to_string()));
+        assert_eq!(execute(":next"), Ok(String::new()));
+        assert_eq!(
+            execute("F.x' - x"),
+            Ok("18446744069414584317 (-4)".to_string())
+        );
+        assert_eq!(
+            execute("y + 1"),
+            Err(
+                "(F.y + 1) references unknown columns or constants or cannot be evaluated row by row."
+                    .to_string()
+            )
+        );
+        assert!(execute("x +").is_err());
+        assert!(execute("%M").is_err());
+        assert_eq!(execute("%M + 1"), Err("Constant %M not found.".to_string()));
+        assert!(execute(":row 4").is_err());
+        assert!(repl.execute(":quit").is_none());
+    }
+}
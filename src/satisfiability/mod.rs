@@ -0,0 +1,284 @@
+use std::fmt::Write;
+
+use crate::analyzer::Analyzed;
+use crate::backend::PublicValues;
+use crate::commit_evaluator;
+use crate::constant_evaluator::FixedColumn;
+use crate::constraint_checker;
+use crate::number::{format_number, to_field_element, AbstractNumberType, DegreeType};
+use crate::smt_exporter;
+use crate::witness_io::Witness;
+
+/// Maximal number of differing cells listed in a report.
+const MAX_REPORTED_DIFFERENCES: usize = 20;
+
+/// A witness that differs from the given one but has the same public values
+/// and satisfies the constraints.
+#[derive(Debug, PartialEq)]
+pub struct SecondWitness {
+    /// The number of trials until the witness was found.
+    pub trials: usize,
+    /// The cell that was changed to find the witness: column, row and value.
+    pub changed_cell: (String, DegreeType, AbstractNumberType),
+    /// The cells that differ: column, row, original and new value.
+    pub differences: Vec<(String, DegreeType, AbstractNumberType, AbstractNumberType)>,
+}
+
+impl SecondWitness {
+    pub fn report(&self) -> String {
+        let (column, row, value) = &self.changed_cell;
+        let mut out = format!(
+            "Found a second witness with the same public values in trial {}, \
+             after setting {column} in row {row} to {}.\n\
+             It differs in {} cells:\n",
+            self.trials,
+            format_number(value),
+            self.differences.len()
+        );
+        for (column, row, original, value) in self.differences.iter().take(MAX_REPORTED_DIFFERENCES)
+        {
+            writeln!(
+                out,
+                "  {column} in row {row}: {} instead of {}",
+                format_number(value),
+                format_number(original)
+            )
+            .unwrap();
+        }
+        if self.differences.len() > MAX_REPORTED_DIFFERENCES {
+            writeln!(
+                out,
+                "  ... and {} more cells.",
+                self.differences.len() - MAX_REPORTED_DIFFERENCES
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+/// Searches for a second witness with the same public values as the given
+/// witness, which points to missing constraints. Each trial changes a
+/// pseudo-randomly chosen cell to a different value and runs witness
+/// generation again, where all other cells are derived from the identities
+/// where possible and taken from the given witness otherwise. A result is
+/// only reported if it satisfies all identities checked by
+/// `constraint_checker::check`, so finding nothing does not prove that the
+/// constraints determine the witness.
+/// @returns the first second witness found within the given number of trials.
+pub fn explore(
+    analyzed: &Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    witness: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+    trials: usize,
+    seed: u64,
+) -> Option<SecondWitness> {
+    if witness.is_empty() || degree == 0 {
+        return None;
+    }
+    let publics = PublicValues::compute(analyzed, fixed, witness, degree);
+    let hints = Witness {
+        first_row: 0,
+        columns: witness
+            .iter()
+            .map(|(name, values)| (name.to_string(), values.clone()))
+            .collect(),
+    };
+    let mut random = Random::new(seed);
+
+    let mut result = None;
+    for trial in 1..=trials {
+        let (column, values) = &witness[random.below(witness.len() as u64) as usize];
+        let row = random.below(degree);
+        let original = &values[row as usize];
+        let value = to_field_element(&match random.below(4) {
+            0 => original + 1,
+            1 => original - 1,
+            2 => AbstractNumberType::from(random.next() % 16),
+            _ => AbstractNumberType::from(random.next()),
+        });
+        if value == *original {
+            continue;
+        }
+        let overrides = Witness {
+            first_row: row,
+            columns: vec![(column.to_string(), vec![value.clone()])],
+        };
+        let Ok(candidate) = commit_evaluator::silently(|| {
+            commit_evaluator::generate_with_overrides(
+                analyzed,
+                degree,
+                fixed,
+                None::<fn(&str) -> Option<AbstractNumberType>>,
+                &hints,
+                &overrides,
+            )
+        }) else {
+            continue;
+        };
+        let differences = differences(witness, &candidate);
+        if differences.is_empty()
+            || PublicValues::compute(analyzed, fixed, &candidate, degree) != publics
+            || constraint_checker::check(analyzed, fixed, &candidate, degree).is_err()
+        {
+            continue;
+        }
+        result = Some(SecondWitness {
+            trials: trial,
+            changed_cell: (column.to_string(), row, value),
+            differences,
+        });
+        break;
+    }
+    result
+}
+
+/// Exports the constraints like `smt_exporter::export` together with
+/// assertions that the public values are those of the given witness and
+/// that at least one witness cell differs from it. The problem is
+/// satisfiable if and only if there is a second witness (with respect to
+/// the translated identities).
+pub fn export_smt(
+    analyzed: &Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    witness: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+) -> String {
+    let mut out = smt_exporter::export(analyzed, fixed, degree);
+    let publics = PublicValues::compute(analyzed, fixed, witness, degree);
+    for (name, value) in &publics.0 {
+        writeln!(
+            out,
+            "(assert (= |{name}| (as ff{} F)))",
+            to_field_element(value)
+        )
+        .unwrap();
+    }
+    let cells = witness
+        .iter()
+        .flat_map(|(name, values)| {
+            values.iter().enumerate().map(move |(row, value)| {
+                format!(
+                    "(not (= |{name}@{row}| (as ff{} F)))",
+                    to_field_element(value)
+                )
+            })
+        })
+        .collect::<Vec<_>>();
+    writeln!(out, "(assert (or {}))", cells.join(" ")).unwrap();
+    writeln!(out, "(check-sat)\n(get-model)").unwrap();
+    out
+}
+
+fn differences(
+    original: &[(&str, Vec<AbstractNumberType>)],
+    candidate: &[(&str, Vec<AbstractNumberType>)],
+) -> Vec<(String, DegreeType, AbstractNumberType, AbstractNumberType)> {
+    original
+        .iter()
+        .zip(candidate)
+        .flat_map(|((name, original), (_, candidate))| {
+            (0..)
+                .zip(original.iter().zip(candidate))
+                .filter(|(_, (o, c))| o != c)
+                .map(|(row, (o, c))| (name.to_string(), row, o.clone(), c.clone()))
+        })
+        .collect()
+}
+
+/// Xorshift pseudo-random numbers, so that the search is reproducible.
+struct Random(u64);
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        Random(seed.wrapping_mul(6364136223846793005).wrapping_add(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::constant_evaluator;
+
+    use super::*;
+
+    fn explore_source(source: &str) -> Option<SecondWitness> {
+        let analyzed = analyze_string(source).unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        let witness = commit_evaluator::generate(
+            &analyzed,
+            degree,
+            &fixed,
+            Some(|query: &str| crate::compiler::pil_input_query(&[1.into()], query)),
+        );
+        explore(&analyzed, &fixed, &witness, degree, 50, 1)
+    }
+
+    #[test]
+    fn determined_witness() {
+        assert_eq!(
+            explore_source(
+                r#"
+                namespace F(4);
+                pol constant FIRST = [1, 0, 0, 0];
+                pol commit x, y;
+                FIRST * x = 0;
+                (1 - FIRST') * (x' - x - 1) = 0;
+                y = 2 * x;
+                public out = y(3);
+                "#
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn unconstrained_column() {
+        // `free` is only constrained in the first row and does not
+        // influence the public value.
+        let found = explore_source(
+            r#"
+            namespace F(4);
+            pol constant FIRST = [1, 0, 0, 0];
+            pol commit x;
+            col witness free(i) query ("input", 0);
+            FIRST * x = 0;
+            (1 - FIRST') * (x' - x - 1) = 0;
+            FIRST * free = 0;
+            public out = x(3);
+            "#,
+        )
+        .unwrap();
+        assert!(found
+            .differences
+            .iter()
+            .all(|(column, ..)| column == "F.free"));
+        assert!(found.report().starts_with("Found a second witness"));
+    }
+
+    #[test]
+    fn smt_query() {
+        let analyzed =
+            analyze_string("namespace F(2); pol commit x; x * (x - 1) = 0; public out = x(1);")
+                .unwrap();
+        let witness = [("F.x", vec![0.into(), 1.into()])];
+        let smt = export_smt(&analyzed, &[], &witness, 2);
+        assert!(smt.contains("(assert (= |out| (as ff1 F)))"));
+        assert!(smt.ends_with(
+            "(assert (or (not (= |F.x@0| (as ff0 F))) (not (= |F.x@1| (as ff1 F)))))\n\
+             (check-sat)\n(get-model)\n"
+        ));
+    }
+}
@@ -0,0 +1,362 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::analyzer::{
+    evaluator, Analyzed, BinaryOperator, Expression, FunctionValueDefinition, Identity,
+    IdentityKind, PolynomialType, SelectedExpressions, UnaryOperator,
+};
+use crate::constant_evaluator::FixedColumn;
+use crate::number::{
+    abstract_to_degree, is_zero, to_field_element, AbstractNumberType, DegreeType, GOLDILOCKS_MOD,
+};
+
+/// Exports the constraints as SMT-LIB assertions over the Goldilocks field
+/// (using the finite field theory of cvc5), unrolled for all rows.
+/// The given fixed columns are substituted by their values, all other
+/// columns are declared as one variable per row, called `|name@row|`.
+/// Public values are declared as variables of their name and constrained
+/// to the value of their polynomial.
+/// Polynomial identities and plookups into fixed columns are translated,
+/// other identities are only listed as comments.
+pub fn export(analyzed: &Analyzed, fixed: &[(&str, FixedColumn)], degree: DegreeType) -> String {
+    Exporter {
+        analyzed,
+        fixed: fixed.iter().map(|(name, c)| (*name, c)).collect(),
+        degree,
+    }
+    .export()
+}
+
+struct Exporter<'a> {
+    analyzed: &'a Analyzed,
+    fixed: HashMap<&'a str, &'a FixedColumn>,
+    degree: DegreeType,
+}
+
+/// A term of the translation, constant sub-expressions are evaluated directly.
+enum Term {
+    Value(AbstractNumberType),
+    Smt(String),
+}
+
+impl Term {
+    fn into_smt(self) -> String {
+        match self {
+            Term::Value(v) => format_field_element(&v),
+            Term::Smt(s) => s,
+        }
+    }
+}
+
+impl<'a> Exporter<'a> {
+    fn export(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "(set-logic QF_FF)").unwrap();
+        writeln!(out, "(define-sort F () (_ FiniteField {GOLDILOCKS_MOD}))").unwrap();
+        for (poly, _) in self
+            .analyzed
+            .committed_polys_in_source_order()
+            .into_iter()
+            .chain(
+                self.analyzed
+                    .constant_polys_in_source_order()
+                    .into_iter()
+                    .filter(|(poly, _)| !self.fixed.contains_key(poly.absolute_name.as_str())),
+            )
+        {
+            assert!(poly.length.is_none(), "Arrays are not supported.");
+            for row in 0..self.degree {
+                writeln!(
+                    out,
+                    "(declare-const {} F)",
+                    variable(&poly.absolute_name, row)
+                )
+                .unwrap();
+            }
+        }
+        for public in self.analyzed.public_declarations_in_id_order() {
+            assert!(
+                public.polynomial.index.is_none(),
+                "Arrays are not supported."
+            );
+            writeln!(out, "(declare-const |{}| F)", public.name).unwrap();
+            let value = self
+                .term(
+                    &Expression::PolynomialReference(public.polynomial.clone()),
+                    public.index,
+                )
+                .into_smt();
+            writeln!(out, "(assert (= |{}| {value}))", public.name).unwrap();
+        }
+        for identity in &self.analyzed.identities {
+            writeln!(
+                out,
+                "; {identity} (from {}:{})",
+                identity.source.file, identity.source.line
+            )
+            .unwrap();
+            match identity.kind {
+                IdentityKind::Polynomial => {
+                    let expr = identity.left.selector.as_ref().unwrap();
+                    for row in 0..self.degree {
+                        writeln!(out, "(assert {})", equals_zero(self.term(expr, row))).unwrap();
+                    }
+                }
+                IdentityKind::Plookup if self.is_fixed(&identity.right) => {
+                    let table = self.lookup_table(&identity.right);
+                    for row in 0..self.degree {
+                        writeln!(out, "(assert {})", self.lookup(identity, &table, row)).unwrap();
+                    }
+                }
+                _ => writeln!(out, "; not translated").unwrap(),
+            }
+        }
+        out
+    }
+
+    /// @returns true if the selected expressions only reference fixed columns.
+    fn is_fixed(&self, selected: &SelectedExpressions) -> bool {
+        fn is_fixed(exporter: &Exporter, expr: &Expression) -> bool {
+            match expr {
+                Expression::Constant(_) | Expression::Number(_) => true,
+                Expression::PolynomialReference(poly) => match exporter.intermediate(&poly.name) {
+                    Some(definition) => is_fixed(exporter, definition),
+                    None => exporter.fixed.contains_key(poly.name.as_str()),
+                },
+                Expression::BinaryOperation(left, _, right) => {
+                    is_fixed(exporter, left) && is_fixed(exporter, right)
+                }
+                Expression::UnaryOperation(_, e) => is_fixed(exporter, e),
+                Expression::IfElse(c, t, f) => [c, t, f].into_iter().all(|e| is_fixed(exporter, e)),
+                _ => false,
+            }
+        }
+        selected
+            .selector
+            .iter()
+            .chain(selected.expressions.iter())
+            .all(|e| is_fixed(self, e))
+    }
+
+    /// @returns the distinct tuples of values of the selected rows of fixed columns.
+    fn lookup_table(&self, selected: &SelectedExpressions) -> BTreeSet<Vec<AbstractNumberType>> {
+        let value = |e, row| match self.term(e, row) {
+            Term::Value(v) => to_field_element(&v),
+            Term::Smt(_) => unreachable!(),
+        };
+        (0..self.degree)
+            .filter(|row| {
+                selected
+                    .selector
+                    .as_ref()
+                    .map(|s| !is_zero(&value(s, *row)))
+                    .unwrap_or(true)
+            })
+            .map(|row| selected.expressions.iter().map(|e| value(e, row)).collect())
+            .collect()
+    }
+
+    /// Translates the plookup in one row to a disjunction over the rows of the table.
+    fn lookup(
+        &self,
+        identity: &Identity,
+        table: &BTreeSet<Vec<AbstractNumberType>>,
+        row: DegreeType,
+    ) -> String {
+        let left = identity
+            .left
+            .expressions
+            .iter()
+            .map(|e| self.term(e, row).into_smt())
+            .collect::<Vec<_>>();
+        let contained = table
+            .iter()
+            .map(|values| {
+                let equalities = left
+                    .iter()
+                    .zip(values)
+                    .map(|(l, v)| format!("(= {l} {})", format_field_element(v)))
+                    .join(" ");
+                format!("(and {equalities})")
+            })
+            .join(" ");
+        let contained = format!("(or {contained})");
+        match &identity.left.selector {
+            Some(selector) => format!("(or {} {contained})", equals_zero(self.term(selector, row))),
+            None => contained,
+        }
+    }
+
+    fn term(&self, expr: &Expression, row: DegreeType) -> Term {
+        match expr {
+            Expression::Constant(name) => Term::Value(self.analyzed.constants[name].clone()),
+            Expression::Number(n) => Term::Value(n.clone()),
+            Expression::PolynomialReference(poly) => {
+                assert!(poly.index.is_none(), "Arrays are not supported.");
+                let row = if poly.next {
+                    (row + 1) % self.degree
+                } else {
+                    row
+                };
+                if let Some(definition) = self.intermediate(&poly.name) {
+                    self.term(definition, row)
+                } else if let Some(column) = self.fixed.get(poly.name.as_str()) {
+                    Term::Value(column.get(row))
+                } else {
+                    Term::Smt(variable(&poly.name, row))
+                }
+            }
+            Expression::PublicReference(name) => Term::Smt(format!("|{name}|")),
+            Expression::BinaryOperation(left, op, right) => {
+                match (self.term(left, row), op, self.term(right, row)) {
+                    (Term::Value(l), op, Term::Value(r)) => {
+                        Term::Value(evaluator::evaluate_binary_operation(l, op, r))
+                    }
+                    (t, BinaryOperator::Add | BinaryOperator::Sub, Term::Value(v))
+                    | (Term::Value(v), BinaryOperator::Add, t)
+                    | (t, BinaryOperator::Mul, Term::Value(v))
+                    | (Term::Value(v), BinaryOperator::Mul, t)
+                        if is_zero(&to_field_element(&v)) =>
+                    {
+                        if *op == BinaryOperator::Mul {
+                            Term::Value(0.into())
+                        } else {
+                            t
+                        }
+                    }
+                    (l, BinaryOperator::Add, r) => {
+                        Term::Smt(format!("(ff.add {} {})", l.into_smt(), r.into_smt()))
+                    }
+                    (l, BinaryOperator::Sub, r) => Term::Smt(format!(
+                        "(ff.add {} (ff.neg {}))",
+                        l.into_smt(),
+                        r.into_smt()
+                    )),
+                    (l, BinaryOperator::Mul, r) => {
+                        Term::Smt(format!("(ff.mul {} {})", l.into_smt(), r.into_smt()))
+                    }
+                    (l, BinaryOperator::Pow, Term::Value(exp)) => {
+                        let l = l.into_smt();
+                        Term::Smt(match abstract_to_degree(&exp) {
+                            0 => format_field_element(&1.into()),
+                            1 => l,
+                            exp => format!("(ff.mul {})", vec![l; exp as usize].join(" ")),
+                        })
+                    }
+                    _ => panic!("Operation not supported in SMT export: {expr}"),
+                }
+            }
+            Expression::UnaryOperation(op, e) => match (op, self.term(e, row)) {
+                (op, Term::Value(v)) => Term::Value(evaluator::evaluate_unary_operation(op, v)),
+                (UnaryOperator::Plus, t) => t,
+                (UnaryOperator::Minus, t) => Term::Smt(format!("(ff.neg {})", t.into_smt())),
+                _ => panic!("Operation not supported in SMT export: {expr}"),
+            },
+            Expression::IfElse(condition, if_true, if_false) => match self.term(condition, row) {
+                Term::Value(c) if is_zero(&to_field_element(&c)) => self.term(if_false, row),
+                Term::Value(_) => self.term(if_true, row),
+                Term::Smt(_) => panic!("Conditions have to be constant in SMT export: {expr}"),
+            },
+            _ => panic!("Expression not supported in SMT export: {expr}"),
+        }
+    }
+
+    /// @returns the definition of the intermediate polynomial of the given name.
+    fn intermediate(&self, name: &str) -> Option<&'a Expression> {
+        match self.analyzed.definitions.get(name) {
+            Some((poly, Some(FunctionValueDefinition::Mapping(definition))))
+                if poly.poly_type == PolynomialType::Intermediate =>
+            {
+                Some(definition)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn variable(name: &str, row: DegreeType) -> String {
+    format!("|{name}@{row}|")
+}
+
+fn equals_zero(term: Term) -> String {
+    format!(
+        "(= {} {})",
+        term.into_smt(),
+        format_field_element(&0.into())
+    )
+}
+
+fn format_field_element(v: &AbstractNumberType) -> String {
+    format!("(as ff{} F)", to_field_element(v))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::constant_evaluator;
+
+    use super::*;
+
+    fn export_string(src: &str) -> String {
+        let analyzed = analyze_string(src).unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        export(&analyzed, &fixed, degree)
+    }
+
+    #[test]
+    fn polynomial_identities() {
+        let smt = export_string(
+            r#"
+            namespace F(2);
+            pol constant FIRST = [1, 0];
+            pol commit x, y;
+            pol sq = x * x;
+            FIRST * (x - 3) = 0;
+            y = sq + x**2 - 1;
+            public out = y(1);
+        "#,
+        );
+        let lines = smt.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[..6],
+            [
+                "(set-logic QF_FF)",
+                "(define-sort F () (_ FiniteField 18446744069414584321))",
+                "(declare-const |F.x@0| F)",
+                "(declare-const |F.x@1| F)",
+                "(declare-const |F.y@0| F)",
+                "(declare-const |F.y@1| F)",
+            ]
+        );
+        assert!(lines.contains(&"(assert (= |out| |F.y@1|))"));
+        assert!(lines.contains(
+            &"(assert (= (ff.mul (as ff1 F) (ff.add |F.x@0| (ff.neg (as ff3 F)))) (as ff0 F)))"
+        ));
+        assert!(lines.contains(&"(assert (= (as ff0 F) (as ff0 F)))"));
+        assert!(lines.contains(&"(assert (= (ff.add |F.y@1| (ff.neg (ff.add (ff.add (ff.mul |F.x@1| |F.x@1|) (ff.mul |F.x@1| |F.x@1|)) (ff.neg (as ff1 F))))) (as ff0 F)))"));
+    }
+
+    #[test]
+    fn range_lookups() {
+        let smt = export_string(
+            r#"
+            namespace F(4);
+            pol constant BIT(i) { i % 2 };
+            pol constant SEL = [0, 1, 1, 1];
+            pol commit x, y;
+            SEL { x } in { BIT };
+            x is y;
+        "#,
+        );
+        let lines = smt.lines().collect::<Vec<_>>();
+        assert!(lines.contains(
+            &"(assert (or (= (as ff0 F) (as ff0 F)) (or (and (= |F.x@0| (as ff0 F))) (and (= |F.x@0| (as ff1 F))))))"
+        ));
+        assert!(lines.contains(
+            &"(assert (or (= (as ff1 F) (as ff0 F)) (or (and (= |F.x@3| (as ff0 F))) (and (= |F.x@3| (as ff1 F))))))"
+        ));
+        assert_eq!(lines.last(), Some(&"; not translated"));
+    }
+}
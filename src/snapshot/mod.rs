@@ -0,0 +1,135 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use crate::compiler;
+use crate::number::{format_number, AbstractNumberType, DegreeType};
+use crate::witness_io::Witness;
+
+/// Maximal number of differences listed when a snapshot does not match.
+const MAX_REPORTED_DIFFERENCES: usize = 20;
+
+/// Generates the witness of a .pil or .asm file at the given (small) degree
+/// and compares the selected columns (all if `None`) with a csv fixture in
+/// the format of `Witness::to_csv`. The inputs are the free inputs of an
+/// assembly program or answer the queries `"input", <index>` in PIL.
+/// If the environment variable `UPDATE_SNAPSHOTS` is set, the fixture
+/// is written instead.
+/// @returns a description of the differences if the columns do not match.
+pub fn check(
+    file: &Path,
+    inputs: &[AbstractNumberType],
+    degree: DegreeType,
+    columns: Option<&[&str]>,
+    fixture: &Path,
+) -> Result<(), String> {
+    let witness = compiler::witness_at_degree(file, &[], inputs, degree)?;
+    let witness = match columns {
+        Some(columns) => {
+            let names = columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            witness.select(Some(&names), None)?
+        }
+        None => witness,
+    };
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        if let Some(dir) = fixture.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Could not create {dir:?}: {e}"))?;
+        }
+        return fs::write(fixture, witness.to_csv())
+            .map_err(|e| format!("Could not write {fixture:?}: {e}"));
+    }
+    let expected = fs::read_to_string(fixture).map_err(|_| {
+        format!("Snapshot {fixture:?} does not exist, run with UPDATE_SNAPSHOTS=1 to create it.")
+    })?;
+    let expected =
+        Witness::from_csv(&expected).map_err(|e| format!("Invalid snapshot {fixture:?}: {e}"))?;
+    let differences = differences(&expected, &witness);
+    if differences.is_empty() {
+        return Ok(());
+    }
+    let mut report = differences
+        .iter()
+        .take(MAX_REPORTED_DIFFERENCES)
+        .map(|d| format!("  {d}"))
+        .collect::<Vec<_>>();
+    if differences.len() > MAX_REPORTED_DIFFERENCES {
+        report.push(format!(
+            "  ... and {} more differences.",
+            differences.len() - MAX_REPORTED_DIFFERENCES
+        ));
+    }
+    Err(format!(
+        "Snapshot {fixture:?} does not match, run with UPDATE_SNAPSHOTS=1 to update it:\n{}",
+        report.join("\n")
+    ))
+}
+
+/// @returns a line for each missing or additional column
+/// and for each cell with a different value.
+fn differences(expected: &Witness, actual: &Witness) -> Vec<String> {
+    let mut result = vec![];
+    for (name, _) in &expected.columns {
+        if actual.columns.iter().all(|(n, _)| n != name) {
+            result.push(format!("Column {name} is missing."));
+        }
+    }
+    for (name, values) in &actual.columns {
+        let Some((_, expected_values)) = expected.columns.iter().find(|(n, _)| n == name) else {
+            result.push(format!("Column {name} is not in the snapshot."));
+            continue;
+        };
+        if expected_values.len() != values.len() {
+            result.push(format!(
+                "Column {name} has {} rows instead of {}.",
+                values.len(),
+                expected_values.len()
+            ));
+        }
+        for (row, (expected, actual)) in expected_values.iter().zip(values).enumerate() {
+            if expected != actual {
+                result.push(format!(
+                    "{name} in row {row}: expected {}, got {}.",
+                    format_number(expected),
+                    format_number(actual)
+                ));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn witness(columns: &[(&str, &[i32])]) -> Witness {
+        Witness {
+            first_row: 0,
+            columns: columns
+                .iter()
+                .map(|(name, values)| {
+                    (
+                        name.to_string(),
+                        values.iter().map(|v| (*v).into()).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn report_differences() {
+        let expected = witness(&[("F.x", &[1, 2, 3]), ("F.y", &[0, 0, 0])]);
+        let actual = witness(&[("F.x", &[1, 5, 3, 4]), ("F.z", &[0])]);
+        assert_eq!(
+            differences(&expected, &actual),
+            vec![
+                "Column F.y is missing.",
+                "Column F.x has 4 rows instead of 3.",
+                "F.x in row 1: expected 2, got 5.",
+                "Column F.z is not in the snapshot.",
+            ]
+        );
+        assert!(differences(&expected, &expected).is_empty());
+    }
+}
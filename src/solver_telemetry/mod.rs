@@ -0,0 +1,231 @@
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use json::{object, JsonValue};
+
+use crate::commit_evaluator::RowMetrics;
+use crate::number::DegreeType;
+
+/// Number of rows listed in each ranking of the summary.
+const TOP_ROWS: usize = 10;
+
+/// Aggregated solver metrics of all rows, which point out the rows
+/// that dominate the time of witness generation.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    pub rows: usize,
+    pub time: Duration,
+    pub mean_iterations: f64,
+    pub max_iterations: usize,
+    pub machine_lookups: usize,
+    pub queries: usize,
+    /// The slowest rows, slowest first.
+    pub slowest_rows: Vec<(DegreeType, Duration)>,
+    /// The rows that needed the most iterations, most first.
+    pub most_iterations: Vec<(DegreeType, usize)>,
+}
+
+pub fn summarize(metrics: &[RowMetrics]) -> Summary {
+    let mut by_time = metrics.iter().collect::<Vec<_>>();
+    by_time.sort_by_key(|m| std::cmp::Reverse(m.time));
+    let mut by_iterations = metrics.iter().collect::<Vec<_>>();
+    by_iterations.sort_by_key(|m| std::cmp::Reverse(m.iterations));
+    let iterations = metrics.iter().map(|m| m.iterations).sum::<usize>();
+    Summary {
+        rows: metrics.len(),
+        time: metrics.iter().map(|m| m.time).sum(),
+        mean_iterations: if metrics.is_empty() {
+            0.0
+        } else {
+            iterations as f64 / metrics.len() as f64
+        },
+        max_iterations: metrics
+            .iter()
+            .map(|m| m.iterations)
+            .max()
+            .unwrap_or_default(),
+        machine_lookups: metrics.iter().map(|m| m.machine_lookups).sum(),
+        queries: metrics.iter().map(|m| m.queries).sum(),
+        slowest_rows: by_time
+            .iter()
+            .take(TOP_ROWS)
+            .map(|m| (m.row, m.time))
+            .collect(),
+        most_iterations: by_iterations
+            .iter()
+            .take(TOP_ROWS)
+            .map(|m| (m.row, m.iterations))
+            .collect(),
+    }
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} rows in {:.3} ms, {:.2} iterations per row (at most {}), {} machine lookups, {} queries.",
+            self.rows,
+            millis(self.time),
+            self.mean_iterations,
+            self.max_iterations,
+            self.machine_lookups,
+            self.queries
+        )?;
+        let slowest = self
+            .slowest_rows
+            .iter()
+            .map(|(row, time)| format!("{row} ({:.3} ms)", millis(*time)));
+        writeln!(
+            f,
+            "Slowest rows: {}",
+            slowest.collect::<Vec<_>>().join(", ")
+        )?;
+        let most = self
+            .most_iterations
+            .iter()
+            .map(|(row, iterations)| format!("{row} ({iterations})"));
+        write!(
+            f,
+            "Rows with the most iterations: {}",
+            most.collect::<Vec<_>>().join(", ")
+        )
+    }
+}
+
+/// @returns the metrics of each row and the summary as json.
+pub fn to_json(metrics: &[RowMetrics]) -> JsonValue {
+    let summary = summarize(metrics);
+    object! {
+        summary: object! {
+            rows: summary.rows,
+            timeMs: millis(summary.time),
+            meanIterations: summary.mean_iterations,
+            maxIterations: summary.max_iterations,
+            machineLookups: summary.machine_lookups,
+            queries: summary.queries,
+            slowestRows: summary.slowest_rows.iter().map(|(row, _)| *row).collect::<Vec<_>>(),
+            mostIterations: summary.most_iterations.iter().map(|(row, _)| *row).collect::<Vec<_>>(),
+        },
+        rows: metrics.iter().map(|m| object! {
+            row: m.row,
+            iterations: m.iterations,
+            identitiesEvaluated: m.identities_evaluated,
+            machineLookups: m.machine_lookups,
+            queries: m.queries,
+            timeMs: millis(m.time),
+        }).collect::<Vec<_>>(),
+    }
+}
+
+/// @returns the metrics with one line per row.
+pub fn to_csv(metrics: &[RowMetrics]) -> String {
+    std::iter::once(
+        "row,iterations,identities_evaluated,machine_lookups,queries,time_ms\n".to_string(),
+    )
+    .chain(metrics.iter().map(|m| {
+        format!(
+            "{},{},{},{},{},{:.6}\n",
+            m.row,
+            m.iterations,
+            m.identities_evaluated,
+            m.machine_lookups,
+            m.queries,
+            millis(m.time)
+        )
+    }))
+    .collect()
+}
+
+/// Writes the metrics as csv if the file name ends in `.csv`, otherwise as json.
+pub fn write(metrics: &[RowMetrics], file: &Path) -> Result<(), String> {
+    let contents = if file.extension() == Some("csv".as_ref()) {
+        to_csv(metrics)
+    } else {
+        to_json(metrics).pretty(2)
+    };
+    fs::write(file, contents).map_err(|e| format!("Could not write {file:?}: {e}"))
+}
+
+fn millis(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn metrics() -> Vec<RowMetrics> {
+        [(2, 3, 1), (5, 10, 4), (2, 3, 2)]
+            .into_iter()
+            .enumerate()
+            .map(|(row, (iterations, lookups, time))| RowMetrics {
+                row: row as DegreeType,
+                iterations,
+                identities_evaluated: iterations * 4,
+                machine_lookups: lookups,
+                queries: 1,
+                time: Duration::from_millis(time),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn summary() {
+        let summary = summarize(&metrics());
+        assert_eq!(summary.rows, 3);
+        assert_eq!(summary.time, Duration::from_millis(7));
+        assert_eq!(summary.mean_iterations, 3.0);
+        assert_eq!(summary.max_iterations, 5);
+        assert_eq!(summary.machine_lookups, 16);
+        assert_eq!(summary.slowest_rows[0], (1, Duration::from_millis(4)));
+        assert_eq!(summary.slowest_rows[1], (2, Duration::from_millis(2)));
+        assert_eq!(summary.most_iterations[0], (1, 5));
+        assert_eq!(
+            summary.to_string(),
+            "3 rows in 7.000 ms, 3.00 iterations per row (at most 5), 16 machine lookups, 3 queries.\n\
+             Slowest rows: 1 (4.000 ms), 2 (2.000 ms), 0 (1.000 ms)\n\
+             Rows with the most iterations: 1 (5), 0 (2), 2 (2)"
+        );
+    }
+
+    #[test]
+    fn export() {
+        let metrics = metrics();
+        assert_eq!(
+            to_csv(&metrics[..2]),
+            "row,iterations,identities_evaluated,machine_lookups,queries,time_ms\n\
+             0,2,8,3,1,1.000000\n\
+             1,5,20,10,1,4.000000\n"
+        );
+        let json = to_json(&metrics);
+        assert_eq!(json["summary"]["slowestRows"], json::array![1, 2, 0]);
+        assert_eq!(json["rows"][1]["identitiesEvaluated"], 20);
+        assert_eq!(json["rows"].len(), 3);
+    }
+
+    #[test]
+    fn generated_metrics() {
+        let analyzed = crate::analyzer::analyze_string(
+            "namespace F(4); pol constant FIRST = [1, 0, 0, 0]; pol commit x;\n\
+             FIRST * x = 0; (1 - FIRST') * (x' - x - 1) = 0;",
+        )
+        .unwrap();
+        let (fixed, degree) = crate::constant_evaluator::generate(&analyzed);
+        let (_, metrics) = crate::commit_evaluator::generate_with_telemetry(
+            &analyzed,
+            degree,
+            &fixed,
+            crate::compiler::no_callback(),
+            None,
+        );
+        assert_eq!(
+            metrics.iter().map(|m| m.row).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        assert!(metrics
+            .iter()
+            .all(|m| m.iterations >= 1 && m.identities_evaluated == 2 * m.iterations));
+    }
+}
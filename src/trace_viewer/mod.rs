@@ -0,0 +1,360 @@
+use std::io::{self, Write};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+
+use crate::analyzer::Identity;
+use crate::constraint_checker::{ColumnValues, ConstraintChecker};
+use crate::number::{format_number, DegreeType};
+
+const HELP: &str =
+    "q: quit, arrows: move, /: filter columns, c: next change, i: highlight identity, n: next violation";
+const MAX_COLUMN_WIDTH: usize = 20;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Mode {
+    Normal,
+    /// Typing the filter for the column names.
+    Filter,
+}
+
+/// State of the terminal UI to browse the fixed and witness columns:
+/// the selected row and column, the columns that match the filter and
+/// the rows in which the highlighted identity is violated.
+pub struct TraceViewer<'a> {
+    checker: ConstraintChecker<'a>,
+    identities: &'a [Identity],
+    columns: Vec<(&'a str, &'a dyn ColumnValues)>,
+    degree: DegreeType,
+    mode: Mode,
+    filter: String,
+    /// Indices into `columns` of the columns that match the filter.
+    visible: Vec<usize>,
+    /// The selected column, as an index into `visible`.
+    column: usize,
+    row: DegreeType,
+    /// The first row and column on the screen.
+    scroll: (DegreeType, usize),
+    /// The index of the highlighted identity and the rows in which it is violated.
+    highlighted: Option<(usize, Vec<DegreeType>)>,
+    message: String,
+}
+
+impl<'a> TraceViewer<'a> {
+    /// Creates a viewer for the columns (witness columns first),
+    /// the identities are evaluated on them when highlighted.
+    pub fn new(
+        checker: ConstraintChecker<'a>,
+        identities: &'a [Identity],
+        columns: Vec<(&'a str, &'a dyn ColumnValues)>,
+        degree: DegreeType,
+    ) -> Self {
+        let visible = (0..columns.len()).collect();
+        TraceViewer {
+            checker,
+            identities,
+            columns,
+            degree,
+            mode: Mode::Normal,
+            filter: String::new(),
+            visible,
+            column: 0,
+            row: 0,
+            scroll: (0, 0),
+            highlighted: None,
+            message: HELP.to_string(),
+        }
+    }
+
+    /// Updates the state according to the pressed key, `page_size` is
+    /// the number of rows on the screen.
+    /// @returns false if the viewer should be closed.
+    pub fn handle_key(&mut self, key: KeyCode, page_size: DegreeType) -> bool {
+        if self.mode == Mode::Filter {
+            match key {
+                KeyCode::Char(c) => self.filter.push(c),
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Enter | KeyCode::Esc => self.mode = Mode::Normal,
+                _ => {}
+            }
+            self.apply_filter();
+            return true;
+        }
+        let last_row = self.degree.saturating_sub(1);
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.row = self.row.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.row = (self.row + 1).min(last_row),
+            KeyCode::PageUp => self.row = self.row.saturating_sub(page_size),
+            KeyCode::PageDown => self.row = (self.row + page_size).min(last_row),
+            KeyCode::Home | KeyCode::Char('g') => self.row = 0,
+            KeyCode::End | KeyCode::Char('G') => self.row = last_row,
+            KeyCode::Left | KeyCode::Char('h') => self.column = self.column.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.column = (self.column + 1).min(self.visible.len().saturating_sub(1))
+            }
+            KeyCode::Char('/') => {
+                self.mode = Mode::Filter;
+                self.message = "Filter columns, enter to finish.".to_string();
+            }
+            KeyCode::Char('c') => self.jump_to_change(),
+            KeyCode::Char('i') => self.highlight_next_identity(),
+            KeyCode::Char('x') => {
+                self.highlighted = None;
+                self.message = HELP.to_string();
+            }
+            KeyCode::Char('n') => self.jump_to_violation(),
+            _ => {}
+        }
+        true
+    }
+
+    fn apply_filter(&mut self) {
+        self.visible = (0..self.columns.len())
+            .filter(|i| self.columns[*i].0.contains(self.filter.as_str()))
+            .collect();
+        self.column = 0;
+        self.scroll.1 = 0;
+    }
+
+    /// Moves to the next row in which the value of the selected column
+    /// differs from the value in the current row.
+    fn jump_to_change(&mut self) {
+        let Some(&index) = self.visible.get(self.column) else {
+            return;
+        };
+        let (name, values) = self.columns[index];
+        let current = values.value(self.row);
+        match (self.row + 1..self.degree).find(|r| values.value(*r) != current) {
+            Some(row) => {
+                self.row = row;
+                self.message = format!("{name} changes in row {row}.");
+            }
+            None => self.message = format!("{name} does not change after row {}.", self.row),
+        }
+    }
+
+    /// Highlights the rows in which the identity after the currently
+    /// highlighted one is violated.
+    fn highlight_next_identity(&mut self) {
+        if self.identities.is_empty() {
+            self.message = "There are no identities.".to_string();
+            return;
+        }
+        let index = self
+            .highlighted
+            .as_ref()
+            .map(|(i, _)| (i + 1) % self.identities.len())
+            .unwrap_or_default();
+        let identity = &self.identities[index];
+        let description = format!(
+            "{identity} (from {}:{})",
+            identity.source.file, identity.source.line
+        );
+        if !self.checker.references_available(identity) {
+            self.highlighted = Some((index, vec![]));
+            self.message = format!("{description} references columns that are not available.");
+            return;
+        }
+        let rows = self.checker.violating_rows(identity);
+        self.message = format!("{description} is violated in {} row(s).", rows.len());
+        self.highlighted = Some((index, rows));
+    }
+
+    /// Moves to the next row (wrapping around) in which the highlighted identity is violated.
+    fn jump_to_violation(&mut self) {
+        let Some((_, rows)) = &self.highlighted else {
+            self.message = "No identity highlighted, press i.".to_string();
+            return;
+        };
+        match rows.iter().find(|r| **r > self.row).or(rows.first()) {
+            Some(row) => self.row = *row,
+            None => self.message = "The identity is not violated.".to_string(),
+        }
+    }
+
+    fn is_violated(&self, row: DegreeType) -> bool {
+        self.highlighted
+            .as_ref()
+            .is_some_and(|(_, rows)| rows.binary_search(&row).is_ok())
+    }
+
+    /// Adjusts the scroll position so that the selected cell is on the screen.
+    fn scroll_to_selection(&mut self, page_size: DegreeType, columns_on_screen: usize) {
+        let (first_row, first_column) = &mut self.scroll;
+        if self.row < *first_row {
+            *first_row = self.row;
+        } else if self.row >= *first_row + page_size {
+            *first_row = self.row + 1 - page_size;
+        }
+        if self.column < *first_column {
+            *first_column = self.column;
+        } else if self.column >= *first_column + columns_on_screen {
+            *first_column = self.column + 1 - columns_on_screen;
+        }
+    }
+
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        // Header line and status line.
+        let page_size = height.saturating_sub(2).max(1) as DegreeType;
+        let row_width = self.degree.to_string().len().max(3);
+        let column_width = MAX_COLUMN_WIDTH + 1;
+        let columns_on_screen = ((width as usize).saturating_sub(row_width) / column_width).max(1);
+        self.scroll_to_selection(page_size, columns_on_screen);
+        let (first_row, first_column) = self.scroll;
+        let columns = self
+            .visible
+            .iter()
+            .enumerate()
+            .skip(first_column)
+            .take(columns_on_screen)
+            .map(|(i, c)| (i == self.column, self.columns[*c]))
+            .collect::<Vec<_>>();
+
+        queue!(
+            out,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0)
+        )?;
+        queue!(
+            out,
+            SetAttribute(Attribute::Bold),
+            Print(format!("{:>row_width$}", "row"))
+        )?;
+        for (selected, (name, _)) in &columns {
+            if *selected {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(
+                out,
+                Print(format!(" {}", fit(name))),
+                SetAttribute(Attribute::NoReverse)
+            )?;
+        }
+        queue!(out, SetAttribute(Attribute::Reset))?;
+        for (line, row) in (first_row..self.degree)
+            .take(page_size as usize)
+            .enumerate()
+        {
+            queue!(out, cursor::MoveTo(0, line as u16 + 1))?;
+            if self.is_violated(row) {
+                queue!(out, SetForegroundColor(Color::Red))?;
+            }
+            if row == self.row {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(out, Print(format!("{row:>row_width$}")))?;
+            for (_, (_, values)) in &columns {
+                queue!(
+                    out,
+                    Print(format!(" {}", fit(&format_number(&values.value(row)))))
+                )?;
+            }
+            queue!(out, SetAttribute(Attribute::Reset), ResetColor)?;
+        }
+        let status = match self.mode {
+            Mode::Normal => self.message.clone(),
+            Mode::Filter => format!("/{}", self.filter),
+        };
+        queue!(
+            out,
+            cursor::MoveTo(0, height.saturating_sub(1)),
+            Print(status.chars().take(width as usize).collect::<String>())
+        )?;
+        out.flush()
+    }
+}
+
+/// Pads or truncates the text to the column width.
+fn fit(text: &str) -> String {
+    if text.chars().count() > MAX_COLUMN_WIDTH {
+        let start = text.chars().count() - MAX_COLUMN_WIDTH + 1;
+        format!("…{}", text.chars().skip(start).collect::<String>())
+    } else {
+        format!("{text:>MAX_COLUMN_WIDTH$}")
+    }
+}
+
+/// Runs the viewer in the terminal until it is closed.
+pub fn run(viewer: &mut TraceViewer) -> io::Result<()> {
+    let mut out = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result = (|| loop {
+        viewer.draw(&mut out)?;
+        if let Event::Key(key) = event::read()? {
+            let page_size = terminal::size()?.1.saturating_sub(2).max(1) as DegreeType;
+            if key.kind == KeyEventKind::Press && !viewer.handle_key(key.code, page_size) {
+                return Ok(());
+            }
+        }
+    })();
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::constant_evaluator;
+    use crate::constraint_checker::column_values;
+    use crate::number::AbstractNumberType;
+
+    use super::*;
+
+    fn press(viewer: &mut TraceViewer, keys: &str) {
+        for c in keys.chars() {
+            assert!(viewer.handle_key(KeyCode::Char(c), 4));
+        }
+    }
+
+    #[test]
+    fn navigate() {
+        let analyzed = analyze_string(
+            r#"
+            namespace F(8);
+            pol constant FIRST = [1, 0, 0, 0, 0, 0, 0, 0];
+            pol commit x, y;
+            (1 - FIRST') * (x' - x) = 0;
+            x * (1 - x) = 0;
+        "#,
+        )
+        .unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        let witness: Vec<(&str, Vec<AbstractNumberType>)> = vec![
+            ("F.x", [0, 0, 0, 1, 1, 0, 0, 0].map(|v| v.into()).to_vec()),
+            ("F.y", vec![7.into(); 8]),
+        ];
+        let checker = ConstraintChecker::from_columns(&analyzed, &fixed, &witness, degree);
+        let columns = column_values(&fixed, &witness);
+        let mut viewer = TraceViewer::new(checker, &analyzed.identities, columns, degree);
+
+        press(&mut viewer, "c");
+        assert_eq!(viewer.row, 3);
+        press(&mut viewer, "c");
+        assert_eq!(viewer.row, 5);
+        press(&mut viewer, "c");
+        assert_eq!(viewer.message, "F.x does not change after row 5.");
+
+        press(&mut viewer, "i");
+        assert_eq!(viewer.highlighted, Some((0, vec![2, 4])));
+        press(&mut viewer, "n");
+        assert_eq!(viewer.row, 2);
+        press(&mut viewer, "nn");
+        assert_eq!(viewer.row, 2);
+        press(&mut viewer, "i");
+        assert_eq!(viewer.highlighted, Some((1, vec![])));
+
+        press(&mut viewer, "/FI");
+        assert_eq!(viewer.visible, vec![2]);
+        assert!(viewer.handle_key(KeyCode::Enter, 4));
+        press(&mut viewer, "gc");
+        assert_eq!(viewer.row, 1);
+        assert!(!viewer.handle_key(KeyCode::Char('q'), 4));
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::analyzer::{Analyzed, PolynomialType};
+use crate::constant_evaluator::FixedColumn;
+use crate::constraint_checker::ConstraintChecker;
+use crate::number::{format_number, AbstractNumberType, DegreeType};
+use crate::witness_io::{self, Witness};
+
+/// Checks that the hints only contain witness columns and rows below the degree.
+pub fn validate(analyzed: &Analyzed, hints: &Witness, degree: DegreeType) -> Result<(), String> {
+    let names = witness_io::column_names(analyzed, PolynomialType::Committed);
+    if let Some((name, _)) = hints
+        .columns
+        .iter()
+        .find(|(name, _)| !names.contains(&name.as_str()))
+    {
+        return Err(format!("Witness column {name} not found."));
+    }
+    if hints.first_row + hints.row_count() > degree {
+        return Err(format!(
+            "The witness hints contain rows beyond the degree {degree}."
+        ));
+    }
+    Ok(())
+}
+
+/// Compares the hints with the witness generated from them.
+/// @returns a line for each hint that was overridden because witness
+/// generation derived a different value, and for each hint that was used
+/// but violates an identity in the generated witness.
+pub fn report(
+    analyzed: &Analyzed,
+    fixed: &[(&str, FixedColumn)],
+    witness: &[(&str, Vec<AbstractNumberType>)],
+    degree: DegreeType,
+    hints: &Witness,
+) -> Vec<String> {
+    let witness_values = witness
+        .iter()
+        .map(|(name, values)| (*name, values))
+        .collect::<HashMap<_, _>>();
+    let hint = |name: &str, row: DegreeType| {
+        let (_, values) = hints.columns.iter().find(|(n, _)| n == name)?;
+        values.get(row.checked_sub(hints.first_row)? as usize)
+    };
+
+    let mut result = vec![];
+    for (name, values) in &hints.columns {
+        for (row, value) in (hints.first_row..).zip(values) {
+            let generated = &witness_values[name.as_str()][row as usize];
+            if generated != value {
+                result.push(format!(
+                    "Hint {name} = {} in row {row} was overridden by {}.",
+                    format_number(value),
+                    format_number(generated)
+                ));
+            }
+        }
+    }
+
+    let checker = ConstraintChecker::from_columns(analyzed, fixed, witness, degree);
+    for identity in &analyzed.identities {
+        let hinted_cells = [&identity.left, &identity.right]
+            .into_iter()
+            .flat_map(|side| side.selector.iter().chain(&side.expressions))
+            .flat_map(|e| checker.referenced_cells(e))
+            .filter(|(name, _)| hints.columns.iter().any(|(n, _)| n == name))
+            .collect::<BTreeSet<_>>();
+        if hinted_cells.is_empty() || !checker.references_available(identity) {
+            continue;
+        }
+        for violating_row in checker.violating_rows(identity) {
+            for (name, next) in &hinted_cells {
+                let row = (violating_row + *next as DegreeType) % degree;
+                match hint(name, row) {
+                    Some(value) if *value == witness_values[name][row as usize] => {
+                        result.push(format!(
+                            "Hint {name} = {} in row {row} contradicts {identity} (from {}:{}).",
+                            format_number(value),
+                            identity.source.file,
+                            identity.source.line
+                        ))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+    use crate::{commit_evaluator, constant_evaluator};
+
+    use super::*;
+
+    const SRC: &str = r#"
+        namespace F(4);
+        pol constant FIRST = [1, 0, 0, 0];
+        pol commit x, y, free;
+        FIRST * x = 0;
+        (1 - FIRST') * (x' - x - 1) = 0;
+        y = x + free;
+        free * (free - 1) = 0;
+    "#;
+
+    fn hints(csv: &str) -> Vec<String> {
+        let analyzed = analyze_string(SRC).unwrap();
+        let (fixed, degree) = constant_evaluator::generate(&analyzed);
+        let hints = Witness::from_csv(csv).unwrap();
+        validate(&analyzed, &hints, degree).unwrap();
+        let witness = commit_evaluator::generate_with_hints(
+            &analyzed,
+            degree,
+            &fixed,
+            None::<fn(&str) -> Option<AbstractNumberType>>,
+            &hints,
+        );
+        report(&analyzed, &fixed, &witness, degree, &hints)
+    }
+
+    #[test]
+    fn hints_fill_underived_cells() {
+        assert!(hints("row,F.free\n0,1\n1,0\n2,1\n3,0\n").is_empty());
+    }
+
+    #[test]
+    fn overridden_and_contradicted() {
+        assert_eq!(
+            hints("row,F.x,F.free\n0,0,0\n1,5,1\n2,2,7\n3,3,0\n"),
+            vec![
+                "Hint F.x = 5 in row 1 was overridden by 1.",
+                "Hint F.free = 7 in row 2 contradicts (F.free * (F.free - 1)) = 0; (from input:8).",
+            ]
+        );
+    }
+
+    #[test]
+    fn non_canonical_hints() {
+        assert!(
+            hints("row,F.x,F.free\n0,-18446744069414584321,0\n1,1,1\n2,2,0\n3,3,1\n").is_empty()
+        );
+    }
+
+    #[test]
+    fn invalid_hints() {
+        let analyzed = analyze_string(SRC).unwrap();
+        let hints = Witness::from_csv("row,F.FIRST\n0,1\n").unwrap();
+        assert_eq!(
+            validate(&analyzed, &hints, 4),
+            Err("Witness column F.FIRST not found.".to_string())
+        );
+        let hints = Witness::from_csv("row,F.x\n3,1\n4,1\n").unwrap();
+        assert!(validate(&analyzed, &hints, 4).is_err());
+    }
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::ops::Range;
+
+use json::{object, JsonValue};
+
+use crate::analyzer::{Analyzed, PolynomialType};
+use crate::column_file::{self, ColumnFile};
+use crate::number::{
+    format_number, from_le_bytes, parse_number, to_field_element, to_le_bytes, AbstractNumberType,
+    DegreeType,
+};
+
+/// The values of (a selection of) the witness columns in a contiguous range of rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Witness {
+    /// The row of the first value of each column.
+    pub first_row: DegreeType,
+    /// The columns by absolute name.
+    pub columns: Vec<(String, Vec<AbstractNumberType>)>,
+}
+
+impl Witness {
+    /// Reads a witness from the binary layout of pilcom (see `to_binary`).
+    pub fn from_binary(analyzed: &Analyzed, contents: &[u8]) -> Result<Self, String> {
+        let names = column_names(analyzed, PolynomialType::Committed);
+        let columns = read_binary_columns(&names, contents)?;
+        Ok(Witness {
+            first_row: 0,
+            columns: columns
+                .into_iter()
+                .map(|(name, values)| (name.to_string(), values))
+                .collect(),
+        })
+    }
+
+    /// Encodes the witness in the binary layout of pilcom and zkevm-proverjs:
+    /// row by row, each value as a field element in 8 little-endian bytes,
+    /// the columns of a row ordered by their polynomial ID.
+    /// Fails if the witness does not contain all columns and rows.
+    pub fn to_binary(&self, analyzed: &Analyzed) -> Result<Vec<u8>, String> {
+        let names = column_names(analyzed, PolynomialType::Committed);
+        let columns = self
+            .columns
+            .iter()
+            .map(|(name, values)| (name.as_str(), values))
+            .collect::<HashMap<_, _>>();
+        let columns = names
+            .iter()
+            .map(|name| {
+                columns
+                    .get(name)
+                    .copied()
+                    .ok_or_else(|| format!("Witness column {name} is missing."))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let degree = self.row_count();
+        if self.first_row != 0
+            || names
+                .first()
+                .is_some_and(|name| degree != analyzed.definitions[*name].0.degree)
+        {
+            return Err("The witness does not contain all rows.".to_string());
+        }
+        Ok((0..degree as usize)
+            .flat_map(|row| columns.iter().flat_map(move |c| to_le_bytes(&c[row])))
+            .collect())
+    }
+
+    /// Reads all columns of a column file.
+    pub fn from_column_file(file: &mut ColumnFile) -> Result<Self, String> {
+        Ok(Witness {
+            first_row: 0,
+            columns: file
+                .names()
+                .to_vec()
+                .into_iter()
+                .map(|name| {
+                    let values = file.column(&name)?;
+                    Ok((name, values))
+                })
+                .collect::<Result<_, String>>()?,
+        })
+    }
+
+    /// Writes the witness as a column file, which always starts at row zero.
+    pub fn write_column_file(&self, file: &mut impl Write) -> std::io::Result<()> {
+        assert_eq!(self.first_row, 0, "Column files have to start at row zero.");
+        let columns = self
+            .columns
+            .iter()
+            .map(|(name, values)| (name.as_str(), values.clone()))
+            .collect::<Vec<_>>();
+        column_file::write_columns(file, self.row_count(), &columns)
+    }
+
+    /// @returns the witness restricted to the given columns (all if `None`)
+    /// and the given rows (all if `None`).
+    pub fn select(
+        &self,
+        names: Option<&[String]>,
+        rows: Option<Range<DegreeType>>,
+    ) -> Result<Self, String> {
+        let available = self.first_row..self.first_row + self.row_count();
+        let rows = rows.unwrap_or_else(|| available.clone());
+        if rows.start > rows.end || rows.start < available.start || rows.end > available.end {
+            return Err(format!(
+                "Rows {rows:?} are not in the witness (rows {available:?})."
+            ));
+        }
+        let range = (rows.start - self.first_row) as usize..(rows.end - self.first_row) as usize;
+        let columns = match names {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    self.columns
+                        .iter()
+                        .find(|(n, _)| n == name)
+                        .ok_or_else(|| format!("Witness column {name} not found."))
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => self.columns.iter().collect(),
+        };
+        Ok(Witness {
+            first_row: rows.start,
+            columns: columns
+                .into_iter()
+                .map(|(name, values)| (name.clone(), values[range.clone()].to_vec()))
+                .collect(),
+        })
+    }
+
+    /// Formats the witness as csv with a header line of column names,
+    /// the first column contains the row number.
+    pub fn to_csv(&self) -> String {
+        let mut out = std::iter::once("row")
+            .chain(self.columns.iter().map(|(name, _)| name.as_str()))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push('\n');
+        for i in 0..self.row_count() {
+            let row = std::iter::once((self.first_row + i).to_string())
+                .chain(
+                    self.columns
+                        .iter()
+                        .map(|(_, values)| format_number(&values[i as usize])),
+                )
+                .collect::<Vec<_>>()
+                .join(",");
+            out.push_str(&row);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Parses the csv format produced by `to_csv`.
+    pub fn from_csv(contents: &str) -> Result<Self, String> {
+        let mut lines = contents
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split(',').map(|v| v.trim()).collect::<Vec<_>>()))
+            .filter(|(_, line)| line.iter().any(|v| !v.is_empty()));
+        let (_, header) = lines.next().ok_or("Empty witness csv file.")?;
+        if header[0] != "row" {
+            return Err("Expected the first column to be \"row\".".to_string());
+        }
+        let mut columns = header[1..]
+            .iter()
+            .map(|name| (name.to_string(), vec![]))
+            .collect::<Vec<_>>();
+        let mut first_row = None;
+        for (i, (line_number, line)) in lines.enumerate() {
+            if line.len() != header.len() {
+                return Err(format!(
+                    "Line {line_number} has {} values instead of {}.",
+                    line.len(),
+                    header.len()
+                ));
+            }
+            let row: DegreeType = line[0]
+                .parse()
+                .map_err(|_| format!("Invalid row number {} in line {line_number}.", line[0]))?;
+            let first_row = *first_row.get_or_insert(row);
+            if row != first_row + i as DegreeType {
+                return Err(format!(
+                    "Rows have to be contiguous, found row {row} in line {line_number}."
+                ));
+            }
+            for ((name, values), v) in columns.iter_mut().zip(&line[1..]) {
+                values.push(parse_value(v).map_err(|e| format!("Column {name}, row {row}: {e}"))?);
+            }
+        }
+        Ok(Witness {
+            first_row: first_row.unwrap_or_default(),
+            columns,
+        })
+    }
+
+    /// Formats the witness as json, the values are stored as strings.
+    pub fn to_json(&self) -> JsonValue {
+        object! {
+            firstRow: self.first_row,
+            columns: self.columns.iter().map(|(name, values)| object! {
+                name: name.as_str(),
+                values: values.iter().map(format_number).collect::<Vec<_>>(),
+            }).collect::<Vec<_>>(),
+        }
+    }
+
+    /// Parses the json format produced by `to_json`.
+    pub fn from_json(json: &JsonValue) -> Result<Self, String> {
+        let first_row = match &json["firstRow"] {
+            JsonValue::Null => 0,
+            value => value
+                .as_u64()
+                .ok_or_else(|| format!("Invalid firstRow {value}."))?,
+        };
+        if !json["columns"].is_array() {
+            return Err("Expected an array of columns.".to_string());
+        }
+        let columns = json["columns"]
+            .members()
+            .map(|column| {
+                let name = column["name"]
+                    .as_str()
+                    .ok_or("Expected a name for each column.")?;
+                let values = column["values"]
+                    .members()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        v.as_str()
+                            .ok_or_else(|| format!("Expected a string but found {v}."))
+                            .and_then(parse_value)
+                            .map_err(|e| {
+                                format!("Column {name}, row {}: {e}", first_row + i as u64)
+                            })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((name.to_string(), values))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if let Some((name, values)) = columns
+            .iter()
+            .find(|(_, values)| values.len() != columns[0].1.len())
+        {
+            return Err(format!(
+                "Column {name} has {} values but {} has {}.",
+                values.len(),
+                columns[0].0,
+                columns[0].1.len()
+            ));
+        }
+        Ok(Witness { first_row, columns })
+    }
+
+    /// @returns the number of rows, which is the same for all columns.
+    pub fn row_count(&self) -> DegreeType {
+        let count = self
+            .columns
+            .first()
+            .map(|(_, values)| values.len())
+            .unwrap_or_default();
+        assert!(self.columns.iter().all(|(_, values)| values.len() == count));
+        count as DegreeType
+    }
+}
+
+/// Parses a value in the csv or json format as a field element,
+/// so that e.g. `-1` is read as the same value as `p - 1`.
+fn parse_value(v: &str) -> Result<AbstractNumberType, String> {
+    parse_number(v)
+        .map(|n| to_field_element(&n))
+        .ok_or_else(|| {
+            format!(
+                "Invalid value \"{v}\", expected a decimal or hexadecimal (0x) number. \
+            Values are read as field elements, so -1 is the same as p - 1."
+            )
+        })
+}
+
+/// @returns the names of the committed or constant polynomials, in the order of their IDs.
+pub fn column_names(analyzed: &Analyzed, poly_type: PolynomialType) -> Vec<&str> {
+    let polys = match poly_type {
+        PolynomialType::Committed => analyzed.committed_polys_in_source_order(),
+        PolynomialType::Constant => analyzed.constant_polys_in_source_order(),
+        PolynomialType::Intermediate => panic!("Intermediate polynomials are not stored."),
+    };
+    polys
+        .into_iter()
+        .map(|(poly, _)| {
+            assert!(poly.length.is_none(), "Arrays not implemented.");
+            poly.absolute_name.as_str()
+        })
+        .collect()
+}
+
+/// Reads the columns with the given names from the binary layout of pilcom
+/// (see `Witness::to_binary`), which works for fixed and witness columns alike.
+pub fn read_binary_columns<'a>(
+    names: &[&'a str],
+    contents: &[u8],
+) -> Result<Vec<(&'a str, Vec<AbstractNumberType>)>, String> {
+    if !contents.len().is_multiple_of(names.len() * 8) {
+        return Err(format!(
+            "The size of the file does not match the number of columns ({}).",
+            names.len()
+        ));
+    }
+    let mut columns = names.iter().map(|name| (*name, vec![])).collect::<Vec<_>>();
+    for (i, value) in contents.chunks(8).enumerate() {
+        columns[i % names.len()].1.push(from_le_bytes(value));
+    }
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analyzer::analyze_string;
+
+    use super::*;
+
+    fn witness() -> Witness {
+        Witness {
+            first_row: 0,
+            columns: vec![
+                (
+                    "F.x".to_string(),
+                    vec![1.into(), 2.into(), 3.into(), (-1).into()],
+                ),
+                (
+                    "F.y".to_string(),
+                    vec![4.into(), 5.into(), 6.into(), 7.into()],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn csv() {
+        let selected = witness()
+            .select(Some(&["F.y".to_string(), "F.x".to_string()]), Some(2..4))
+            .unwrap();
+        let csv = selected.to_csv();
+        assert_eq!(csv, "row,F.y,F.x\n2,6,3\n3,7,-1\n");
+        let mut expected = selected;
+        expected.columns[1].1[1] = to_field_element(&(-1).into());
+        assert_eq!(Witness::from_csv(&csv), Ok(expected));
+    }
+
+    #[test]
+    fn invalid_csv() {
+        assert_eq!(
+            Witness::from_csv("row,F.x\n0,1\n1,x\n"),
+            Err(
+                "Column F.x, row 1: Invalid value \"x\", expected a decimal or hexadecimal (0x) \
+                number. Values are read as field elements, so -1 is the same as p - 1."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            Witness::from_csv("row,F.x\n0,1\n2,1\n"),
+            Err("Rows have to be contiguous, found row 2 in line 3.".to_string())
+        );
+        assert_eq!(
+            Witness::from_csv("row,F.x\n0,1,2\n"),
+            Err("Line 2 has 3 values instead of 2.".to_string())
+        );
+        assert!(Witness::from_csv("").is_err());
+        assert!(Witness::from_csv("x,F.x\n").is_err());
+    }
+
+    #[test]
+    fn invalid_selection() {
+        assert_eq!(
+            witness().select(Some(&["F.z".to_string()]), None),
+            Err("Witness column F.z not found.".to_string())
+        );
+        assert_eq!(
+            witness().select(None, Some(2..5)),
+            Err("Rows 2..5 are not in the witness (rows 0..4).".to_string())
+        );
+    }
+
+    #[test]
+    fn json() {
+        let json = witness().to_json();
+        assert_eq!(json["columns"][0]["values"][3], "-1");
+        let mut expected = witness();
+        expected.columns[0].1[3] = to_field_element(&(-1).into());
+        assert_eq!(Witness::from_json(&json), Ok(expected));
+        let mut invalid = json.clone();
+        invalid["columns"][1]["values"][2] = 6.into();
+        assert_eq!(
+            Witness::from_json(&invalid),
+            Err("Column F.y, row 2: Expected a string but found 6.".to_string())
+        );
+        let mut invalid = json;
+        invalid["columns"][1]["values"].pop();
+        assert_eq!(
+            Witness::from_json(&invalid),
+            Err("Column F.y has 3 values but F.x has 4.".to_string())
+        );
+    }
+
+    #[test]
+    fn binary() {
+        let analyzed = analyze_string("namespace F(4); pol commit y, x;").unwrap();
+        let binary = witness().to_binary(&analyzed).unwrap();
+        assert_eq!(binary.len(), 4 * 2 * 8);
+        // The columns are ordered as declared.
+        assert_eq!(binary[..8], to_le_bytes(&4.into()));
+        let mut expected = witness();
+        expected.columns.reverse();
+        expected.columns[1].1[3] = from_le_bytes(&to_le_bytes(&(-1).into()));
+        assert_eq!(Witness::from_binary(&analyzed, &binary), Ok(expected));
+        assert!(Witness::from_binary(&analyzed, &binary[1..]).is_err());
+    }
+
+    #[test]
+    fn binary_partial() {
+        let analyzed = analyze_string("namespace F(4); pol commit x, y;").unwrap();
+        assert_eq!(
+            witness()
+                .select(None, Some(1..4))
+                .unwrap()
+                .to_binary(&analyzed),
+            Err("The witness does not contain all rows.".to_string())
+        );
+        let analyzed = analyze_string("namespace F(4); pol commit x, z;").unwrap();
+        assert_eq!(
+            witness().to_binary(&analyzed),
+            Err("Witness column F.z is missing.".to_string())
+        );
+    }
+}
@@ -0,0 +1,44 @@
+// Verfies that a sum in the input has been computed properly,
+// adding each input in a function.
+// Input: sum, cnt, x_1, x_2, ..., x_cnt
+
+reg pc[@pc];
+reg X[<=];
+reg A;
+reg CNT;
+// Return address, calls cannot be nested.
+reg RA;
+
+pil{
+    col witness XInv;
+    col witness XIsZero;
+    XIsZero  = 1 - X * XInv;
+    XIsZero * X = 0;
+    XIsZero * (1 - XIsZero) = 0;
+}
+
+instr jmpz <=X= c, l: label { pc' = XIsZero * l + (1 - XIsZero) * (pc + 1) }
+instr jmp l: label { pc' = l }
+instr call l: label { RA' = pc + 1, pc' = l }
+instr ret { pc' = RA }
+instr assert_zero <=X= a { XIsZero = 1 }
+
+CNT <=X= ${ ("input", 1) };
+
+start::
+ jmpz CNT, end;
+ call add_input;
+ CNT <=X= CNT - 1;
+ jmp start;
+
+end::
+ A <=X= A - ${ ("input", 0) };
+ assert_zero A;
+ jmp exit;
+
+function add_input {
+ A <=X= A + ${ ("input", CNT + 1) };
+ ret;
+}
+
+exit::
@@ -1,6 +1,7 @@
 use std::{fs, path::Path, process::Command};
 
 use itertools::Itertools;
+use powdr::backend::BackendType;
 use powdr::compiler;
 use powdr::number::AbstractNumberType;
 
@@ -10,11 +11,14 @@ fn verify_pil(file_name: &str, query_callback: Option<fn(&str) -> Option<Abstrac
         .unwrap();
 
     let temp_dir = mktemp::Temp::new_dir().unwrap();
-    assert!(compiler::compile_pil(
+    compiler::compile_pil(
         &input_file,
+        &[],
         &temp_dir,
-        query_callback
-    ));
+        query_callback,
+        Some(BackendType::Mock),
+    )
+    .unwrap();
     verify(file_name, &temp_dir);
 }
 
@@ -23,7 +27,7 @@ fn verify_asm(file_name: &str, inputs: Vec<AbstractNumberType>) {
     let pil = powdr::asm_compiler::compile(Some(file_name), &contents).unwrap();
     let pil_file_name = "asm.pil";
     let temp_dir = mktemp::Temp::new_dir().unwrap();
-    assert!(compiler::compile_pil_ast(
+    compiler::compile_pil_ast(
         &pil,
         pil_file_name,
         &temp_dir,
@@ -42,8 +46,9 @@ fn verify_asm(file_name: &str, inputs: Vec<AbstractNumberType>) {
             }
             None
         }),
-        false
-    ));
+        Some(BackendType::Mock),
+    )
+    .unwrap();
     verify(pil_file_name, &temp_dir);
 }
 
@@ -134,6 +139,14 @@ fn simple_sum_asm() {
     );
 }
 
+#[test]
+fn functions_asm() {
+    verify_asm(
+        "functions.asm",
+        [16, 4, 1, 2, 8, 5].iter().map(|&x| x.into()).collect(),
+    );
+}
+
 #[test]
 fn palindrome() {
     verify_asm(
@@ -146,3 +159,72 @@ fn palindrome() {
 fn test_mem_read_write() {
     verify_asm("mem_read_write.asm", Default::default());
 }
+
+#[test]
+fn fixed_columns_standalone() {
+    let analyzed = powdr::analyzer::analyze(Path::new("./tests/fixed_from_file.pil"), &[]).unwrap();
+    let (columns, degree) = powdr::constant_evaluator::generate_values_by_name(&analyzed);
+    assert_eq!(degree, 8);
+    let names = columns.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    assert_eq!(names, vec!["T.FROM_CSV", "T.DOUBLED"]);
+    for (_, values) in &columns {
+        assert_eq!(values.len(), 8);
+    }
+    assert_eq!(columns[1].1[3], 32.into());
+}
+
+/// Compares the witness with `tests/snapshots/<file_name>.csv`,
+/// run with UPDATE_SNAPSHOTS=1 to update the snapshots.
+fn check_snapshot(file_name: &str, inputs: &[i32], degree: u64, columns: Option<&[&str]>) {
+    let inputs = inputs.iter().map(|&x| x.into()).collect::<Vec<_>>();
+    if let Err(e) = powdr::snapshot::check(
+        Path::new(&format!("./tests/{file_name}")),
+        &inputs,
+        degree,
+        columns,
+        Path::new(&format!("./tests/snapshots/{file_name}.csv")),
+    ) {
+        panic!("{e}");
+    }
+}
+
+#[test]
+fn snapshot_fibonacci() {
+    check_snapshot("fibonacci.pil", &[], 16, None);
+}
+
+#[test]
+fn snapshot_witness_lookup() {
+    check_snapshot(
+        "witness_lookup.pil",
+        &[3, 5, 2, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7],
+        16,
+        None,
+    );
+}
+
+#[test]
+fn snapshot_simple_sum_asm() {
+    check_snapshot(
+        "simple_sum.asm",
+        &[16, 4, 1, 2, 8, 5],
+        32,
+        Some(&["Assembly.pc", "Assembly.A", "Assembly.CNT"]),
+    );
+}
+
+#[test]
+fn snapshot_palindrome_asm() {
+    check_snapshot(
+        "palindrome.asm",
+        &[7, 1, 7, 3, 9, 3, 7, 1],
+        64,
+        Some(&[
+            "Assembly.pc",
+            "Assembly.A",
+            "Assembly.B",
+            "Assembly.I",
+            "Assembly.CNT",
+        ]),
+    );
+}